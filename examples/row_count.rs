@@ -0,0 +1,32 @@
+//! Prints the number of rows of a Parquet file.
+//!
+//! ```sh
+//! cargo run --example row_count -- data.parquet
+//! ```
+use nu_plugin_from_parquet::{from_parquet_bytes, FromParquetOpts};
+use nu_source::Tag;
+use std::process::exit;
+
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("usage: row_count <file.parquet>");
+            exit(2);
+        }
+    };
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("failed to read {}: {}", path, e);
+            exit(1);
+        }
+    };
+    match from_parquet_bytes(bytes, Tag::unknown(), &FromParquetOpts::default()) {
+        Ok(table) => println!("{}", table.table_entries().count()),
+        Err(e) => {
+            eprintln!("failed to read {}: {:?}", path, e);
+            exit(1);
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_plugin_from_parquet::from_arrow::from_arrow_bytes;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, Value};
+
+struct FromArrow {
+    input: BinaryInput,
+    name_tag: Tag
+}
+
+impl FromArrow {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for FromArrow {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("from arrow")
+            .desc("Convert from Arrow IPC file or stream binary into table")
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = from_arrow_bytes(bytes, tag)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut FromArrow::new());
+}
@@ -0,0 +1,71 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::bloom::parquet_bloom_check;
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, SyntaxShape, Value};
+
+struct ParquetBloomCheck {
+    input: BinaryInput,
+    column: String,
+    value: String,
+    name_tag: Tag
+}
+
+impl ParquetBloomCheck {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            column: String::new(),
+            value: String::new(),
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetBloomCheck {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet bloom-check")
+            .desc("Check the Bloom filters of .parquet binary for whether each row group may contain a value")
+            .required_named(
+                "column",
+                SyntaxShape::String,
+                "leaf column whose Bloom filters are checked, as a dotted path",
+                None,
+            )
+            .required_named(
+                "value",
+                SyntaxShape::String,
+                "value to look up, parsed as the physical type of the column",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        if let Some(column) = call_info.args.get("column") {
+            self.column = column.as_string()?;
+        }
+        if let Some(value) = call_info.args.get("value") {
+            self.value = value.as_string()?;
+        }
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = parquet_bloom_check(bytes, tag, &self.column, &self.value)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetBloomCheck::new());
+}
@@ -0,0 +1,49 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_plugin_from_parquet::meta::parquet_count;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, Value};
+
+struct ParquetCount {
+    input: BinaryInput,
+    name_tag: Tag
+}
+
+impl ParquetCount {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetCount {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet count")
+            .desc("Show the row and row group counts of .parquet binary without reading its rows")
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = parquet_count(bytes, tag)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetCount::new());
+}
@@ -0,0 +1,115 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::diff::{parquet_diff, DEFAULT_MAX_DIFF_ROWS};
+use nu_plugin_from_parquet::input::{read_file, BinaryInput};
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, SyntaxShape, Value};
+use std::path::PathBuf;
+
+struct ParquetDiff {
+    input: BinaryInput,
+    a: Option<(PathBuf, Tag)>,
+    b: Option<(PathBuf, Tag)>,
+    key: Option<String>,
+    max_rows: usize,
+    name_tag: Tag
+}
+
+impl ParquetDiff {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            a: None,
+            b: None,
+            key: None,
+            max_rows: DEFAULT_MAX_DIFF_ROWS,
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetDiff {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet diff")
+            .desc("Compare the schemas, row counts and rows of two .parquet files")
+            .named(
+                "a",
+                SyntaxShape::FilePath,
+                "the first Parquet file, instead of binary from the pipeline",
+                None,
+            )
+            .named(
+                "b",
+                SyntaxShape::FilePath,
+                "the Parquet file to compare with",
+                None,
+            )
+            .named(
+                "key",
+                SyntaxShape::String,
+                "match rows by the given column and list those whose values changed",
+                None,
+            )
+            .named(
+                "max-diff-rows",
+                SyntaxShape::Int,
+                "list at most this many rows in only_in_a, only_in_b and changed (default 10)",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        if let Some(a) = call_info.args.get("a") {
+            self.a = Some((a.as_path()?, a.tag.clone()));
+        }
+        match call_info.args.get("b") {
+            Some(b) => self.b = Some((b.as_path()?, b.tag.clone())),
+            None => {
+                return Err(ShellError::labeled_error(
+                    "Missing --b",
+                    "pass the Parquet file to compare with as --b",
+                    &call_info.name_tag,
+                ))
+            }
+        }
+        if let Some(key) = call_info.args.get("key") {
+            self.key = Some(key.as_string()?);
+        }
+        if let Some(max_rows) = call_info.args.get("max-diff-rows") {
+            self.max_rows = max_rows.as_u64()? as usize;
+        }
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let a = match &self.a {
+            Some(_) if !self.input.is_empty() => {
+                return Err(ShellError::labeled_error(
+                    "Both --a and pipeline input given",
+                    "compare either the --a file or the piped binary",
+                    &self.name_tag,
+                ))
+            }
+            Some((path, tag)) => read_file(path, tag)?,
+            None => self.input.take(&self.name_tag).0,
+        };
+        let b = match &self.b {
+            Some((path, tag)) => read_file(path, tag)?,
+            None => Vec::new(),
+        };
+        let value = parquet_diff(a, b, self.name_tag.clone(), self.key.as_deref(), self.max_rows)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetDiff::new());
+}
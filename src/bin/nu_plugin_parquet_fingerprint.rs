@@ -0,0 +1,49 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::fingerprint::parquet_fingerprint;
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, Value};
+
+struct ParquetFingerprint {
+    input: BinaryInput,
+    name_tag: Tag
+}
+
+impl ParquetFingerprint {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetFingerprint {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet fingerprint")
+            .desc("Hash the schema of .parquet binary, to check that files share a schema")
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = parquet_fingerprint(bytes, tag)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetFingerprint::new());
+}
@@ -0,0 +1,132 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::read_file;
+use nu_plugin_from_parquet::merge::{parquet_merge, MergeMode};
+use nu_protocol::{
+    CallInfo, Primitive, ReturnSuccess, ReturnValue, Signature, SyntaxShape, UntaggedValue, Value,
+};
+use std::path::PathBuf;
+
+const MAGIC: &[u8] = b"PAR1";
+
+struct ParquetMerge {
+    files: Vec<Vec<u8>>,
+    paths: Vec<(PathBuf, Tag)>,
+    mode: MergeMode,
+    name_tag: Tag
+}
+
+impl ParquetMerge {
+    fn new() -> Self {
+        Self {
+            files: Vec::new(),
+            paths: Vec::new(),
+            mode: MergeMode::ReEncode,
+            name_tag: Tag::unknown()
+        }
+    }
+
+    /// Large binaries can reach the plugin in several chunks, a chunk starting with
+    /// the magic after a file that ended with it starts the next file.
+    fn push_binary(&mut self, bytes: Vec<u8>) {
+        match self.files.last_mut() {
+            Some(file) if !(file.ends_with(MAGIC) && bytes.starts_with(MAGIC)) => file.extend(bytes),
+            _ => self.files.push(bytes),
+        }
+    }
+}
+
+impl Plugin for ParquetMerge {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet merge")
+            .desc("Merge several .parquet binaries sharing a schema into one")
+            .named(
+                "files",
+                SyntaxShape::Table,
+                "merge the given Parquet files instead of binaries from the pipeline, eg [a.parquet b.parquet]",
+                None,
+            )
+            .switch(
+                "re-encode",
+                "decode and encode every column chunk again (default)",
+                None,
+            )
+            .switch(
+                "raw-copy",
+                "copy the compressed column chunks as they are, without decoding them",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        if let Some(files) = call_info.args.get("files") {
+            self.paths = match &files.value {
+                UntaggedValue::Table(values) => values.iter()
+                    .map(|v| Ok((v.as_path()?, v.tag.clone())))
+                    .collect::<Result<_, ShellError>>()?,
+                _ => vec![(files.as_path()?, files.tag.clone())],
+            };
+        }
+        match (call_info.args.has("re-encode"), call_info.args.has("raw-copy")) {
+            (true, true) => {
+                return Err(ShellError::labeled_error(
+                    "Conflicting merge flags",
+                    "use either --re-encode or --raw-copy",
+                    &call_info.name_tag,
+                ))
+            }
+            (_, true) => self.mode = MergeMode::RawCopy,
+            _ => self.mode = MergeMode::ReEncode,
+        }
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        let values = match input.value {
+            UntaggedValue::Table(values) => values,
+            _ => vec![input],
+        };
+        for value in values {
+            match value {
+                Value {
+                    value: UntaggedValue::Primitive(Primitive::Binary(b)),
+                    ..
+                } => self.push_binary(b),
+                Value { tag, .. } => {
+                    return Err(ShellError::labeled_error_with_secondary(
+                        "Expected binary from pipeline",
+                        "requires binary input",
+                        self.name_tag.clone(),
+                        "value originates from here",
+                        tag,
+                    ))
+                }
+            }
+        }
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        if !self.paths.is_empty() && !self.files.is_empty() {
+            return Err(ShellError::labeled_error(
+                "Both --files and pipeline input given",
+                "merge either the files or the piped binaries",
+                &self.name_tag,
+            ));
+        }
+        let mut files = std::mem::take(&mut self.files);
+        for (path, tag) in &self.paths {
+            files.push(read_file(path, tag)?);
+        }
+        let value = parquet_merge(files, self.name_tag.clone(), self.mode)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetMerge::new());
+}
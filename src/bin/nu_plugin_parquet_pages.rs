@@ -0,0 +1,71 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::pages::parquet_pages;
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, SyntaxShape, Value};
+
+struct ParquetPages {
+    input: BinaryInput,
+    column: String,
+    row_group: usize,
+    name_tag: Tag
+}
+
+impl ParquetPages {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            column: String::new(),
+            row_group: 0,
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetPages {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet pages")
+            .desc("List the page headers of a column chunk of .parquet binary")
+            .required_named(
+                "column",
+                SyntaxShape::String,
+                "leaf column whose pages are listed, as a dotted path",
+                None,
+            )
+            .required_named(
+                "row-group",
+                SyntaxShape::Int,
+                "index of the row group holding the column chunk",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        if let Some(column) = call_info.args.get("column") {
+            self.column = column.as_string()?;
+        }
+        if let Some(row_group) = call_info.args.get("row-group") {
+            self.row_group = row_group.as_u64()? as usize;
+        }
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = parquet_pages(bytes, tag, &self.column, self.row_group)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetPages::new());
+}
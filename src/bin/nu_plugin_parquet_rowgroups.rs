@@ -0,0 +1,49 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_plugin_from_parquet::rowgroups::parquet_rowgroups;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, Value};
+
+struct ParquetRowGroups {
+    input: BinaryInput,
+    name_tag: Tag
+}
+
+impl ParquetRowGroups {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetRowGroups {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet rowgroups")
+            .desc("Show the row groups of .parquet binary and their column chunks without reading any rows")
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = parquet_rowgroups(bytes, tag)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetRowGroups::new());
+}
@@ -0,0 +1,61 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_plugin_from_parquet::schema::{parquet_schema, parquet_schema_json};
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, Value};
+
+struct ParquetSchema {
+    input: BinaryInput,
+    schema_json: bool,
+    name_tag: Tag
+}
+
+impl ParquetSchema {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            schema_json: false,
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetSchema {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet schema")
+            .desc("Show the schema of .parquet binary without reading its rows")
+            .switch(
+                "schema-json",
+                "output the schema converted to Arrow, as an Arrow JSON schema string",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        self.schema_json = call_info.args.has("schema-json");
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = if self.schema_json {
+            parquet_schema_json(bytes, tag)?
+        } else {
+            parquet_schema(bytes, tag)?
+        };
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetSchema::new());
+}
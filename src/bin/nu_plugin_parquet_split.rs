@@ -0,0 +1,74 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_plugin_from_parquet::split::parquet_split;
+use nu_plugin_from_parquet::to_parquet::ToParquetOpts;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, SyntaxShape, Value};
+use std::path::PathBuf;
+
+struct ParquetSplit {
+    input: BinaryInput,
+    by: String,
+    output_dir: PathBuf,
+    name_tag: Tag
+}
+
+impl ParquetSplit {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            by: String::new(),
+            output_dir: PathBuf::new(),
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetSplit {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet split")
+            .desc("Split .parquet binary into Hive style partitions, one file per value of a column")
+            .required_named(
+                "by",
+                SyntaxShape::String,
+                "top-level column whose values name the partitions",
+                None,
+            )
+            .required_named(
+                "output-dir",
+                SyntaxShape::FilePath,
+                "directory the <column>=<value>/part-0.parquet files are written to",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        if let Some(by) = call_info.args.get("by") {
+            self.by = by.as_string()?;
+        }
+        if let Some(output_dir) = call_info.args.get("output-dir") {
+            self.output_dir = output_dir.as_path()?;
+        }
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let opts = ToParquetOpts::default();
+        let value = parquet_split(bytes, tag, &self.by, &self.output_dir, &opts)?;
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetSplit::new());
+}
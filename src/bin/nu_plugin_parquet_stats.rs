@@ -0,0 +1,61 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_plugin_from_parquet::stats::{parquet_null_stats, parquet_stats};
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, Value};
+
+struct ParquetStats {
+    input: BinaryInput,
+    null_stats: bool,
+    name_tag: Tag
+}
+
+impl ParquetStats {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            null_stats: false,
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetStats {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet stats")
+            .desc("Show the column statistics of each row group of .parquet binary")
+            .switch(
+                "null-stats",
+                "sum the null counts of each column over the whole file instead",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        self.null_stats = call_info.args.has("null-stats");
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = if self.null_stats {
+            parquet_null_stats(bytes, tag)?
+        } else {
+            parquet_stats(bytes, tag)?
+        };
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetStats::new());
+}
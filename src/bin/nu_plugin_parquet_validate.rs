@@ -0,0 +1,57 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::input::BinaryInput;
+use nu_plugin_from_parquet::validate::parquet_validate;
+use nu_protocol::{CallInfo, ReturnSuccess, ReturnValue, Signature, Value};
+
+struct ParquetValidate {
+    input: BinaryInput,
+    check_crcs: bool,
+    name_tag: Tag
+}
+
+impl ParquetValidate {
+    fn new() -> Self {
+        Self {
+            input: BinaryInput::new(),
+            check_crcs: false,
+            name_tag: Tag::unknown()
+        }
+    }
+}
+
+impl Plugin for ParquetValidate {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("parquet validate")
+            .desc("Check the structure of .parquet binary from its footer without reading its rows")
+            .switch(
+                "check-crcs",
+                "also read every page header and verify the page CRC checksums that are present",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        self.check_crcs = call_info.args.has("check-crcs");
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        self.input.push(input, &self.name_tag)?;
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let (bytes, tag) = self.input.take(&self.name_tag);
+        let value = parquet_validate(bytes, tag, self.check_crcs);
+        Ok(vec![ReturnSuccess::value(value)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ParquetValidate::new());
+}
@@ -0,0 +1,272 @@
+use nu_source::{Tag};
+
+use nu_errors::ShellError;
+use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::split::{to_parquet_partitioned, ExistingFiles};
+use nu_plugin_from_parquet::to_parquet::{to_parquet, CompressionChoice, StatisticsLevel, ToParquetOpts};
+use nu_protocol::{
+    CallInfo, ReturnSuccess, ReturnValue, Signature, SyntaxShape, UntaggedValue, Value,
+};
+use std::path::PathBuf;
+
+struct ToParquet {
+    rows: Vec<Value>,
+    name_tag: Tag,
+    opts: ToParquetOpts,
+    partition_by: Option<String>,
+    output_dir: Option<PathBuf>,
+    existing: ExistingFiles
+}
+
+impl ToParquet {
+    fn new() -> Self {
+        Self {
+            rows: Vec::new(),
+            name_tag: Tag::unknown(),
+            opts: ToParquetOpts::default(),
+            partition_by: None,
+            output_dir: None,
+            existing: ExistingFiles::Error
+        }
+    }
+}
+
+impl Plugin for ToParquet {
+    fn config(&mut self) -> Result<Signature, ShellError> {
+        Ok(Signature::build("to parquet")
+            .desc("Convert table into .parquet binary")
+            .named(
+                "compression",
+                SyntaxShape::String,
+                "compression codec: zstd (default), snappy, gzip, lz4 or uncompressed",
+                Some('c'),
+            )
+            .named(
+                "compression-level",
+                SyntaxShape::Int,
+                "compression level, only the defaults of 1 for zstd and 6 for gzip can be written",
+                None,
+            )
+            .named(
+                "row-group-size",
+                SyntaxShape::Int,
+                "most rows per row group (default 1000000), groups also end at about 128MB of values",
+                None,
+            )
+            .named(
+                "bloom-filter-columns",
+                SyntaxShape::Table,
+                "leaf columns to write a Bloom filter for in every row group",
+                None,
+            )
+            .named(
+                "bloom-filter-fpp",
+                SyntaxShape::Number,
+                "false-positive probability the Bloom filters are sized for (default 0.05)",
+                None,
+            )
+            .named(
+                "dict",
+                SyntaxShape::Table,
+                "turn dictionary encoding on or off per column, as column:on or column:off",
+                None,
+            )
+            .switch(
+                "no-dictionary",
+                "write no column with dictionary encoding, unless --dict turns it on",
+                None,
+            )
+            .named(
+                "statistics",
+                SyntaxShape::String,
+                "statistics to write: none, chunk (default) or page, page statistics let readers skip pages but grow every page header",
+                None,
+            )
+            .switch(
+                "column-index",
+                "write column and offset indexes, output a record of the binary and the index size",
+                None,
+            )
+            .named(
+                "partition-by",
+                SyntaxShape::String,
+                "write one file per value of this column to --output-dir, as <column>=<value>/part-0.parquet without the column",
+                None,
+            )
+            .named(
+                "output-dir",
+                SyntaxShape::FilePath,
+                "directory --partition-by writes the partitions to",
+                None,
+            )
+            .switch(
+                "overwrite",
+                "replace partition files that already exist",
+                None,
+            )
+            .switch(
+                "append",
+                "add the rows to partition files that already exist",
+                None,
+            )
+            .filter())
+    }
+
+    fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        if let Some(value) = call_info.args.get("compression") {
+            let name = value.as_string()?;
+            self.opts.compression = CompressionChoice::from_name(&name).ok_or_else(|| {
+                ShellError::labeled_error(
+                    "Unknown compression codec",
+                    "expected one of zstd, snappy, gzip, lz4 or uncompressed",
+                    &value.tag,
+                )
+            })?;
+        }
+        if let Some(value) = call_info.args.get("compression-level") {
+            let level = value.as_i64()?;
+            if self.opts.compression.takes_level() {
+                self.opts.compression = self.opts.compression.with_level(level).ok_or_else(|| {
+                    ShellError::labeled_error(
+                        "Compression level out of range",
+                        "zstd levels are 1 to 22 and gzip levels 0 to 9",
+                        &value.tag,
+                    )
+                })?;
+                self.opts.compression.check_level(&value.tag)?;
+            } else {
+                eprintln!("warning: --compression-level only applies to zstd and gzip, it is ignored");
+            }
+        }
+        if let Some(size) = call_info.args.get("row-group-size") {
+            self.opts.row_group_size = size.as_u64()? as usize;
+            if self.opts.row_group_size == 0 {
+                return Err(ShellError::labeled_error(
+                    "Row group size must be at least 1",
+                    "row group size of 0",
+                    &size.tag,
+                ));
+            }
+        }
+        if let Some(columns) = call_info.args.get("bloom-filter-columns") {
+            self.opts.bloom_filter_columns = match &columns.value {
+                UntaggedValue::Table(values) => values.iter().map(|v| v.as_string()).collect::<Result<_, _>>()?,
+                _ => vec![columns.as_string()?],
+            };
+        }
+        if let Some(fpp) = call_info.args.get("bloom-filter-fpp") {
+            self.opts.bloom_filter_fpp = match fpp.convert_to_string().parse::<f64>() {
+                Ok(p) if p > 0.0 && p < 1.0 => p,
+                _ => {
+                    return Err(ShellError::labeled_error(
+                        "Bloom filter false-positive probability out of range",
+                        "expected a number between 0 and 1, such as 0.01",
+                        &fpp.tag,
+                    ))
+                }
+            };
+            if self.opts.bloom_filter_columns.is_empty() {
+                eprintln!("warning: --bloom-filter-fpp only applies with --bloom-filter-columns, it is ignored");
+            }
+        }
+        if let Some(value) = call_info.args.get("statistics") {
+            let name = value.as_string()?;
+            self.opts.statistics = StatisticsLevel::from_name(&name).ok_or_else(|| {
+                ShellError::labeled_error(
+                    "Unknown statistics level",
+                    "expected one of none, chunk or page",
+                    &value.tag,
+                )
+            })?;
+        }
+        self.opts.column_index = call_info.args.has("column-index");
+        self.opts.dictionary = !call_info.args.has("no-dictionary");
+        if let Some(dict) = call_info.args.get("dict") {
+            let entries = match &dict.value {
+                UntaggedValue::Table(values) => values.iter().map(|v| Ok((v.as_string()?, v.tag.clone()))).collect::<Result<Vec<_>, ShellError>>()?,
+                _ => vec![(dict.as_string()?, dict.tag.clone())],
+            };
+            for (entry, tag) in entries {
+                let setting = entry.rsplit_once(':').and_then(|(column, setting)| match setting.trim() {
+                    "on" | "true" => Some((column.trim().to_string(), true)),
+                    "off" | "false" => Some((column.trim().to_string(), false)),
+                    _ => None,
+                });
+                match setting {
+                    Some(setting) => self.opts.dictionary_columns.push(setting),
+                    None => {
+                        return Err(ShellError::labeled_error(
+                            "Invalid --dict setting",
+                            "expected column:on or column:off",
+                            &tag,
+                        ))
+                    }
+                }
+            }
+        }
+        if let Some(by) = call_info.args.get("partition-by") {
+            self.partition_by = Some(by.as_string()?);
+        }
+        if let Some(output_dir) = call_info.args.get("output-dir") {
+            self.output_dir = Some(output_dir.as_path()?);
+        }
+        match (&self.partition_by, &self.output_dir) {
+            (Some(_), None) => {
+                return Err(ShellError::labeled_error(
+                    "--partition-by needs --output-dir",
+                    "the partitions are written to files",
+                    &call_info.name_tag,
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(ShellError::labeled_error(
+                    "--output-dir needs --partition-by",
+                    "only partitions are written to a directory",
+                    &call_info.name_tag,
+                ))
+            }
+            _ => {}
+        }
+        self.existing = match (call_info.args.has("overwrite"), call_info.args.has("append")) {
+            (true, true) => {
+                return Err(ShellError::labeled_error(
+                    "--overwrite and --append cannot be combined",
+                    "choose what happens to existing partition files",
+                    &call_info.name_tag,
+                ))
+            }
+            (true, false) => ExistingFiles::Overwrite,
+            (false, true) => ExistingFiles::Append,
+            (false, false) => ExistingFiles::Error,
+        };
+        if self.existing != ExistingFiles::Error && self.partition_by.is_none() {
+            eprintln!("warning: --overwrite and --append only apply with --partition-by, they are ignored");
+        }
+        self.name_tag = call_info.name_tag;
+        Ok(vec![])
+    }
+
+    fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
+        match input {
+            Value {
+                value: UntaggedValue::Table(rows),
+                ..
+            } => self.rows.extend(rows),
+            row => self.rows.push(row),
+        }
+        Ok(vec![])
+    }
+
+    fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
+        let rows = std::mem::take(&mut self.rows);
+        if let (Some(by), Some(output_dir)) = (&self.partition_by, &self.output_dir) {
+            let files = to_parquet_partitioned(rows, self.name_tag.clone(), by, output_dir, &self.opts, self.existing)?;
+            return Ok(vec![ReturnSuccess::value(files)]);
+        }
+        Ok(vec![ReturnSuccess::value(to_parquet(&rows, self.name_tag.clone(), &self.opts)?)])
+    }
+}
+
+fn main() {
+    serve_plugin(&mut ToParquet::new());
+}
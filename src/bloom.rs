@@ -0,0 +1,631 @@
+use parquet::basic::Type as PhysicalType;
+use parquet::column::reader::{ColumnReader, ColumnReaderImpl};
+use parquet::data_type::DataType;
+use parquet::file::reader::FileReader;
+use parquet::schema::types::ColumnDescriptor;
+use thrift::protocol::{
+    TCompactInputProtocol, TCompactOutputProtocol, TFieldIdentifier, TInputProtocol, TOutputProtocol,
+    TStructIdentifier, TType,
+};
+use nu_errors::ShellError;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use std::io::{Cursor, Read, Write};
+use crate::{open_reader, parquet_error};
+
+/// Salts of the split block Bloom filter, one per 32-bit word of a block.
+const SALT: [u32; 8] = [
+    0x47b6_137b, 0x4497_4d91, 0x8824_ad5b, 0xa2b7_289d, 0x7054_95c7, 0x2df1_424b, 0x9efc_4947, 0x5c6b_fb31,
+];
+/// Bytes in a block of the split block Bloom filter.
+const BLOCK_BYTES: usize = 32;
+
+/// False-positive probability of the Bloom filters written when `--bloom-filter-fpp` is
+/// not given.
+pub const DEFAULT_BLOOM_FILTER_FPP: f64 = 0.05;
+/// Largest bitset written for a column chunk, filters for more distinct values than
+/// fit are less selective than asked for.
+const MAX_BLOOM_FILTER_BYTES: usize = 128 * 1024 * 1024;
+/// Path of field ids from the footer root to the `ColumnMetaData` of a column chunk:
+/// `row_groups`, `columns`, `meta_data`.
+const COLUMN_META_DATA: [i16; 3] = [4, 1, 3];
+/// Values read per batch when hashing a column chunk.
+const BATCH_SIZE: usize = 1024;
+
+const PRIME_1: u64 = 0x9E37_79B1_85EB_CA87;
+const PRIME_2: u64 = 0xC2B2_AE3D_27D4_EB4F;
+const PRIME_3: u64 = 0x1656_67B1_9E37_79F9;
+const PRIME_4: u64 = 0x85EB_CA77_C2B2_AE63;
+const PRIME_5: u64 = 0x27D4_EB2F_1656_67C5;
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    let mut word = [0; 8];
+    word.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(word)
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    let mut word = [0; 4];
+    word.copy_from_slice(&bytes[..4]);
+    u32::from_le_bytes(word)
+}
+
+fn xxh64_round(acc: u64, input: u64) -> u64 {
+    acc.wrapping_add(input.wrapping_mul(PRIME_2)).rotate_left(31).wrapping_mul(PRIME_1)
+}
+
+fn xxh64_merge(acc: u64, value: u64) -> u64 {
+    (acc ^ xxh64_round(0, value)).wrapping_mul(PRIME_1).wrapping_add(PRIME_4)
+}
+
+/// XXH64 with a seed of 0, the hash Parquet Bloom filters are built with.
+fn xxh64(data: &[u8]) -> u64 {
+    let mut rest = data;
+    let mut hash = if data.len() >= 32 {
+        let mut v = [
+            PRIME_1.wrapping_add(PRIME_2),
+            PRIME_2,
+            0,
+            0u64.wrapping_sub(PRIME_1),
+        ];
+        while rest.len() >= 32 {
+            for (i, v) in v.iter_mut().enumerate() {
+                *v = xxh64_round(*v, read_u64(&rest[i * 8..]));
+            }
+            rest = &rest[32..];
+        }
+        let hash = v[0].rotate_left(1)
+            .wrapping_add(v[1].rotate_left(7))
+            .wrapping_add(v[2].rotate_left(12))
+            .wrapping_add(v[3].rotate_left(18));
+        v.iter().fold(hash, |hash, v| xxh64_merge(hash, *v))
+    } else {
+        PRIME_5
+    };
+    hash = hash.wrapping_add(data.len() as u64);
+    while rest.len() >= 8 {
+        hash ^= xxh64_round(0, read_u64(rest));
+        hash = hash.rotate_left(27).wrapping_mul(PRIME_1).wrapping_add(PRIME_4);
+        rest = &rest[8..];
+    }
+    if rest.len() >= 4 {
+        hash ^= u64::from(read_u32(rest)).wrapping_mul(PRIME_1);
+        hash = hash.rotate_left(23).wrapping_mul(PRIME_2).wrapping_add(PRIME_3);
+        rest = &rest[4..];
+    }
+    for byte in rest {
+        hash ^= u64::from(*byte).wrapping_mul(PRIME_5);
+        hash = hash.rotate_left(11).wrapping_mul(PRIME_1);
+    }
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(PRIME_2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(PRIME_3);
+    hash ^ (hash >> 32)
+}
+
+/// Whether the split block Bloom filter `bitset` may hold the value hashed to `hash`.
+fn may_contain(bitset: &[u8], hash: u64) -> bool {
+    let num_blocks = (bitset.len() / BLOCK_BYTES) as u64;
+    if num_blocks == 0 {
+        return true;
+    }
+    let (block, bits) = block_bits(num_blocks, hash);
+    let block = &bitset[block * BLOCK_BYTES..(block + 1) * BLOCK_BYTES];
+    bits.enumerate().all(|(i, bit)| read_u32(&block[i * 4..]) & (1 << bit) != 0)
+}
+
+/// Bitset bytes for `distinct` values at a false-positive probability of `fpp`: the
+/// optimal size for a split block filter, rounded up to a power of two.
+fn bitset_bytes(distinct: usize, fpp: f64) -> usize {
+    let bits = -8.0 * distinct as f64 / (1.0 - fpp.powf(1.0 / 8.0)).ln();
+    let bytes = (bits / 8.0).ceil() as usize;
+    bytes.clamp(BLOCK_BYTES, MAX_BLOOM_FILTER_BYTES).next_power_of_two().min(MAX_BLOOM_FILTER_BYTES)
+}
+
+/// Builds the split block Bloom filter of the distinct values hashed to `hashes`.
+fn build_bitset(hashes: &[u64], fpp: f64) -> Vec<u8> {
+    let mut bitset = vec![0u8; bitset_bytes(hashes.len(), fpp)];
+    let num_blocks = (bitset.len() / BLOCK_BYTES) as u64;
+    for hash in hashes {
+        let (block, bits) = block_bits(num_blocks, *hash);
+        for (i, bit) in bits.enumerate() {
+            let at = block * BLOCK_BYTES + i * 4;
+            let word = read_u32(&bitset[at..]) | (1 << bit);
+            bitset[at..at + 4].copy_from_slice(&word.to_le_bytes());
+        }
+    }
+    bitset
+}
+
+/// The block of `hash` and the bit it sets in each word of that block.
+fn block_bits(num_blocks: u64, hash: u64) -> (usize, impl Iterator<Item = u32>) {
+    let block = (((hash >> 32) * num_blocks) >> 32) as usize;
+    let key = hash as u32;
+    (block, SALT.iter().map(move |salt| key.wrapping_mul(*salt) >> 27))
+}
+
+/// The plain encoding of the literal in the column's physical type, the bytes its
+/// Bloom filter hashes.
+fn value_bytes(literal: &str, column: &ColumnDescriptor) -> Result<Vec<u8>, String> {
+    let invalid = |kind: &str| format!("'{}' is not {} value", literal, kind);
+    let bytes = match column.physical_type() {
+        PhysicalType::INT32 => literal.trim().parse::<i32>().map_err(|_| invalid("an INT32"))?.to_le_bytes().to_vec(),
+        PhysicalType::INT64 => literal.trim().parse::<i64>().map_err(|_| invalid("an INT64"))?.to_le_bytes().to_vec(),
+        PhysicalType::FLOAT => literal.trim().parse::<f32>().map_err(|_| invalid("a FLOAT"))?.to_le_bytes().to_vec(),
+        PhysicalType::DOUBLE => literal.trim().parse::<f64>().map_err(|_| invalid("a DOUBLE"))?.to_le_bytes().to_vec(),
+        PhysicalType::BYTE_ARRAY => literal.as_bytes().to_vec(),
+        PhysicalType::FIXED_LEN_BYTE_ARRAY if literal.len() == column.type_length() as usize => literal.as_bytes().to_vec(),
+        PhysicalType::FIXED_LEN_BYTE_ARRAY => return Err(format!("the column holds {} byte values", column.type_length())),
+        other => return Err(format!("{} columns have no Bloom filters", other)),
+    };
+    Ok(bytes)
+}
+
+/// Skips a value of type `ttype`. The `skip` of thrift 0.13 reads binary fields as UTF-8
+/// strings and fails on statistics holding other bytes.
+fn skip<R: Read>(protocol: &mut TCompactInputProtocol<R>, ttype: TType) -> thrift::Result<()> {
+    match ttype {
+        TType::String => protocol.read_bytes().map(|_| ()),
+        TType::Struct => read_struct(protocol, &mut |_, _| Ok(false)),
+        TType::List | TType::Set => {
+            let list = protocol.read_list_begin()?;
+            for _ in 0..list.size {
+                skip(protocol, list.element_type)?;
+            }
+            protocol.read_list_end()
+        }
+        TType::Map => {
+            let map = protocol.read_map_begin()?;
+            if let (Some(key), Some(value)) = (map.key_type, map.value_type) {
+                for _ in 0..map.size {
+                    skip(protocol, key)?;
+                    skip(protocol, value)?;
+                }
+            }
+            protocol.read_map_end()
+        }
+        other => protocol.skip(other),
+    }
+}
+
+/// Reads the fields of a struct, handing every one to `field`, which returns whether
+/// it read the value. Unread values are skipped.
+fn read_struct<R: Read>(
+    protocol: &mut TCompactInputProtocol<R>,
+    field: &mut dyn FnMut(&mut TCompactInputProtocol<R>, i16) -> thrift::Result<bool>,
+) -> thrift::Result<()> {
+    protocol.read_struct_begin()?;
+    loop {
+        let identifier = protocol.read_field_begin()?;
+        if identifier.field_type == TType::Stop {
+            break;
+        }
+        if !field(protocol, identifier.id.unwrap_or(0))? {
+            skip(protocol, identifier.field_type)?;
+        }
+        protocol.read_field_end()?;
+    }
+    protocol.read_struct_end()
+}
+
+fn read_list<R: Read>(
+    protocol: &mut TCompactInputProtocol<R>,
+    element: &mut dyn FnMut(&mut TCompactInputProtocol<R>) -> thrift::Result<()>,
+) -> thrift::Result<()> {
+    let list = protocol.read_list_begin()?;
+    for _ in 0..list.size {
+        element(protocol)?;
+    }
+    protocol.read_list_end()
+}
+
+/// The Bloom filter offset of every column chunk, by row group. parquet 3.0 and its
+/// thrift definitions predate the `bloom_filter_offset` field, so it is read by hand.
+fn bloom_filter_offsets(footer: &[u8]) -> thrift::Result<Vec<Vec<Option<i64>>>> {
+    let mut protocol = TCompactInputProtocol::new(footer);
+    let mut row_groups = Vec::new();
+    read_struct(&mut protocol, &mut |protocol, id| match id {
+        4 => read_list(protocol, &mut |protocol| {
+            let mut columns = Vec::new();
+            read_struct(protocol, &mut |protocol, id| match id {
+                1 => read_list(protocol, &mut |protocol| {
+                    let mut offset = None;
+                    read_struct(protocol, &mut |protocol, id| match id {
+                        3 => read_struct(protocol, &mut |protocol, id| match id {
+                            14 => protocol.read_i64().map(|o| {
+                                offset = Some(o);
+                                true
+                            }),
+                            _ => Ok(false),
+                        })
+                        .map(|_| true),
+                        _ => Ok(false),
+                    })?;
+                    columns.push(offset);
+                    Ok(())
+                })
+                .map(|_| true),
+                _ => Ok(false),
+            })?;
+            row_groups.push(columns);
+            Ok(())
+        })
+        .map(|_| true),
+        _ => Ok(false),
+    })?;
+    Ok(row_groups)
+}
+
+/// Reads the Bloom filter at `offset`: a header holding the size of the bitset, followed
+/// by the bitset.
+fn read_bitset(bytes: &[u8], offset: i64) -> Option<&[u8]> {
+    let data = bytes.get(offset as usize..)?;
+    let mut cursor = Cursor::new(data);
+    let mut num_bytes = None;
+    let mut protocol = TCompactInputProtocol::new(&mut cursor);
+    read_struct(&mut protocol, &mut |protocol, id| match id {
+        1 => protocol.read_i32().map(|n| {
+            num_bytes = Some(n);
+            true
+        }),
+        _ => Ok(false),
+    })
+    .ok()?;
+    drop(protocol);
+    let start = cursor.position() as usize;
+    data.get(start..start + num_bytes? as usize)
+}
+
+/// Tests whether the row groups of a Parquet file may hold `literal` in the leaf column
+/// `column` using their Bloom filters. Row groups without a filter may hold any value.
+pub fn parquet_bloom_check(bytes: Vec<u8>, tag: impl Into<Tag>, column: &str, literal: &str) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let (index, hash) = {
+        let reader = open_reader(bytes.clone(), &tag)?;
+        let descr = reader.metadata().file_metadata().schema_descr();
+        let index = (0..descr.num_columns()).find(|i| descr.column(*i).path().string() == column);
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let available: Vec<String> = descr.columns().iter().map(|c| c.path().string()).collect();
+                return Err(ShellError::labeled_error(
+                    format!("Unknown column '{}'", column),
+                    format!("available columns are: {}", available.join(", ")),
+                    &tag,
+                ));
+            }
+        };
+        let value = value_bytes(literal, &descr.column(index))
+            .map_err(|e| ShellError::labeled_error(format!("Cannot look up '{}' in column '{}'", literal, column), e, &tag))?;
+        (index, xxh64(&value))
+    };
+
+    // open_reader has already checked the magic and the footer length.
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+    let footer_start = bytes.len() - 8 - u32::from_le_bytes(footer_len) as usize;
+    let offsets = bloom_filter_offsets(&bytes[footer_start..bytes.len() - 8])
+        .map_err(|e| ShellError::labeled_error("Failed to read Parquet footer", e.to_string(), &tag))?;
+
+    let mut found = false;
+    let mut result = Vec::with_capacity(offsets.len());
+    for (row_group, columns) in offsets.iter().enumerate() {
+        let bitset = columns.get(index).copied().flatten().and_then(|offset| read_bitset(&bytes, offset));
+        found |= bitset.is_some();
+        let mut dict = TaggedDictBuilder::new(&tag);
+        dict.insert_untagged("row_group", UntaggedValue::int(row_group as u64));
+        dict.insert_untagged("may_contain", UntaggedValue::boolean(bitset.is_none_or(|b| may_contain(b, hash))));
+        result.push(dict.into_value());
+    }
+    if !found {
+        return Err(ShellError::labeled_error(
+            format!("Column '{}' has no Bloom filter", column),
+            "write the file again with Bloom filters enabled for this column",
+            &tag,
+        ));
+    }
+    Ok(UntaggedValue::Table(result).into_value(&tag))
+}
+
+/// Hashes the plain encoding, given by `encode`, of every value of a column chunk.
+fn hash_column<T: DataType>(
+    reader: &mut ColumnReaderImpl<T>,
+    descr: &ColumnDescriptor,
+    encode: impl Fn(&T::T) -> Vec<u8>,
+    hashes: &mut Vec<u64>,
+) -> parquet::errors::Result<()> {
+    let mut values = vec![T::T::default(); BATCH_SIZE];
+    let mut def_levels = vec![0; BATCH_SIZE];
+    let mut rep_levels = vec![0; BATCH_SIZE];
+    let (has_def, has_rep) = (descr.max_def_level() > 0, descr.max_rep_level() > 0);
+    loop {
+        let (values_read, levels_read) = reader.read_batch(
+            BATCH_SIZE,
+            if has_def { Some(&mut def_levels) } else { None },
+            if has_rep { Some(&mut rep_levels) } else { None },
+            &mut values,
+        )?;
+        if values_read == 0 && levels_read == 0 {
+            return Ok(());
+        }
+        hashes.extend(values[..values_read].iter().map(|value| xxh64(&encode(value))));
+    }
+}
+
+/// Writes the header of a bitset of `num_bytes`: split block filter, XXH64 hash and no
+/// compression, the only choices the format defines.
+fn write_bitset_header(out: &mut Vec<u8>, num_bytes: usize) -> thrift::Result<()> {
+    fn empty_union(protocol: &mut TCompactOutputProtocol<&mut Vec<u8>>, id: i16) -> thrift::Result<()> {
+        protocol.write_field_begin(&TFieldIdentifier::new("", TType::Struct, id))?;
+        protocol.write_struct_begin(&TStructIdentifier::new(""))?;
+        protocol.write_field_begin(&TFieldIdentifier::new("", TType::Struct, 1))?;
+        protocol.write_struct_begin(&TStructIdentifier::new(""))?;
+        protocol.write_field_stop()?;
+        protocol.write_struct_end()?;
+        protocol.write_field_end()?;
+        protocol.write_field_stop()?;
+        protocol.write_struct_end()?;
+        protocol.write_field_end()
+    }
+
+    let mut protocol = TCompactOutputProtocol::new(out);
+    protocol.write_struct_begin(&TStructIdentifier::new("BloomFilterHeader"))?;
+    protocol.write_field_begin(&TFieldIdentifier::new("numBytes", TType::I32, 1))?;
+    protocol.write_i32(num_bytes as i32)?;
+    protocol.write_field_end()?;
+    for id in 2..=4 {
+        empty_union(&mut protocol, id)?;
+    }
+    protocol.write_field_stop()?;
+    protocol.write_struct_end()?;
+    protocol.flush()
+}
+
+/// Copies a thrift value of type `ttype` from `input` to `output`. `path` holds the field
+/// ids leading to the value; every `ColumnMetaData` struct gets the next Bloom filter
+/// offset and length of `filters` added to its fields.
+fn copy_value<R: Read, W: Write>(
+    input: &mut TCompactInputProtocol<R>,
+    output: &mut TCompactOutputProtocol<W>,
+    ttype: TType,
+    path: &mut Vec<i16>,
+    filters: &mut dyn Iterator<Item = Option<(i64, i32)>>,
+) -> thrift::Result<()> {
+    match ttype {
+        TType::Bool => output.write_bool(input.read_bool()?),
+        TType::I08 => output.write_i8(input.read_i8()?),
+        TType::I16 => output.write_i16(input.read_i16()?),
+        TType::I32 => output.write_i32(input.read_i32()?),
+        TType::I64 => output.write_i64(input.read_i64()?),
+        TType::Double => output.write_double(input.read_double()?),
+        TType::String => output.write_bytes(&input.read_bytes()?),
+        TType::Struct => {
+            input.read_struct_begin()?;
+            output.write_struct_begin(&TStructIdentifier::new(""))?;
+            let is_column_meta_data = path.as_slice() == COLUMN_META_DATA;
+            loop {
+                let identifier = input.read_field_begin()?;
+                if identifier.field_type == TType::Stop {
+                    break;
+                }
+                let id = identifier.id.unwrap_or(0);
+                if is_column_meta_data && (id == 14 || id == 15) {
+                    skip(input, identifier.field_type)?;
+                    input.read_field_end()?;
+                    continue;
+                }
+                output.write_field_begin(&TFieldIdentifier::new("", identifier.field_type, id))?;
+                path.push(id);
+                copy_value(input, output, identifier.field_type, path, filters)?;
+                path.pop();
+                input.read_field_end()?;
+                output.write_field_end()?;
+            }
+            if is_column_meta_data {
+                if let Some((offset, length)) = filters.next().flatten() {
+                    output.write_field_begin(&TFieldIdentifier::new("bloom_filter_offset", TType::I64, 14))?;
+                    output.write_i64(offset)?;
+                    output.write_field_end()?;
+                    output.write_field_begin(&TFieldIdentifier::new("bloom_filter_length", TType::I32, 15))?;
+                    output.write_i32(length)?;
+                    output.write_field_end()?;
+                }
+            }
+            input.read_struct_end()?;
+            output.write_field_stop()?;
+            output.write_struct_end()
+        }
+        TType::List | TType::Set => {
+            let list = input.read_list_begin()?;
+            output.write_list_begin(&list)?;
+            for _ in 0..list.size {
+                copy_value(input, output, list.element_type, path, filters)?;
+            }
+            input.read_list_end()?;
+            output.write_list_end()
+        }
+        TType::Map => {
+            let map = input.read_map_begin()?;
+            output.write_map_begin(&map)?;
+            if let (Some(key), Some(value)) = (map.key_type, map.value_type) {
+                for _ in 0..map.size {
+                    copy_value(input, output, key, path, filters)?;
+                    copy_value(input, output, value, path, filters)?;
+                }
+            }
+            input.read_map_end()?;
+            output.write_map_end()
+        }
+        other => Err(thrift::Error::Protocol(thrift::ProtocolError::new(
+            thrift::ProtocolErrorKind::InvalidData,
+            format!("unexpected thrift type {:?} in the footer", other),
+        ))),
+    }
+}
+
+/// Adds split block Bloom filters for the leaf columns `columns` to every row group of
+/// the Parquet file `bytes`, sized for a false-positive probability of `fpp`. parquet 3.0
+/// cannot write Bloom filters, so the filters are built from the written values, stored
+/// after the last column chunk, and their offsets added to a rewritten footer.
+pub fn write_bloom_filters(bytes: Vec<u8>, columns: &[String], fpp: f64, tag: &Tag) -> Result<Vec<u8>, ShellError> {
+    let reader = open_reader(bytes.clone(), tag)?;
+    let metadata = reader.metadata();
+    let descr = metadata.file_metadata().schema_descr();
+    let mut indices = Vec::with_capacity(columns.len());
+    for column in columns {
+        let index = match (0..descr.num_columns()).find(|i| descr.column(*i).path().string() == *column) {
+            Some(index) => index,
+            None => {
+                let available: Vec<String> = descr.columns().iter().map(|c| c.path().string()).collect();
+                return Err(ShellError::labeled_error(
+                    format!("Unknown column '{}'", column),
+                    format!("available columns are: {}", available.join(", ")),
+                    tag,
+                ));
+            }
+        };
+        let physical = descr.column(index).physical_type();
+        if physical == PhysicalType::BOOLEAN || physical == PhysicalType::INT96 {
+            return Err(ShellError::labeled_error(
+                format!("Cannot write a Bloom filter for column '{}'", column),
+                format!("{} columns have no Bloom filters", physical),
+                tag,
+            ));
+        }
+        indices.push(index);
+    }
+
+    // open_reader has already checked the magic and the footer length.
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+    let footer_start = bytes.len() - 8 - u32::from_le_bytes(footer_len) as usize;
+    let mut output = bytes[..footer_start].to_vec();
+
+    let mut filters = Vec::with_capacity(metadata.num_row_groups() * descr.num_columns());
+    for i in 0..metadata.num_row_groups() {
+        let row_group = reader.get_row_group(i)
+            .map_err(|e| parquet_error("Failed to read Parquet row group", e, tag))?;
+        for j in 0..descr.num_columns() {
+            if !indices.contains(&j) {
+                filters.push(None);
+                continue;
+            }
+            let column = descr.column(j);
+            let mut hashes = Vec::new();
+            let hashed = match row_group.get_column_reader(j)
+                .map_err(|e| parquet_error("Failed to read Parquet column", e, tag))? {
+                ColumnReader::Int32ColumnReader(mut r) => hash_column(&mut r, &column, |v| v.to_le_bytes().to_vec(), &mut hashes),
+                ColumnReader::Int64ColumnReader(mut r) => hash_column(&mut r, &column, |v| v.to_le_bytes().to_vec(), &mut hashes),
+                ColumnReader::FloatColumnReader(mut r) => hash_column(&mut r, &column, |v| v.to_le_bytes().to_vec(), &mut hashes),
+                ColumnReader::DoubleColumnReader(mut r) => hash_column(&mut r, &column, |v| v.to_le_bytes().to_vec(), &mut hashes),
+                ColumnReader::ByteArrayColumnReader(mut r) => hash_column(&mut r, &column, |v| v.data().to_vec(), &mut hashes),
+                ColumnReader::FixedLenByteArrayColumnReader(mut r) => hash_column(&mut r, &column, |v| v.data().to_vec(), &mut hashes),
+                _ => unreachable!("Internal error: Bloom filter requested for a BOOLEAN or INT96 column"),
+            };
+            hashed.map_err(|e| parquet_error("Failed to read Parquet column", e, tag))?;
+            hashes.sort_unstable();
+            hashes.dedup();
+
+            let bitset = build_bitset(&hashes, fpp);
+            let offset = output.len();
+            write_bitset_header(&mut output, bitset.len())
+                .map_err(|e| ShellError::labeled_error("Failed to write Bloom filter", e.to_string(), tag))?;
+            output.extend_from_slice(&bitset);
+            filters.push(Some((offset as i64, (output.len() - offset) as i32)));
+        }
+    }
+
+    let mut footer = Vec::new();
+    {
+        let mut input = TCompactInputProtocol::new(&bytes[footer_start..bytes.len() - 8]);
+        let mut protocol = TCompactOutputProtocol::new(&mut footer);
+        copy_value(&mut input, &mut protocol, TType::Struct, &mut Vec::new(), &mut filters.into_iter())
+            .and_then(|_| protocol.flush())
+            .map_err(|e| ShellError::labeled_error("Failed to write Parquet footer", e.to_string(), tag))?;
+    }
+    output.extend_from_slice(&footer);
+    output.extend_from_slice(&(footer.len() as u32).to_le_bytes());
+    output.extend_from_slice(b"PAR1");
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::to_parquet::{to_parquet_bytes, ToParquetOpts};
+
+    #[test]
+    fn hashes_known_xxh64_vectors() {
+        assert_eq!(xxh64(b""), 0xEF46_DB37_51D8_E999);
+        assert_eq!(xxh64(b"a"), 0xD24E_C4F1_A98C_6E5B);
+        assert_eq!(xxh64(b"abc"), 0x44BC_2CF5_AD77_0999);
+        assert_eq!(xxh64(b"Nobody inspects the spammish repetition"), 0xFBCE_A83C_8A37_8BF1);
+    }
+
+    #[test]
+    fn finds_every_hash_added_to_a_bitset() {
+        let hashes: Vec<u64> = (0..100u32).map(|i| xxh64(&i.to_le_bytes())).collect();
+        let bitset = build_bitset(&hashes, DEFAULT_BLOOM_FILTER_FPP);
+
+        assert_eq!(bitset.len() % BLOCK_BYTES, 0);
+        assert!(hashes.iter().all(|hash| may_contain(&bitset, *hash)));
+        let false_positives = (100..1100u32).filter(|i| may_contain(&bitset, xxh64(&i.to_le_bytes()))).count();
+        assert!(false_positives < 150, "{} false positives in 1000", false_positives);
+    }
+
+    /// A file of two row groups with a Bloom filter on `name` but not on `id`.
+    fn with_bloom_filter() -> Vec<u8> {
+        let rows: Vec<Value> = ["ada", "grace", "edsger"].iter().enumerate()
+            .map(|(id, name)| {
+                let mut dict = TaggedDictBuilder::new(Tag::unknown());
+                dict.insert_untagged("id", UntaggedValue::int(id as i64));
+                dict.insert_untagged("name", UntaggedValue::string(*name));
+                dict.into_value()
+            })
+            .collect();
+        let opts = ToParquetOpts { row_group_size: 2, ..ToParquetOpts::default() };
+        let bytes = to_parquet_bytes(&rows, Tag::unknown(), &opts).unwrap();
+        write_bloom_filters(bytes, &["name".to_string()], DEFAULT_BLOOM_FILTER_FPP, &Tag::unknown()).unwrap()
+    }
+
+    fn footer(bytes: &[u8]) -> &[u8] {
+        let mut footer_len = [0u8; 4];
+        footer_len.copy_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+        &bytes[bytes.len() - 8 - u32::from_le_bytes(footer_len) as usize..bytes.len() - 8]
+    }
+
+    #[test]
+    fn reads_the_offsets_of_written_filters() {
+        let bytes = with_bloom_filter();
+        let offsets = bloom_filter_offsets(footer(&bytes)).unwrap();
+
+        assert_eq!(offsets.len(), 2);
+        for columns in &offsets {
+            assert_eq!(columns.len(), 2);
+            assert_eq!(columns[0], None);
+            let bitset = read_bitset(&bytes, columns[1].unwrap()).unwrap();
+            assert!(!bitset.is_empty() && bitset.len().is_multiple_of(BLOCK_BYTES));
+        }
+        assert_ne!(offsets[0][1], offsets[1][1]);
+    }
+
+    #[test]
+    fn finds_no_offsets_in_files_without_filters() {
+        let bytes = to_parquet_bytes(&[TaggedDictBuilder::build(Tag::unknown(), |dict| {
+            dict.insert_untagged("id", UntaggedValue::int(1));
+        })], Tag::unknown(), &ToParquetOpts::default()).unwrap();
+
+        assert_eq!(bloom_filter_offsets(footer(&bytes)).unwrap(), vec![vec![None]]);
+    }
+
+    #[test]
+    fn rejects_bitsets_outside_the_file() {
+        let bytes = with_bloom_filter();
+        let offset = bloom_filter_offsets(footer(&bytes)).unwrap()[1][1].unwrap();
+
+        assert!(read_bitset(&bytes, -1).is_none());
+        assert!(read_bitset(&bytes, bytes.len() as i64 + 1).is_none());
+        assert!(read_bitset(&bytes[..offset as usize + 8], offset).is_none());
+    }
+}
@@ -0,0 +1,277 @@
+use parquet::schema::types::Type;
+use nu_errors::ShellError;
+use nu_protocol::{Primitive, ShellTypeName, UntaggedValue, Value};
+use nu_source::Tag;
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+use chrono::DateTime;
+use num_bigint::BigInt;
+use std::fmt;
+use std::str::FromStr;
+use crate::convert::days_to_date;
+
+/// Nushell type a `--cast` turns a column into.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CastType {
+    /// Text of strings, numbers, booleans, dates and UTF-8 binaries.
+    String,
+    /// Integers, decimals truncated towards zero, booleans, dates as Unix seconds and
+    /// integer strings.
+    Int,
+    /// Numbers and numeric strings, as decimals.
+    Float,
+    /// Booleans, integers, true when not zero, and `true` or `false` strings.
+    Bool,
+    /// Dates, integers counting days since the Unix epoch, and RFC 3339 or
+    /// `YYYY-MM-DD` strings.
+    Date,
+}
+
+impl CastType {
+    /// Looks up a type by the name `--cast` takes, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "string" => Some(CastType::String),
+            "int" => Some(CastType::Int),
+            "float" => Some(CastType::Float),
+            "bool" => Some(CastType::Bool),
+            "date" => Some(CastType::Date),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CastType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CastType::String => "string",
+            CastType::Int => "int",
+            CastType::Float => "float",
+            CastType::Bool => "bool",
+            CastType::Date => "date",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A `--cast` of a top-level column, eg `day:date`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cast {
+    /// Top-level column to convert.
+    pub column: String,
+    /// Type the column is converted to.
+    pub to: CastType,
+}
+
+impl Cast {
+    /// Parses `<column>:<type>`. The type follows the last colon, so column names may
+    /// contain colons.
+    pub fn parse(text: &str) -> Result<Cast, String> {
+        let (column, to) = match text.rfind(':') {
+            Some(at) => (&text[..at], &text[at + 1..]),
+            None => return Err(format!("expected <column>:<type>, found '{}'", text)),
+        };
+        if column.is_empty() {
+            return Err("expected a column before the type".to_string());
+        }
+        let to = CastType::from_name(to)
+            .ok_or_else(|| format!("unknown type '{}', expected string, int, float, bool or date", to))?;
+        Ok(Cast { column: column.to_string(), to })
+    }
+
+    /// Checks that the column is a top-level column of the schema.
+    pub fn check(&self, schema: &Type, tag: &Tag) -> Result<(), ShellError> {
+        if schema.get_fields().iter().any(|f| f.name() == self.column) {
+            return Ok(());
+        }
+        let available: Vec<&str> = schema.get_fields().iter().map(|f| f.name()).collect();
+        Err(ShellError::labeled_error(
+            format!("Unknown cast column '{}'", self.column),
+            format!("available columns are: {}", available.join(", ")),
+            tag,
+        ))
+    }
+
+    /// Casts the column of a converted record, returning why it cannot be cast
+    /// otherwise. Nulls stay null.
+    pub fn apply(&self, record: &mut Value) -> Result<(), String> {
+        let dict = match &mut record.value {
+            UntaggedValue::Row(dict) => dict,
+            _ => return Ok(()),
+        };
+        if let Some(value) = dict.entries.get_mut(&self.column) {
+            let cast = cast_value(value, self.to)?;
+            *value = cast.into_value(value.tag.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Converts a value to the type, describing the value when it has no counterpart in it.
+fn cast_value(value: &Value, to: CastType) -> Result<UntaggedValue, String> {
+    let primitive = match &value.value {
+        UntaggedValue::Primitive(Primitive::Nothing) => return Ok(UntaggedValue::nothing()),
+        UntaggedValue::Primitive(primitive) => primitive,
+        _ => return Err(format!("found {}", value.type_name())),
+    };
+    let failed = || format!("found {} {}", value.type_name(), describe(primitive));
+    let cast = match (to, primitive) {
+        (CastType::String, Primitive::String(s)) => UntaggedValue::string(s),
+        (CastType::String, Primitive::Int(i)) => UntaggedValue::string(i.to_string()),
+        (CastType::String, Primitive::Decimal(d)) => UntaggedValue::string(d.to_string()),
+        (CastType::String, Primitive::Boolean(b)) => UntaggedValue::string(b.to_string()),
+        (CastType::String, Primitive::Date(d)) => UntaggedValue::string(d.to_rfc3339()),
+        (CastType::String, Primitive::Binary(b)) => match String::from_utf8(b.clone()) {
+            Ok(s) => UntaggedValue::string(s),
+            Err(_) => return Err("binary value is not valid UTF-8".to_string()),
+        },
+
+        (CastType::Int, Primitive::Int(i)) => UntaggedValue::int(i.clone()),
+        // Decimals are truncated towards zero.
+        (CastType::Int, Primitive::Decimal(d)) => UntaggedValue::int(d.with_scale(0).as_bigint_and_exponent().0),
+        (CastType::Int, Primitive::Boolean(b)) => UntaggedValue::int(*b as i64),
+        (CastType::Int, Primitive::Date(d)) => UntaggedValue::int(d.timestamp()),
+        (CastType::Int, Primitive::String(s)) => UntaggedValue::int(BigInt::from_str(s.trim()).map_err(|_| failed())?),
+
+        (CastType::Float, Primitive::Decimal(d)) => UntaggedValue::decimal(d.clone()),
+        (CastType::Float, Primitive::Int(i)) => UntaggedValue::decimal(BigDecimal::from(i.clone())),
+        (CastType::Float, Primitive::String(s)) => {
+            UntaggedValue::decimal(BigDecimal::from_str(s.trim()).map_err(|_| failed())?)
+        }
+
+        (CastType::Bool, Primitive::Boolean(b)) => UntaggedValue::boolean(*b),
+        (CastType::Bool, Primitive::Int(i)) => UntaggedValue::boolean(!i.is_zero()),
+        (CastType::Bool, Primitive::String(s)) => UntaggedValue::boolean(s.trim().parse::<bool>().map_err(|_| failed())?),
+
+        (CastType::Date, Primitive::Date(d)) => UntaggedValue::date(*d),
+        // Integers are days since the epoch, the way DATE columns store them.
+        (CastType::Date, Primitive::Int(i)) => match i.to_i64().map(days_to_date) {
+            Some(Some(date)) => UntaggedValue::date(date),
+            _ => return Err(failed()),
+        },
+        (CastType::Date, Primitive::String(s)) => match DateTime::parse_from_rfc3339(s.trim()) {
+            Ok(date) => UntaggedValue::date(date),
+            Err(_) => {
+                let days = chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+                    .map_err(|_| failed())?
+                    .signed_duration_since(chrono::NaiveDate::from_ymd(1970, 1, 1))
+                    .num_days();
+                UntaggedValue::date(days_to_date(days).ok_or_else(failed)?)
+            }
+        },
+        _ => return Err(failed()),
+    };
+    Ok(cast)
+}
+
+fn describe(primitive: &Primitive) -> String {
+    match primitive {
+        Primitive::String(s) => format!("'{}'", s),
+        Primitive::Int(i) => i.to_string(),
+        Primitive::Decimal(d) => d.to_string(),
+        Primitive::Boolean(b) => b.to_string(),
+        Primitive::Date(d) => d.to_rfc3339(),
+        _ => "value".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nu_protocol::TaggedDictBuilder;
+    use parquet::schema::parser::parse_message_type;
+
+    fn cast(value: UntaggedValue, to: CastType) -> Result<UntaggedValue, String> {
+        cast_value(&value.into_value(Tag::unknown()), to)
+    }
+
+    fn decimal(text: &str) -> UntaggedValue {
+        UntaggedValue::decimal(BigDecimal::from_str(text).unwrap())
+    }
+
+    fn date(text: &str) -> UntaggedValue {
+        UntaggedValue::date(DateTime::parse_from_rfc3339(text).unwrap())
+    }
+
+    #[test]
+    fn parses_column_and_type() {
+        assert_eq!(Cast::parse("day:date"), Ok(Cast { column: "day".to_string(), to: CastType::Date }));
+        assert_eq!(Cast::parse("a:b:INT"), Ok(Cast { column: "a:b".to_string(), to: CastType::Int }));
+        assert_eq!(Cast::parse("day"), Err("expected <column>:<type>, found 'day'".to_string()));
+        assert_eq!(Cast::parse(":int"), Err("expected a column before the type".to_string()));
+        assert_eq!(Cast::parse("day:time"), Err("unknown type 'time', expected string, int, float, bool or date".to_string()));
+        for to in &[CastType::String, CastType::Int, CastType::Float, CastType::Bool, CastType::Date] {
+            assert_eq!(CastType::from_name(&to.to_string()), Some(*to));
+        }
+    }
+
+    #[test]
+    fn casts_to_string() {
+        assert_eq!(cast(UntaggedValue::int(-3), CastType::String), Ok(UntaggedValue::string("-3")));
+        assert_eq!(cast(decimal("1.25"), CastType::String), Ok(UntaggedValue::string("1.25")));
+        assert_eq!(cast(UntaggedValue::boolean(true), CastType::String), Ok(UntaggedValue::string("true")));
+        assert_eq!(cast(date("2021-02-03T04:05:06+00:00"), CastType::String), Ok(UntaggedValue::string("2021-02-03T04:05:06+00:00")));
+        assert_eq!(cast(UntaggedValue::binary(b"abc".to_vec()), CastType::String), Ok(UntaggedValue::string("abc")));
+        assert_eq!(cast(UntaggedValue::binary(vec![0xff]), CastType::String), Err("binary value is not valid UTF-8".to_string()));
+    }
+
+    #[test]
+    fn casts_to_int() {
+        assert_eq!(cast(decimal("-2.7"), CastType::Int), Ok(UntaggedValue::int(-2)));
+        assert_eq!(cast(UntaggedValue::boolean(true), CastType::Int), Ok(UntaggedValue::int(1)));
+        assert_eq!(cast(date("1970-01-02T00:00:00+00:00"), CastType::Int), Ok(UntaggedValue::int(86_400)));
+        assert_eq!(cast(UntaggedValue::string(" 42 "), CastType::Int), Ok(UntaggedValue::int(42)));
+        assert_eq!(cast(UntaggedValue::string("4.2"), CastType::Int), Err("found string '4.2'".to_string()));
+    }
+
+    #[test]
+    fn casts_to_float() {
+        assert_eq!(cast(UntaggedValue::int(3), CastType::Float), Ok(decimal("3")));
+        assert_eq!(cast(UntaggedValue::string("1e2"), CastType::Float), Ok(decimal("100")));
+        assert_eq!(cast(UntaggedValue::string("one"), CastType::Float), Err("found string 'one'".to_string()));
+        assert_eq!(cast(UntaggedValue::boolean(true), CastType::Float), Err("found boolean true".to_string()));
+    }
+
+    #[test]
+    fn casts_to_bool() {
+        assert_eq!(cast(UntaggedValue::int(0), CastType::Bool), Ok(UntaggedValue::boolean(false)));
+        assert_eq!(cast(UntaggedValue::int(-5), CastType::Bool), Ok(UntaggedValue::boolean(true)));
+        assert_eq!(cast(UntaggedValue::string("false"), CastType::Bool), Ok(UntaggedValue::boolean(false)));
+        assert_eq!(cast(UntaggedValue::string("yes"), CastType::Bool), Err("found string 'yes'".to_string()));
+    }
+
+    #[test]
+    fn casts_to_date() {
+        let epoch_day = date("1970-01-11T00:00:00+00:00");
+        assert_eq!(cast(UntaggedValue::int(10), CastType::Date), Ok(epoch_day.clone()));
+        assert_eq!(cast(UntaggedValue::string("1970-01-11"), CastType::Date), Ok(epoch_day));
+        assert_eq!(cast(UntaggedValue::string("2021-02-03T04:05:06+02:00"), CastType::Date), Ok(date("2021-02-03T04:05:06+02:00")));
+        assert_eq!(cast(UntaggedValue::string("03/02/2021"), CastType::Date), Err("found string '03/02/2021'".to_string()));
+        assert_eq!(cast(decimal("1.5"), CastType::Date), Err("found decimal 1.5".to_string()));
+    }
+
+    #[test]
+    fn keeps_nulls_and_refuses_nested_values() {
+        assert_eq!(cast(UntaggedValue::nothing(), CastType::Int), Ok(UntaggedValue::nothing()));
+        assert_eq!(cast(UntaggedValue::Table(vec![]), CastType::String), Err("found table".to_string()));
+    }
+
+    #[test]
+    fn applies_to_the_column_of_a_record() {
+        let mut record = TaggedDictBuilder::build(Tag::unknown(), |dict| {
+            dict.insert_untagged("id", UntaggedValue::string("7"));
+            dict.insert_untagged("name", UntaggedValue::string("ada"));
+        });
+        Cast::parse("id:int").unwrap().apply(&mut record).unwrap();
+
+        let expected = TaggedDictBuilder::build(Tag::unknown(), |dict| {
+            dict.insert_untagged("id", UntaggedValue::int(7));
+            dict.insert_untagged("name", UntaggedValue::string("ada"));
+        });
+        assert_eq!(record, expected);
+        assert_eq!(Cast::parse("name:int").unwrap().apply(&mut record), Err("found string 'ada'".to_string()));
+
+        let schema = parse_message_type("message schema { required int32 id; }").unwrap();
+        assert!(Cast::parse("id:string").unwrap().check(&schema, &Tag::unknown()).is_ok());
+        assert!(Cast::parse("name:string").unwrap().check(&schema, &Tag::unknown()).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+use chrono::{Datelike, DateTime, FixedOffset, LocalResult, TimeZone, Timelike, Utc};
+use bigdecimal::BigDecimal;
+use indexmap::IndexMap;
+use num_bigint::BigInt;
+
+/// Resolution of an integer timestamp counted from the Unix epoch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeUnit {
+    /// Seconds since the epoch.
+    Seconds,
+    /// Milliseconds since the epoch.
+    Millis,
+    /// Microseconds since the epoch.
+    Micros,
+    /// Nanoseconds since the epoch.
+    Nanos,
+}
+
+impl TimeUnit {
+    pub(crate) fn per_second(self) -> i64 {
+        match self {
+            TimeUnit::Seconds => 1,
+            TimeUnit::Millis => 1_000,
+            TimeUnit::Micros => 1_000_000,
+            TimeUnit::Nanos => 1_000_000_000,
+        }
+    }
+}
+
+/// Converts a timestamp into a UTC date. The value is split into whole seconds and
+/// nanoseconds rather than added to the epoch as a `Duration`, so that timestamps far
+/// from the epoch return `None` instead of overflowing.
+pub fn timestamp_to_date(value: i64, unit: TimeUnit) -> Option<DateTime<FixedOffset>> {
+    let per_second = unit.per_second();
+    let seconds = value.div_euclid(per_second);
+    let nanos = value.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    match Utc.timestamp_opt(seconds, nanos as u32) {
+        LocalResult::Single(date) => Some(date.into()),
+        _ => None,
+    }
+}
+
+/// Converts a number of days since the Unix epoch into a UTC date.
+pub fn days_to_date(days: i64) -> Option<DateTime<FixedOffset>> {
+    timestamp_to_date(days.checked_mul(86_400)?, TimeUnit::Seconds)
+}
+
+/// Formats a time of day counted from midnight as `HH:MM:SS` followed by as many
+/// fractional digits as the unit has, eg `12:34:56.789` for milliseconds.
+pub fn time_of_day(value: i64, unit: TimeUnit) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let per_second = unit.per_second() as u64;
+    let value = value.unsigned_abs();
+    let (seconds, fraction) = (value / per_second, value % per_second);
+    let hms = format!("{}{:02}:{:02}:{:02}", sign, seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match unit {
+        TimeUnit::Seconds => hms,
+        TimeUnit::Millis => format!("{}.{:03}", hms, fraction),
+        TimeUnit::Micros => format!("{}.{:06}", hms, fraction),
+        TimeUnit::Nanos => format!("{}.{:09}", hms, fraction),
+    }
+}
+
+pub(crate) fn date_or_nothing(date: Option<DateTime<FixedOffset>>) -> UntaggedValue {
+    date.map(UntaggedValue::date).unwrap_or_else(UntaggedValue::nothing)
+}
+
+/// Breaks a date into a record of its components, so they can be queried directly.
+pub fn timestamp_to_extended_record(dt: DateTime<FixedOffset>, tag: impl Into<Tag>) -> Value {
+    let tag = tag.into();
+    let mut record = IndexMap::with_capacity(9);
+    record.insert("year".to_string(), UntaggedValue::int(dt.year()).into_value(tag.clone()));
+    record.insert("month".to_string(), UntaggedValue::int(dt.month()).into_value(tag.clone()));
+    record.insert("day".to_string(), UntaggedValue::int(dt.day()).into_value(tag.clone()));
+    record.insert("hour".to_string(), UntaggedValue::int(dt.hour()).into_value(tag.clone()));
+    record.insert("minute".to_string(), UntaggedValue::int(dt.minute()).into_value(tag.clone()));
+    record.insert("second".to_string(), UntaggedValue::int(dt.second()).into_value(tag.clone()));
+    record.insert("nanosecond".to_string(), UntaggedValue::int(dt.nanosecond()).into_value(tag.clone()));
+    record.insert("timezone".to_string(), UntaggedValue::string(dt.offset().to_string()).into_value(tag.clone()));
+    record.insert("unix_epoch_ms".to_string(), UntaggedValue::int(dt.timestamp_millis()).into_value(tag.clone()));
+    UntaggedValue::row(record).into_value(tag)
+}
+
+/// Breaks an interval into a record of its months, days and milliseconds, which have
+/// no fixed ratio to each other.
+pub(crate) fn interval_to_record(months: i64, days: i64, millis: i64, tag: impl Into<Tag>) -> Value {
+    let tag = tag.into();
+    let mut record = IndexMap::with_capacity(3);
+    record.insert("months".to_string(), UntaggedValue::int(months).into_value(tag.clone()));
+    record.insert("days".to_string(), UntaggedValue::int(days).into_value(tag.clone()));
+    record.insert("milliseconds".to_string(), UntaggedValue::int(millis).into_value(tag.clone()));
+    UntaggedValue::row(record).into_value(tag)
+}
+
+/// Whether a decimal parses back into the float it was made from. `BigDecimal::to_f64`
+/// scales by powers of ten and is not exact, so this goes through the decimal string.
+pub(crate) fn reads_back<F: std::str::FromStr + PartialEq>(decimal: &BigDecimal, float: F) -> bool {
+    decimal.to_string().parse::<F>().is_ok_and(|parsed| parsed == float)
+}
+
+/// Converts an unscaled integer and its scale, as decimals are stored in Parquet and
+/// Arrow, into a decimal. `1234` with a scale of 2 is `12.34`.
+pub fn decimal_from_unscaled(unscaled: i128, scale: i64) -> BigDecimal {
+    BigDecimal::new(BigInt::from(unscaled), scale)
+}
+
+/// Converts the big-endian two's complement bytes of an unscaled integer, as Parquet
+/// stores decimals of any width, into a decimal with the given scale.
+pub fn decimal_from_be_bytes(unscaled: &[u8], scale: i64) -> BigDecimal {
+    BigDecimal::new(BigInt::from_signed_bytes_be(unscaled), scale)
+}
@@ -0,0 +1,138 @@
+use nu_protocol::{Primitive, ShellTypeName, TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
+use indexmap::IndexMap;
+use std::collections::HashSet;
+
+/// What `--describe` gathers about one column.
+#[derive(Default)]
+struct Summary {
+    type_name: Option<String>,
+    null_count: u64,
+    /// Number of numeric values.
+    count: u64,
+    /// Smallest and largest numbers, with the values they were read from.
+    min: Option<(BigDecimal, Value)>,
+    max: Option<(BigDecimal, Value)>,
+    sum: BigDecimal,
+    /// Whether any number was a decimal, making the sum one too.
+    decimals: bool,
+    /// Running mean and sum of squared differences to it, Welford's way, for `stddev`.
+    mean: f64,
+    squares: f64,
+    /// Distinct strings and the bounds of their lengths in characters.
+    distinct: HashSet<String>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+}
+
+impl Summary {
+    fn add(&mut self, value: &Value) {
+        let number = match &value.value {
+            UntaggedValue::Primitive(Primitive::Nothing) => {
+                self.null_count += 1;
+                return;
+            }
+            UntaggedValue::Primitive(Primitive::Int(i)) => Some(BigDecimal::from(i.clone())),
+            UntaggedValue::Primitive(Primitive::Decimal(d)) => Some(d.clone()),
+            _ => None,
+        };
+        if self.type_name.is_none() {
+            self.type_name = Some(value.value.type_name().to_string());
+        }
+        if let Some(number) = number {
+            self.add_number(number, value);
+        } else if let UntaggedValue::Primitive(Primitive::String(s)) = &value.value {
+            let length = s.chars().count();
+            self.min_length = Some(self.min_length.map_or(length, |min| min.min(length)));
+            self.max_length = Some(self.max_length.map_or(length, |max| max.max(length)));
+            if !self.distinct.contains(s) {
+                self.distinct.insert(s.clone());
+            }
+        }
+    }
+
+    fn add_number(&mut self, number: BigDecimal, value: &Value) {
+        self.decimals |= matches!(value.value, UntaggedValue::Primitive(Primitive::Decimal(_)));
+        self.count += 1;
+        let float = number.to_f64().unwrap_or(f64::NAN);
+        let delta = float - self.mean;
+        self.mean += delta / self.count as f64;
+        self.squares += delta * (float - self.mean);
+        self.sum += &number;
+        if self.min.as_ref().is_none_or(|(min, _)| number < *min) {
+            self.min = Some((number.clone(), value.clone()));
+        }
+        if self.max.as_ref().is_none_or(|(max, _)| number > *max) {
+            self.max = Some((number, value.clone()));
+        }
+    }
+
+    fn into_value(self, column: &str, tag: &Tag) -> Value {
+        let float = |f: f64| match BigDecimal::from_f64(f) {
+            Some(d) => UntaggedValue::decimal(d),
+            None => UntaggedValue::nothing(),
+        };
+        let numeric = self.count > 0;
+        let strings = self.max_length.is_some();
+        let mut dict = TaggedDictBuilder::new(tag);
+        dict.insert_untagged("column", UntaggedValue::string(column));
+        dict.insert_untagged("type", match self.type_name {
+            Some(name) => UntaggedValue::string(name),
+            None => UntaggedValue::nothing(),
+        });
+        dict.insert_untagged("null_count", UntaggedValue::int(self.null_count));
+        match self.min {
+            Some((_, min)) => dict.insert_value("min", min),
+            None => dict.insert_untagged("min", UntaggedValue::nothing()),
+        }
+        match self.max {
+            Some((_, max)) => dict.insert_value("max", max),
+            None => dict.insert_untagged("max", UntaggedValue::nothing()),
+        }
+        dict.insert_untagged("sum", match (numeric, self.decimals) {
+            (false, _) => UntaggedValue::nothing(),
+            (true, false) => UntaggedValue::int(self.sum.with_scale(0).as_bigint_and_exponent().0),
+            (true, true) => UntaggedValue::decimal(self.sum),
+        });
+        dict.insert_untagged("mean", if numeric { float(self.mean) } else { UntaggedValue::nothing() });
+        // The sample standard deviation, as pandas gives.
+        dict.insert_untagged("stddev", if self.count > 1 {
+            float((self.squares / (self.count - 1) as f64).sqrt())
+        } else {
+            UntaggedValue::nothing()
+        });
+        dict.insert_untagged("distinct_count", if strings {
+            UntaggedValue::int(self.distinct.len() as u64)
+        } else {
+            UntaggedValue::nothing()
+        });
+        let length = |length: Option<usize>| match length {
+            Some(length) => UntaggedValue::int(length as u64),
+            None => UntaggedValue::nothing(),
+        };
+        dict.insert_untagged("min_length", length(self.min_length));
+        dict.insert_untagged("max_length", length(self.max_length));
+        dict.into_value()
+    }
+}
+
+/// Summarizes the top-level columns of `rows`, one record per column in the order they
+/// first appear. Numeric columns get their `min`, `max`, `sum`, `mean` and `stddev`,
+/// string columns their `distinct_count`, `min_length` and `max_length`, and every
+/// column its `null_count` and the nushell `type` of its first value.
+pub(crate) fn describe(rows: &[Value], tag: &Tag) -> Value {
+    let mut summaries: IndexMap<&str, Summary> = IndexMap::new();
+    for row in rows {
+        if let UntaggedValue::Row(dict) = &row.value {
+            for (name, value) in &dict.entries {
+                summaries.entry(name.as_str()).or_default().add(value);
+            }
+        }
+    }
+    let rows = summaries.into_iter()
+        .map(|(column, summary)| summary.into_value(column, tag))
+        .collect();
+    UntaggedValue::Table(rows).into_value(tag)
+}
+
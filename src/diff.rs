@@ -0,0 +1,197 @@
+use parquet::file::reader::FileReader;
+use parquet::schema::types::Type;
+use nu_errors::ShellError;
+use nu_protocol::{Dictionary, TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use indexmap::IndexMap;
+use std::collections::{HashMap, HashSet};
+use crate::fingerprint::canonical;
+use crate::from_parquet::{from_parquet_bytes, write_key, FromParquetOpts};
+use crate::open_reader;
+
+/// Rows `parquet diff` lists in each of `only_in_a`, `only_in_b` and `changed` unless
+/// told otherwise.
+pub const DEFAULT_MAX_DIFF_ROWS: usize = 10;
+
+/// The `schema_changes` of two schemas: top-level columns added in `b`, removed from
+/// `a`, or modified, compared by their fingerprints.
+fn schema_changes(a: &Type, b: &Type, tag: &Tag) -> Vec<Value> {
+    let texts = |schema: &Type| -> IndexMap<String, String> {
+        schema.get_fields().iter()
+            .map(|field| (field.name().to_string(), canonical(field).trim().to_string()))
+            .collect()
+    };
+    let (a, b) = (texts(a), texts(b));
+    let change = |column: &str, change: &str, a: Option<&String>, b: Option<&String>| {
+        let text = |text: Option<&String>| text.map_or_else(UntaggedValue::nothing, UntaggedValue::string);
+        let mut dict = TaggedDictBuilder::new(tag);
+        dict.insert_untagged("column", UntaggedValue::string(column));
+        dict.insert_untagged("change", UntaggedValue::string(change));
+        dict.insert_untagged("a", text(a));
+        dict.insert_untagged("b", text(b));
+        dict.into_value()
+    };
+
+    let mut changes = Vec::new();
+    for (column, text) in &a {
+        match b.get(column) {
+            None => changes.push(change(column, "removed", Some(text), None)),
+            Some(other) if text != other => {
+                changes.push(change(column, "modified", Some(text), Some(other)))
+            }
+            Some(_) => {}
+        }
+    }
+    for (column, text) in &b {
+        if !a.contains_key(column) {
+            changes.push(change(column, "added", None, Some(text)));
+        }
+    }
+    changes
+}
+
+fn rows(value: Value) -> Vec<Dictionary> {
+    let values = match value.value {
+        UntaggedValue::Table(values) => values,
+        _ => Vec::new(),
+    };
+    values.into_iter()
+        .filter_map(|value| match value.value {
+            UntaggedValue::Row(dict) => Some(dict),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The text of the `columns` of a row, equal for rows with equal values there.
+fn row_key(row: &Dictionary, columns: &[&str]) -> String {
+    let mut key = String::new();
+    for column in columns {
+        match row.entries.get(*column) {
+            Some(value) => write_key(&value.value, &mut key),
+            None => key.push_str("nothing"),
+        }
+        key.push('\u{1f}');
+    }
+    key
+}
+
+/// Compares two Parquet files: the top-level columns their schemas disagree on, their
+/// row counts, and their rows. Rows are compared on the columns both files have. With
+/// a `key` column, rows are matched by key and those with a differing value are listed
+/// in `changed` with the columns that differ, a key appearing several times is matched
+/// by its first row. Without one, whole rows are matched, counting duplicates. Each
+/// list keeps at most `max_rows` rows, the counts cover all of them.
+pub fn parquet_diff(
+    a: Vec<u8>,
+    b: Vec<u8>,
+    tag: impl Into<Tag>,
+    key: Option<&str>,
+    max_rows: usize,
+) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader_a = open_reader(a.clone(), &tag)?;
+    let reader_b = open_reader(b.clone(), &tag)?;
+    let schema_a = reader_a.metadata().file_metadata().schema();
+    let schema_b = reader_b.metadata().file_metadata().schema();
+    let common: Vec<&str> = schema_a.get_fields().iter()
+        .map(|field| field.name())
+        .filter(|name| schema_b.get_fields().iter().any(|field| field.name() == *name))
+        .collect();
+    if let Some(key) = key {
+        if !common.contains(&key) {
+            return Err(ShellError::labeled_error(
+                format!("Unknown key column '{}'", key),
+                format!("columns in both files are: {}", common.join(", ")),
+                &tag,
+            ));
+        }
+    }
+
+    let opts = FromParquetOpts::default();
+    let rows_a = rows(from_parquet_bytes(a, tag.clone(), &opts)?);
+    let rows_b = rows(from_parquet_bytes(b, tag.clone(), &opts)?);
+    let record = |row: &Dictionary| UntaggedValue::Row(row.clone()).into_value(&tag);
+
+    let (mut only_in_a, mut only_in_b, mut changed) = (Vec::new(), Vec::new(), Vec::new());
+    match key {
+        Some(key) => {
+            let mut by_key: HashMap<String, &Dictionary> = HashMap::new();
+            for row in &rows_b {
+                by_key.entry(row_key(row, &[key])).or_insert(row);
+            }
+            let mut keys_in_a = HashSet::new();
+            for row in &rows_a {
+                let id = row_key(row, &[key]);
+                if !keys_in_a.insert(id.clone()) {
+                    continue;
+                }
+                let other = match by_key.get(&id) {
+                    Some(other) => other,
+                    None => {
+                        only_in_a.push(row);
+                        continue;
+                    }
+                };
+                let columns: Vec<&str> = common.iter()
+                    .copied()
+                    .filter(|column| row_key(row, &[column]) != row_key(other, &[column]))
+                    .collect();
+                if !columns.is_empty() {
+                    changed.push((&row.entries[key], row, *other, columns));
+                }
+            }
+            only_in_b.extend(rows_b.iter().filter(|row| !keys_in_a.contains(&row_key(row, &[key]))));
+        }
+        None => {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for row in &rows_b {
+                *counts.entry(row_key(row, &common)).or_default() += 1;
+            }
+            for row in &rows_a {
+                match counts.get_mut(&row_key(row, &common)) {
+                    Some(count) if *count > 0 => *count -= 1,
+                    _ => only_in_a.push(row),
+                }
+            }
+            // What is left in the counts are the rows of `b` that `a` did not match,
+            // the last ones of each are taken as the unmatched ones.
+            for row in rows_b.iter().rev() {
+                if let Some(count) = counts.get_mut(&row_key(row, &common)) {
+                    if *count > 0 {
+                        *count -= 1;
+                        only_in_b.push(row);
+                    }
+                }
+            }
+            only_in_b.reverse();
+        }
+    }
+
+    let mut data_diff = TaggedDictBuilder::new(&tag);
+    data_diff.insert_untagged("only_in_a_count", UntaggedValue::int(only_in_a.len() as u64));
+    data_diff.insert_untagged("only_in_b_count", UntaggedValue::int(only_in_b.len() as u64));
+    data_diff.insert_untagged("changed_count", UntaggedValue::int(changed.len() as u64));
+    data_diff.insert_untagged("only_in_a", UntaggedValue::Table(only_in_a.iter().take(max_rows).map(|row| record(row)).collect()));
+    data_diff.insert_untagged("only_in_b", UntaggedValue::Table(only_in_b.iter().take(max_rows).map(|row| record(row)).collect()));
+    let changed = changed.iter().take(max_rows)
+        .map(|(key, a, b, columns)| {
+            let mut dict = TaggedDictBuilder::new(&tag);
+            dict.insert_value("key", (*key).clone());
+            dict.insert_untagged("columns", UntaggedValue::Table(
+                columns.iter().map(|column| UntaggedValue::string(*column).into_value(&tag)).collect(),
+            ));
+            dict.insert_value("a", record(a));
+            dict.insert_value("b", record(b));
+            dict.into_value()
+        })
+        .collect();
+    data_diff.insert_untagged("changed", UntaggedValue::Table(changed));
+
+    let mut dict = TaggedDictBuilder::new(&tag);
+    dict.insert_untagged("schema_changes", UntaggedValue::Table(schema_changes(schema_a, schema_b, &tag)));
+    dict.insert_untagged("row_count_a", UntaggedValue::int(reader_a.metadata().file_metadata().num_rows()));
+    dict.insert_untagged("row_count_b", UntaggedValue::int(reader_b.metadata().file_metadata().num_rows()));
+    dict.insert_value("data_diff", data_diff.into_value());
+    Ok(dict.into_value())
+}
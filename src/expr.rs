@@ -0,0 +1,584 @@
+use parquet::schema::types::Type;
+use nu_errors::ShellError;
+use nu_protocol::{Primitive, ShellTypeName, UntaggedValue, Value};
+use nu_source::Tag;
+use bigdecimal::{BigDecimal, Zero};
+use num_bigint::BigInt;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Column(String),
+    Int(BigInt),
+    Decimal(BigDecimal),
+    Str(String),
+    Bool(bool),
+    Null,
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn columns<'a>(&'a self, columns: &mut Vec<&'a str>) {
+        match self {
+            Expr::Column(name) => columns.push(name),
+            Expr::Neg(e) | Expr::Not(e) => e.columns(columns),
+            Expr::Binary(_, a, b) => {
+                a.columns(columns);
+                b.columns(columns);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(BigInt),
+    Decimal(BigDecimal),
+    Str(String),
+    Op(&'static str),
+}
+
+/// Splits an expression into tokens along with the position of their first character.
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, String> {
+    const OPS: &[&str] = &["==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "(", ")", "="];
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (at, c) = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '\'' || c == '"' || c == '`' {
+            let end = chars[i + 1..].iter().position(|(_, ch)| *ch == c)
+                .ok_or_else(|| format!("unterminated {} at position {}", if c == '`' { "column name" } else { "string" }, at))?;
+            let content: String = chars[i + 1..i + 1 + end].iter().map(|(_, ch)| ch).collect();
+            tokens.push((at, if c == '`' { Token::Ident(content) } else { Token::Str(content) }));
+            i += end + 2;
+        } else if c.is_ascii_digit() {
+            let len = chars[i..].iter().take_while(|(_, ch)| ch.is_ascii_digit() || *ch == '.').count();
+            let number: String = chars[i..i + len].iter().map(|(_, ch)| ch).collect();
+            let token = if number.contains('.') {
+                BigDecimal::from_str(&number).map(Token::Decimal).map_err(|_| format!("invalid number '{}' at position {}", number, at))?
+            } else {
+                Token::Int(BigInt::from_str(&number).map_err(|_| format!("invalid number '{}' at position {}", number, at))?)
+            };
+            tokens.push((at, token));
+            i += len;
+        } else if c.is_alphabetic() || c == '_' {
+            let len = chars[i..].iter().take_while(|(_, ch)| ch.is_alphanumeric() || *ch == '_' || *ch == '.').count();
+            tokens.push((at, Token::Ident(chars[i..i + len].iter().map(|(_, ch)| ch).collect())));
+            i += len;
+        } else {
+            let rest = &text[at..];
+            let op = OPS.iter().find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("unexpected '{}' at position {}", c, at))?;
+            tokens.push((at, Token::Op(op)));
+            i += op.chars().count();
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser, from the loosest binding operator to the tightest:
+/// `or`, `and`, comparisons, `+ -`, `* / %` and the unary `-` and `not`.
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn at(&self) -> usize {
+        self.tokens.get(self.position).map_or(self.end, |(at, _)| *at)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat_op(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.position += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(name)) if name.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.eat_keyword("or") || self.eat_op(&["||"]).is_some() {
+            left = Expr::Binary(BinaryOp::Or, Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.comparison()?;
+        while self.eat_keyword("and") || self.eat_op(&["&&"]).is_some() {
+            left = Expr::Binary(BinaryOp::And, Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        let left = self.sum()?;
+        let op = match self.eat_op(&["==", "!=", "<=", ">=", "<", ">"]) {
+            Some("==") => BinaryOp::Eq,
+            Some("!=") => BinaryOp::Ne,
+            Some("<=") => BinaryOp::Le,
+            Some(">=") => BinaryOp::Ge,
+            Some("<") => BinaryOp::Lt,
+            Some(_) => BinaryOp::Gt,
+            None => return Ok(left),
+        };
+        Ok(Expr::Binary(op, Box::new(left), Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, String> {
+        let mut left = self.product()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            let op = if op == "+" { BinaryOp::Add } else { BinaryOp::Sub };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.eat_op(&["*", "/", "%"]) {
+            let op = match op {
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Rem,
+            };
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat_op(&["-"]).is_some() {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat_keyword("not") || self.eat_op(&["!"]).is_some() {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let at = self.at();
+        match self.next() {
+            Some(Token::Int(i)) => Ok(Expr::Int(i)),
+            Some(Token::Decimal(d)) => Ok(Expr::Decimal(d)),
+            Some(Token::Str(s)) => Ok(Expr::Str(s)),
+            Some(Token::Ident(name)) => Ok(match name.to_ascii_lowercase().as_str() {
+                "true" => Expr::Bool(true),
+                "false" => Expr::Bool(false),
+                "null" => Expr::Null,
+                _ => Expr::Column(name),
+            }),
+            Some(Token::Op("(")) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Op(")")) => Ok(expr),
+                    _ => Err(format!("expected ')' at position {}", self.tokens.get(self.position - 1).map_or(self.end, |(at, _)| *at))),
+                }
+            }
+            _ => Err(format!("expected a column, literal or '(' at position {}", at)),
+        }
+    }
+}
+
+/// A `--select` computed column, eg `full_name = first_name + ' ' + last_name`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Select {
+    /// Name of the computed column.
+    pub name: String,
+    expr: Expr,
+}
+
+impl Select {
+    /// Parses `<name> = <expression>`. Errors name the position of the offending
+    /// character in `text`.
+    pub fn parse(text: &str) -> Result<Select, String> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, position: 0, end: text.len() };
+        let name = match parser.next() {
+            Some(Token::Ident(name)) => name,
+            _ => return Err("expected <column> = <expression> at position 0".to_string()),
+        };
+        if parser.eat_op(&["="]).is_none() {
+            return Err(format!("expected '=' after the column name at position {}", parser.at()));
+        }
+        let expr = parser.or()?;
+        if parser.peek().is_some() {
+            return Err(format!("unexpected input at position {}", parser.at()));
+        }
+        Ok(Select { name, expr })
+    }
+
+    /// Checks that the expression only refers to top-level columns of the schema or to
+    /// the columns of the selects before it.
+    pub fn check(&self, schema: &Type, earlier: &[Select], tag: &Tag) -> Result<(), ShellError> {
+        let mut columns = Vec::new();
+        self.expr.columns(&mut columns);
+        let known = |column: &&str| {
+            schema.get_fields().iter().any(|f| f.name() == *column) || earlier.iter().any(|s| s.name == *column)
+        };
+        if let Some(column) = columns.iter().find(|column| !known(column)) {
+            let available: Vec<&str> = schema.get_fields().iter().map(|f| f.name()).collect();
+            return Err(ShellError::labeled_error(
+                format!("Unknown column '{}' in --select '{}'", column, self.name),
+                format!("available columns are: {}", available.join(", ")),
+                tag,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Evaluates the expression against a converted record and appends its result,
+    /// returning why it cannot be evaluated otherwise.
+    pub fn apply(&self, record: &mut Value) -> Result<(), String> {
+        let tag = record.tag.clone();
+        let dict = match &mut record.value {
+            UntaggedValue::Row(dict) => dict,
+            _ => return Ok(()),
+        };
+        let value = eval(&self.expr, &|column| dict.entries.get(column))?;
+        dict.entries.insert(self.name.clone(), value.into_value(tag));
+        Ok(())
+    }
+}
+
+fn as_decimal(primitive: &Primitive) -> Option<BigDecimal> {
+    match primitive {
+        Primitive::Int(i) => Some(BigDecimal::from(i.clone())),
+        Primitive::Decimal(d) => Some(d.clone()),
+        _ => None,
+    }
+}
+
+fn compare(a: &Primitive, b: &Primitive) -> Option<Ordering> {
+    match (a, b) {
+        (Primitive::Int(a), Primitive::Int(b)) => Some(a.cmp(b)),
+        (Primitive::String(a), Primitive::String(b)) => Some(a.cmp(b)),
+        (Primitive::Boolean(a), Primitive::Boolean(b)) => Some(a.cmp(b)),
+        (Primitive::Date(a), Primitive::Date(b)) => Some(a.cmp(b)),
+        (a, b) => as_decimal(a)?.partial_cmp(&as_decimal(b)?),
+    }
+}
+
+fn text(primitive: &Primitive) -> Option<String> {
+    match primitive {
+        Primitive::String(s) => Some(s.clone()),
+        Primitive::Int(i) => Some(i.to_string()),
+        Primitive::Decimal(d) => Some(d.to_string()),
+        Primitive::Boolean(b) => Some(b.to_string()),
+        Primitive::Date(d) => Some(d.to_rfc3339()),
+        _ => None,
+    }
+}
+
+fn arithmetic(op: BinaryOp, a: &Primitive, b: &Primitive) -> Result<Option<UntaggedValue>, String> {
+    if let (Primitive::Int(a), Primitive::Int(b)) = (a, b) {
+        let value = match op {
+            BinaryOp::Add => a + b,
+            BinaryOp::Sub => a - b,
+            BinaryOp::Mul => a * b,
+            BinaryOp::Rem if b.is_zero() => return Err("division by zero".to_string()),
+            BinaryOp::Rem => a % b,
+            // Integer division stays an integer only when exact.
+            BinaryOp::Div if b.is_zero() => return Err("division by zero".to_string()),
+            BinaryOp::Div if (a % b).is_zero() => a / b,
+            _ => return arithmetic(op, &Primitive::Decimal(BigDecimal::from(a.clone())), &Primitive::Decimal(BigDecimal::from(b.clone()))),
+        };
+        return Ok(Some(UntaggedValue::int(value)));
+    }
+    let (a, b) = match (as_decimal(a), as_decimal(b)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Ok(None),
+    };
+    let value = match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div | BinaryOp::Rem if b.is_zero() => return Err("division by zero".to_string()),
+        BinaryOp::Div => a / b,
+        _ => a % b,
+    };
+    Ok(Some(UntaggedValue::decimal(value)))
+}
+
+/// Evaluates an expression. Nulls propagate through every operator.
+fn eval<'a>(expr: &Expr, column: &dyn Fn(&str) -> Option<&'a Value>) -> Result<UntaggedValue, String> {
+    let value = match expr {
+        Expr::Column(name) => match column(name) {
+            Some(value) => value.value.clone(),
+            None => UntaggedValue::nothing(),
+        },
+        Expr::Int(i) => UntaggedValue::int(i.clone()),
+        Expr::Decimal(d) => UntaggedValue::decimal(d.clone()),
+        Expr::Str(s) => UntaggedValue::string(s),
+        Expr::Bool(b) => UntaggedValue::boolean(*b),
+        Expr::Null => UntaggedValue::nothing(),
+        Expr::Neg(e) => match eval(e, column)? {
+            UntaggedValue::Primitive(Primitive::Int(i)) => UntaggedValue::int(-i),
+            UntaggedValue::Primitive(Primitive::Decimal(d)) => UntaggedValue::decimal(-d),
+            UntaggedValue::Primitive(Primitive::Nothing) => UntaggedValue::nothing(),
+            other => return Err(format!("cannot negate {}", other.type_name())),
+        },
+        Expr::Not(e) => match eval(e, column)? {
+            UntaggedValue::Primitive(Primitive::Boolean(b)) => UntaggedValue::boolean(!b),
+            UntaggedValue::Primitive(Primitive::Nothing) => UntaggedValue::nothing(),
+            other => return Err(format!("cannot apply not to {}", other.type_name())),
+        },
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (eval(a, column)?, eval(b, column)?);
+            let (pa, pb) = match (&a, &b) {
+                (UntaggedValue::Primitive(Primitive::Nothing), _) | (_, UntaggedValue::Primitive(Primitive::Nothing)) => {
+                    return Ok(UntaggedValue::nothing())
+                }
+                (UntaggedValue::Primitive(pa), UntaggedValue::Primitive(pb)) => (pa, pb),
+                _ => return Err(format!("cannot apply {} to {} and {}", op.symbol(), a.type_name(), b.type_name())),
+            };
+            let mismatch = || format!("cannot apply {} to {} and {}", op.symbol(), a.type_name(), b.type_name());
+            match op {
+                // With a string on either side `+` concatenates.
+                BinaryOp::Add if matches!(pa, Primitive::String(_)) || matches!(pb, Primitive::String(_)) => {
+                    match (text(pa), text(pb)) {
+                        (Some(a), Some(b)) => UntaggedValue::string(a + &b),
+                        _ => return Err(mismatch()),
+                    }
+                }
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => {
+                    arithmetic(*op, pa, pb)?.ok_or_else(mismatch)?
+                }
+                BinaryOp::And | BinaryOp::Or => match (pa, pb) {
+                    (Primitive::Boolean(a), Primitive::Boolean(b)) => {
+                        UntaggedValue::boolean(if *op == BinaryOp::And { *a && *b } else { *a || *b })
+                    }
+                    _ => return Err(mismatch()),
+                },
+                _ => {
+                    let ordering = compare(pa, pb).ok_or_else(mismatch)?;
+                    UntaggedValue::boolean(match op {
+                        BinaryOp::Eq => ordering == Ordering::Equal,
+                        BinaryOp::Ne => ordering != Ordering::Equal,
+                        BinaryOp::Lt => ordering == Ordering::Less,
+                        BinaryOp::Le => ordering != Ordering::Greater,
+                        BinaryOp::Gt => ordering == Ordering::Greater,
+                        _ => ordering != Ordering::Less,
+                    })
+                }
+            }
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nu_protocol::TaggedDictBuilder;
+    use parquet::schema::parser::parse_message_type;
+
+    fn parse(text: &str) -> Expr {
+        Select::parse(&format!("x = {}", text)).unwrap().expr
+    }
+
+    fn column(name: &str) -> Box<Expr> {
+        Box::new(Expr::Column(name.to_string()))
+    }
+
+    fn int(i: i64) -> Box<Expr> {
+        Box::new(Expr::Int(BigInt::from(i)))
+    }
+
+    /// Evaluates `text` against a record holding `a = 7`, `b = 2`, `s = 'ab'` and `n = null`.
+    fn eval_text(text: &str) -> Result<UntaggedValue, String> {
+        let mut record = TaggedDictBuilder::new(Tag::unknown());
+        record.insert_untagged("a", UntaggedValue::int(7));
+        record.insert_untagged("b", UntaggedValue::int(2));
+        record.insert_untagged("s", UntaggedValue::string("ab"));
+        record.insert_untagged("n", UntaggedValue::nothing());
+        let mut record = record.into_value();
+        Select::parse(&format!("x = {}", text))?.apply(&mut record)?;
+        match record.value {
+            UntaggedValue::Row(dict) => Ok(dict.entries["x"].value.clone()),
+            other => panic!("expected a row, got {:?}", other),
+        }
+    }
+
+    fn decimal(text: &str) -> UntaggedValue {
+        UntaggedValue::decimal(BigDecimal::from_str(text).unwrap())
+    }
+
+    #[test]
+    fn tokenizes_with_positions() {
+        let tokens = tokenize("a.b >= 1.5 + `odd name` - 'x y'").unwrap();
+
+        assert_eq!(tokens, vec![
+            (0, Token::Ident("a.b".to_string())),
+            (4, Token::Op(">=")),
+            (7, Token::Decimal(BigDecimal::from_str("1.5").unwrap())),
+            (11, Token::Op("+")),
+            (13, Token::Ident("odd name".to_string())),
+            (24, Token::Op("-")),
+            (26, Token::Str("x y".to_string())),
+        ]);
+        assert_eq!(tokenize("a == 10").unwrap()[2], (5, Token::Int(BigInt::from(10))));
+    }
+
+    #[test]
+    fn rejects_bad_tokens() {
+        assert_eq!(tokenize("'abc"), Err("unterminated string at position 0".to_string()));
+        assert_eq!(tokenize("a + `b"), Err("unterminated column name at position 4".to_string()));
+        assert_eq!(tokenize("1.2.3"), Err("invalid number '1.2.3' at position 0".to_string()));
+        assert_eq!(tokenize("a # b"), Err("unexpected '#' at position 2".to_string()));
+    }
+
+    #[test]
+    fn binds_operators_by_precedence() {
+        assert_eq!(
+            parse("a + b * 2 == 11 or not c and d"),
+            Expr::Binary(
+                BinaryOp::Or,
+                Box::new(Expr::Binary(
+                    BinaryOp::Eq,
+                    Box::new(Expr::Binary(BinaryOp::Add, column("a"), Box::new(Expr::Binary(BinaryOp::Mul, column("b"), int(2))))),
+                    int(11),
+                )),
+                Box::new(Expr::Binary(BinaryOp::And, Box::new(Expr::Not(column("c"))), column("d"))),
+            )
+        );
+        assert_eq!(parse("a - b - 1"), Expr::Binary(BinaryOp::Sub, Box::new(Expr::Binary(BinaryOp::Sub, column("a"), column("b"))), int(1)));
+        assert_eq!(parse("(a + b) * 2"), Expr::Binary(BinaryOp::Mul, Box::new(Expr::Binary(BinaryOp::Add, column("a"), column("b"))), int(2)));
+        assert_eq!(parse("-a % 3"), Expr::Binary(BinaryOp::Rem, Box::new(Expr::Neg(column("a"))), int(3)));
+        assert_eq!(parse("a || b && c"), parse("a or (b and c)"));
+        assert_eq!(parse("NULL"), Expr::Null);
+        assert_eq!(parse("True"), Expr::Bool(true));
+    }
+
+    #[test]
+    fn names_the_position_of_parse_errors() {
+        assert_eq!(Select::parse("= a"), Err("expected <column> = <expression> at position 0".to_string()));
+        assert_eq!(Select::parse("x a"), Err("expected '=' after the column name at position 2".to_string()));
+        assert_eq!(Select::parse("x = a +"), Err("expected a column, literal or '(' at position 7".to_string()));
+        assert_eq!(Select::parse("x = (a + b"), Err("expected ')' at position 10".to_string()));
+        assert_eq!(Select::parse("x = a b"), Err("unexpected input at position 6".to_string()));
+    }
+
+    #[test]
+    fn propagates_nulls() {
+        assert_eq!(eval_text("n + 1"), Ok(UntaggedValue::nothing()));
+        assert_eq!(eval_text("s + n"), Ok(UntaggedValue::nothing()));
+        assert_eq!(eval_text("-n"), Ok(UntaggedValue::nothing()));
+        assert_eq!(eval_text("not n"), Ok(UntaggedValue::nothing()));
+        assert_eq!(eval_text("n == null"), Ok(UntaggedValue::nothing()));
+        assert_eq!(eval_text("missing * 2"), Ok(UntaggedValue::nothing()));
+        assert_eq!(eval_text("n / 0"), Ok(UntaggedValue::nothing()));
+    }
+
+    #[test]
+    fn divides_integers_exactly_or_as_decimals() {
+        assert_eq!(eval_text("a * b - 4"), Ok(UntaggedValue::int(10)));
+        assert_eq!(eval_text("8 / b"), Ok(UntaggedValue::int(4)));
+        assert_eq!(eval_text("a / b"), Ok(decimal("3.5")));
+        assert_eq!(eval_text("a % b"), Ok(UntaggedValue::int(1)));
+        assert_eq!(eval_text("a + 0.5"), Ok(decimal("7.5")));
+        assert_eq!(eval_text("a / 0"), Err("division by zero".to_string()));
+        assert_eq!(eval_text("a % 0"), Err("division by zero".to_string()));
+        assert_eq!(eval_text("a / 0.0"), Err("division by zero".to_string()));
+    }
+
+    #[test]
+    fn concatenates_strings() {
+        assert_eq!(eval_text("s + '-' + a"), Ok(UntaggedValue::string("ab-7")));
+        assert_eq!(eval_text("1.5 + s"), Ok(UntaggedValue::string("1.5ab")));
+        assert_eq!(eval_text("s + true"), Ok(UntaggedValue::string("abtrue")));
+        assert_eq!(eval_text("s - 'b'"), Err("cannot apply - to string and string".to_string()));
+    }
+
+    #[test]
+    fn compares_and_combines() {
+        assert_eq!(eval_text("a > b and s == 'ab'"), Ok(UntaggedValue::boolean(true)));
+        assert_eq!(eval_text("a <= 6.5 or !(b == 2)"), Ok(UntaggedValue::boolean(false)));
+        assert_eq!(eval_text("a and b"), Err("cannot apply and to integer and integer".to_string()));
+        assert_eq!(eval_text("not a"), Err("cannot apply not to integer".to_string()));
+        assert_eq!(eval_text("-s"), Err("cannot negate string".to_string()));
+    }
+
+    #[test]
+    fn checks_columns_against_the_schema_and_earlier_selects() {
+        let schema = parse_message_type("message schema { required int32 a; optional group g { required int32 b; } }").unwrap();
+        let total = Select::parse("total = a * 2").unwrap();
+
+        assert!(total.check(&schema, &[], &Tag::unknown()).is_ok());
+        assert!(Select::parse("y = g + 1").unwrap().check(&schema, &[], &Tag::unknown()).is_ok());
+        assert!(Select::parse("y = total + 1").unwrap().check(&schema, std::slice::from_ref(&total), &Tag::unknown()).is_ok());
+        assert!(Select::parse("y = total + 1").unwrap().check(&schema, &[], &Tag::unknown()).is_err());
+        assert!(Select::parse("y = b + 1").unwrap().check(&schema, &[total], &Tag::unknown()).is_err());
+    }
+}
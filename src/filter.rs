@@ -0,0 +1,312 @@
+use parquet::basic::{LogicalType, Type as PhysicalType};
+use parquet::file::metadata::RowGroupMetaData;
+use parquet::file::statistics::Statistics;
+use parquet::record::{Field, Row};
+use parquet::schema::types::Type;
+use parquet_format::RowGroup as ThriftRowGroup;
+use nu_errors::ShellError;
+use nu_source::Tag;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Comparison a `--filter` applies to a column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterOp {
+    /// `eq`, equal to the literal.
+    Eq,
+    /// `ne`, not equal to the literal.
+    Ne,
+    /// `lt`, less than the literal.
+    Lt,
+    /// `gt`, greater than the literal.
+    Gt,
+    /// `le`, at most the literal.
+    Le,
+    /// `ge`, at least the literal.
+    Ge,
+    /// `is_null`, the column is null.
+    IsNull,
+    /// `not_null`, the column holds a value.
+    NotNull,
+}
+
+impl FilterOp {
+    /// Looks up an operator by the name `--filter` takes, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "eq" => Some(FilterOp::Eq),
+            "ne" => Some(FilterOp::Ne),
+            "lt" => Some(FilterOp::Lt),
+            "gt" => Some(FilterOp::Gt),
+            "le" => Some(FilterOp::Le),
+            "ge" => Some(FilterOp::Ge),
+            "is_null" => Some(FilterOp::IsNull),
+            "not_null" => Some(FilterOp::NotNull),
+            _ => None,
+        }
+    }
+
+    fn takes_literal(self) -> bool {
+        !matches!(self, FilterOp::IsNull | FilterOp::NotNull)
+    }
+
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            FilterOp::Eq => ordering == Ordering::Equal,
+            FilterOp::Ne => ordering != Ordering::Equal,
+            FilterOp::Lt => ordering == Ordering::Less,
+            FilterOp::Gt => ordering == Ordering::Greater,
+            FilterOp::Le => ordering != Ordering::Greater,
+            FilterOp::Ge => ordering != Ordering::Less,
+            FilterOp::IsNull | FilterOp::NotNull => false,
+        }
+    }
+}
+
+/// The value a column is compared with. Quoted literals are always strings, so that
+/// `"42"` can be compared with a string column.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Literal {
+    /// `true` or `false`.
+    Bool(bool),
+    /// A whole number.
+    Int(i128),
+    /// A finite number with a fraction or an exponent.
+    Float(f64),
+    /// A quoted literal, or one that is none of the others.
+    Str(String),
+}
+
+impl Literal {
+    fn parse(text: &str) -> Literal {
+        let quoted = text.len() >= 2
+            && (text.starts_with('"') && text.ends_with('"') || text.starts_with('\'') && text.ends_with('\''));
+        if quoted {
+            return Literal::Str(text[1..text.len() - 1].to_string());
+        }
+        if let Ok(b) = text.parse::<bool>() {
+            return Literal::Bool(b);
+        }
+        if let Ok(i) = text.parse::<i128>() {
+            return Literal::Int(i);
+        }
+        match text.parse::<f64>() {
+            Ok(f) if f.is_finite() => Literal::Float(f),
+            _ => Literal::Str(text.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::Bool(b) => write!(f, "{}", b),
+            Literal::Int(i) => write!(f, "{}", i),
+            Literal::Float(x) => write!(f, "{}", x),
+            Literal::Str(s) => write!(f, "'{}'", s),
+        }
+    }
+}
+
+/// A value read from a row or from the statistics of a column chunk.
+enum Scalar<'a> {
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(&'a str),
+}
+
+fn compare(value: &Scalar, literal: &Literal) -> Option<Ordering> {
+    match (value, literal) {
+        (Scalar::Bool(a), Literal::Bool(b)) => Some(a.cmp(b)),
+        (Scalar::Int(a), Literal::Int(b)) => Some(a.cmp(b)),
+        (Scalar::Int(a), Literal::Float(b)) => (*a as f64).partial_cmp(b),
+        (Scalar::Float(a), Literal::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Scalar::Float(a), Literal::Float(b)) => a.partial_cmp(b),
+        (Scalar::Str(a), Literal::Str(b)) => Some((*a).cmp(b.as_str())),
+        _ => None,
+    }
+}
+
+fn field_scalar(field: &Field) -> Option<Scalar<'_>> {
+    match field {
+        Field::Bool(b) => Some(Scalar::Bool(*b)),
+        Field::Byte(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::Short(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::Int(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::Long(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::UByte(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::UShort(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::UInt(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::ULong(i) => Some(Scalar::Int(i128::from(*i))),
+        Field::Float(f) => Some(Scalar::Float(f64::from(*f))),
+        Field::Double(f) => Some(Scalar::Float(*f)),
+        Field::Str(s) => Some(Scalar::Str(s)),
+        // String columns read by --validate-utf8.
+        Field::Bytes(bytes) => std::str::from_utf8(bytes.data()).ok().map(Scalar::Str),
+        _ => None,
+    }
+}
+
+/// A `--filter` predicate on a top-level column, eg `age gt 30` or `name is_null`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Predicate {
+    /// Top-level column the predicate tests.
+    pub column: String,
+    /// Comparison applied to the column.
+    pub op: FilterOp,
+    /// Value the column is compared with, `None` for `is_null` and `not_null`.
+    pub literal: Option<Literal>,
+}
+
+impl Predicate {
+    /// Parses `<column> <op> <literal>`, the literal is left out for `is_null` and
+    /// `not_null` and may contain spaces.
+    pub fn parse(text: &str) -> Result<Predicate, String> {
+        let text = text.trim();
+        let mut parts = text.splitn(3, char::is_whitespace);
+        let column = parts.next().filter(|c| !c.is_empty())
+            .ok_or_else(|| "expected <column> <op> <value>".to_string())?;
+        let op_name = parts.next().ok_or_else(|| "expected an operator after the column".to_string())?;
+        let op = FilterOp::from_name(op_name).ok_or_else(|| {
+            format!("unknown operator '{}', expected eq, ne, lt, gt, le, ge, is_null or not_null", op_name)
+        })?;
+        let literal = parts.next().map(str::trim).filter(|l| !l.is_empty());
+        let literal = match (op.takes_literal(), literal) {
+            (true, Some(literal)) => Some(Literal::parse(literal)),
+            (true, None) => return Err(format!("'{}' needs a value to compare with", op_name)),
+            (false, None) => None,
+            (false, Some(_)) => return Err(format!("'{}' does not take a value", op_name)),
+        };
+        Ok(Predicate { column: column.to_string(), op, literal })
+    }
+
+    /// Checks that the column is a top-level column of the schema whose values can be
+    /// compared with the literal.
+    pub fn check(&self, schema: &Type, tag: &Tag) -> Result<(), ShellError> {
+        let column = schema.get_fields().iter().find(|f| f.name() == self.column);
+        let column = match column {
+            Some(column) => column,
+            None => {
+                let available: Vec<&str> = schema.get_fields().iter().map(|f| f.name()).collect();
+                return Err(ShellError::labeled_error(
+                    format!("Unknown filter column '{}'", self.column),
+                    format!("available columns are: {}", available.join(", ")),
+                    tag,
+                ));
+            }
+        };
+        let literal = match &self.literal {
+            Some(literal) => literal,
+            None => return Ok(()),
+        };
+        let comparable = column.is_primitive() && match (column.get_physical_type(), column.get_basic_info().logical_type(), literal) {
+            (PhysicalType::BOOLEAN, _, Literal::Bool(_)) => true,
+            (PhysicalType::INT32, logical, Literal::Int(_) | Literal::Float(_))
+            | (PhysicalType::INT64, logical, Literal::Int(_) | Literal::Float(_)) => is_integer(logical),
+            (PhysicalType::FLOAT, _, Literal::Int(_) | Literal::Float(_))
+            | (PhysicalType::DOUBLE, _, Literal::Int(_) | Literal::Float(_)) => true,
+            (PhysicalType::BYTE_ARRAY, LogicalType::UTF8, Literal::Str(_))
+            | (PhysicalType::BYTE_ARRAY, LogicalType::ENUM, Literal::Str(_))
+            | (PhysicalType::BYTE_ARRAY, LogicalType::JSON, Literal::Str(_)) => true,
+            _ => false,
+        };
+        if comparable {
+            return Ok(());
+        }
+        Err(ShellError::labeled_error(
+            format!("Cannot compare column '{}' with {}", self.column, literal),
+            "only boolean, integer, float and string columns can be compared with a value of their type",
+            tag,
+        ))
+    }
+
+    /// Whether a decoded row satisfies the predicate. Nulls only match `is_null`.
+    pub fn matches(&self, row: &Row) -> bool {
+        let field = row.get_column_iter().find(|(name, _)| **name == self.column).map(|(_, field)| field);
+        match (self.op, field) {
+            (FilterOp::IsNull, field) => matches!(field, None | Some(Field::Null)),
+            (FilterOp::NotNull, field) => !matches!(field, None | Some(Field::Null)),
+            (op, Some(field)) => match (field_scalar(field), &self.literal) {
+                (Some(value), Some(literal)) => compare(&value, literal).is_some_and(|o| op.accepts(o)),
+                _ => false,
+            },
+            (_, None) => false,
+        }
+    }
+
+    /// Whether any row of the group can satisfy the predicate according to the
+    /// statistics of its column chunk. Groups without usable statistics may match.
+    /// `footer` is the thrift of the row group, it tells a null count of 0 apart from
+    /// one that was not written, without it `is_null` never rules a group out.
+    pub fn may_match(&self, row_group: &RowGroupMetaData, footer: Option<&ThriftRowGroup>) -> bool {
+        let index = row_group.columns().iter().position(|c| {
+            let descr = c.column_descr();
+            descr.path().string() == self.column && descr.self_type().name() == self.column
+        });
+        let (index, column) = match index {
+            Some(index) => (index, row_group.column(index)),
+            None => return true,
+        };
+        let statistics = match column.statistics() {
+            Some(statistics) => statistics,
+            None => return true,
+        };
+        match self.op {
+            // parquet 3.0 reads a null count missing from the statistics as 0.
+            FilterOp::IsNull => {
+                let written = footer
+                    .and_then(|footer| footer.columns.get(index))
+                    .and_then(|chunk| chunk.meta_data.as_ref())
+                    .and_then(|meta| meta.statistics.as_ref())
+                    .is_some_and(|statistics| statistics.null_count.is_some());
+                return !written || statistics.has_nulls();
+            }
+            FilterOp::NotNull => return (statistics.null_count() as i64) < row_group.num_rows(),
+            _ => {}
+        }
+        // Unsigned integers and byte arrays were sorted as signed bytes by older
+        // writers, their bounds cannot be trusted.
+        if !statistics.has_min_max_set() || !is_signed(column.column_descr().logical_type()) {
+            return true;
+        }
+        let (min, max) = match statistics {
+            Statistics::Boolean(s) => (Scalar::Bool(*s.min()), Scalar::Bool(*s.max())),
+            Statistics::Int32(s) => (Scalar::Int(i128::from(*s.min())), Scalar::Int(i128::from(*s.max()))),
+            Statistics::Int64(s) => (Scalar::Int(i128::from(*s.min())), Scalar::Int(i128::from(*s.max()))),
+            Statistics::Float(s) => (Scalar::Float(f64::from(*s.min())), Scalar::Float(f64::from(*s.max()))),
+            Statistics::Double(s) => (Scalar::Float(*s.min()), Scalar::Float(*s.max())),
+            _ => return true,
+        };
+        let literal = match &self.literal {
+            Some(literal) => literal,
+            None => return true,
+        };
+        let (min, max) = match (compare(&min, literal), compare(&max, literal)) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return true,
+        };
+        match self.op {
+            FilterOp::Eq => min != Ordering::Greater && max != Ordering::Less,
+            FilterOp::Ne => !(min == Ordering::Equal && max == Ordering::Equal),
+            FilterOp::Lt => min == Ordering::Less,
+            FilterOp::Le => min != Ordering::Greater,
+            FilterOp::Gt => max == Ordering::Greater,
+            FilterOp::Ge => max != Ordering::Less,
+            FilterOp::IsNull | FilterOp::NotNull => true,
+        }
+    }
+}
+
+/// Integer columns without an annotation that turns them into dates, times or decimals.
+fn is_integer(logical: LogicalType) -> bool {
+    is_signed(logical)
+        || matches!(logical, LogicalType::UINT_8 | LogicalType::UINT_16 | LogicalType::UINT_32 | LogicalType::UINT_64)
+}
+
+fn is_signed(logical: LogicalType) -> bool {
+    matches!(
+        logical,
+        LogicalType::NONE | LogicalType::INT_8 | LogicalType::INT_16 | LogicalType::INT_32 | LogicalType::INT_64
+    )
+}
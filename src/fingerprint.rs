@@ -0,0 +1,52 @@
+use parquet::file::reader::FileReader;
+use parquet::schema::printer::print_schema;
+use parquet::schema::types::Type;
+use nu_errors::ShellError;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use sha2::{Digest, Sha256};
+use crate::open_reader;
+
+/// SHA-256 of `data`, as lowercase hex.
+pub(crate) fn sha256(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// The schema text parquet prints for a column, its canonical representation.
+pub(crate) fn canonical(ty: &Type) -> String {
+    let mut out = Vec::new();
+    print_schema(&mut out, ty);
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Hashes the schema of a Parquet file: the SHA-256 of the canonical text of its
+/// top-level columns, in order, and of each column on its own. The name of the root
+/// message is left out, it differs between writers without changing what is stored.
+pub fn parquet_fingerprint(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+    let fields = reader.metadata().file_metadata().schema().get_fields();
+
+    let texts: Vec<String> = fields.iter().map(|field| canonical(field)).collect();
+    let mut columns = TaggedDictBuilder::new(&tag);
+    for (field, text) in fields.iter().zip(&texts) {
+        columns.insert_untagged(field.name(), UntaggedValue::string(sha256(text.as_bytes())));
+    }
+
+    let mut dict = TaggedDictBuilder::new(&tag);
+    dict.insert_untagged("schema_hash", UntaggedValue::string(sha256(texts.join("\n").as_bytes())));
+    dict.insert_untagged("column_count", UntaggedValue::int(fields.len() as u64));
+    dict.insert_value("column_fingerprints", columns.into_value());
+    Ok(dict.into_value())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_the_known_answers() {
+        assert_eq!(sha256(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
+}
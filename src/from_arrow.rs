@@ -0,0 +1,239 @@
+use arrow::array::*;
+use arrow::datatypes::*;
+use arrow::error::ArrowError;
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::record_batch::RecordBatch;
+use nu_errors::ShellError;
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+use chrono::{DateTime, FixedOffset, Offset};
+use chrono_tz::Tz;
+use bigdecimal::{BigDecimal, FromPrimitive};
+use indexmap::IndexMap;
+use std::io::Cursor;
+use crate::convert::{
+    date_or_nothing, days_to_date, decimal_from_unscaled, interval_to_record, time_of_day,
+    timestamp_to_date, TimeUnit,
+};
+
+/// Arrow IPC files start with this magic, streams start with a schema message.
+const FILE_MAGIC: &[u8] = b"ARROW1";
+
+fn arrow_error(msg: &str, error: ArrowError, tag: &Tag) -> ShellError {
+    ShellError::labeled_error(msg, error.to_string(), tag)
+}
+
+fn downcast<T: 'static>(array: &ArrayRef) -> &T {
+    array.as_any()
+        .downcast_ref::<T>()
+        .expect("Internal error: array does not match its data type")
+}
+
+fn time_unit(unit: &arrow::datatypes::TimeUnit) -> TimeUnit {
+    match unit {
+        arrow::datatypes::TimeUnit::Second => TimeUnit::Seconds,
+        arrow::datatypes::TimeUnit::Millisecond => TimeUnit::Millis,
+        arrow::datatypes::TimeUnit::Microsecond => TimeUnit::Micros,
+        arrow::datatypes::TimeUnit::Nanosecond => TimeUnit::Nanos,
+    }
+}
+
+/// Reads an `i64` out of any of the arrays holding integer timestamps, times or durations.
+fn int64_value(array: &ArrayRef, row: usize) -> i64 {
+    use arrow::datatypes::TimeUnit::*;
+    match array.data_type() {
+        DataType::Timestamp(Second, _) => downcast::<TimestampSecondArray>(array).value(row),
+        DataType::Timestamp(Millisecond, _) => downcast::<TimestampMillisecondArray>(array).value(row),
+        DataType::Timestamp(Microsecond, _) => downcast::<TimestampMicrosecondArray>(array).value(row),
+        DataType::Timestamp(Nanosecond, _) => downcast::<TimestampNanosecondArray>(array).value(row),
+        DataType::Time32(Second) => i64::from(downcast::<Time32SecondArray>(array).value(row)),
+        DataType::Time32(_) => i64::from(downcast::<Time32MillisecondArray>(array).value(row)),
+        DataType::Time64(Microsecond) => downcast::<Time64MicrosecondArray>(array).value(row),
+        DataType::Time64(_) => downcast::<Time64NanosecondArray>(array).value(row),
+        DataType::Duration(Second) => downcast::<DurationSecondArray>(array).value(row),
+        DataType::Duration(Millisecond) => downcast::<DurationMillisecondArray>(array).value(row),
+        DataType::Duration(Microsecond) => downcast::<DurationMicrosecondArray>(array).value(row),
+        DataType::Duration(Nanosecond) => downcast::<DurationNanosecondArray>(array).value(row),
+        _ => unreachable!("Internal error: array does not hold 64-bit integers"),
+    }
+}
+
+/// Shows a UTC timestamp in the timezone of its column, which is either an IANA name
+/// or a fixed offset such as `+02:00`. Unknown timezones leave the timestamp in UTC.
+fn in_timezone(date: DateTime<FixedOffset>, timezone: &str) -> DateTime<FixedOffset> {
+    if let Ok(tz) = timezone.parse::<Tz>() {
+        let local = date.with_timezone(&tz);
+        return local.with_timezone(&local.offset().fix());
+    }
+    match DateTime::parse_from_str(&format!("1970-01-01 00:00:00 {}", timezone), "%Y-%m-%d %H:%M:%S %:z") {
+        Ok(offset) => date.with_timezone(offset.offset()),
+        Err(_) => date,
+    }
+}
+
+/// Why the value of an array at a row cannot be converted.
+enum ArrowValueError {
+    /// Nushell has no counterpart for values of this data type.
+    Unsupported(DataType),
+    /// A dictionary key, as written, that is negative or past the end of the dictionary
+    /// of this many values.
+    DictionaryKey(String, usize),
+}
+
+/// The key of a dictionary array at a row, checked against its dictionary, and the
+/// dictionary values.
+fn dictionary_key<K: ArrowPrimitiveType>(array: &ArrayRef, row: usize) -> Result<(usize, ArrayRef), ArrowValueError> {
+    let dictionary = downcast::<DictionaryArray<K>>(array);
+    let key = dictionary.keys().value(row);
+    let values = dictionary.values();
+    match key.to_usize() {
+        Some(index) if index < values.len() => Ok((index, values)),
+        _ => Err(ArrowValueError::DictionaryKey(format!("{:?}", key), values.len())),
+    }
+}
+
+fn list_to_nu(values: ArrayRef, tag: &Tag) -> Result<Value, ArrowValueError> {
+    let values = (0..values.len())
+        .map(|i| array_to_nu(&values, i, tag))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(UntaggedValue::Table(values).into_value(tag))
+}
+
+/// Converts the value of an array at a row. Arrays of types nushell has no counterpart
+/// for and invalid dictionary keys return why instead, so the caller can name the column.
+fn array_to_nu(array: &ArrayRef, row: usize, tag: &Tag) -> Result<Value, ArrowValueError> {
+    if array.is_null(row) {
+        return Ok(UntaggedValue::nothing().into_value(tag));
+    }
+    let value = match array.data_type() {
+        DataType::Null => UntaggedValue::nothing(),
+        DataType::Boolean => UntaggedValue::boolean(downcast::<BooleanArray>(array).value(row)),
+        DataType::Int8 => UntaggedValue::int(downcast::<Int8Array>(array).value(row)),
+        DataType::Int16 => UntaggedValue::int(downcast::<Int16Array>(array).value(row)),
+        DataType::Int32 => UntaggedValue::int(downcast::<Int32Array>(array).value(row)),
+        DataType::Int64 => UntaggedValue::int(downcast::<Int64Array>(array).value(row)),
+        DataType::UInt8 => UntaggedValue::int(downcast::<UInt8Array>(array).value(row)),
+        DataType::UInt16 => UntaggedValue::int(downcast::<UInt16Array>(array).value(row)),
+        DataType::UInt32 => UntaggedValue::int(downcast::<UInt32Array>(array).value(row)),
+        DataType::UInt64 => UntaggedValue::int(downcast::<UInt64Array>(array).value(row)),
+        // NaN and infinities have no decimal representation.
+        DataType::Float32 => BigDecimal::from_f32(downcast::<Float32Array>(array).value(row))
+            .map(UntaggedValue::decimal)
+            .unwrap_or_else(UntaggedValue::nothing),
+        DataType::Float64 => BigDecimal::from_f64(downcast::<Float64Array>(array).value(row))
+            .map(UntaggedValue::decimal)
+            .unwrap_or_else(UntaggedValue::nothing),
+        DataType::Decimal(_, scale) => {
+            let unscaled = downcast::<DecimalArray>(array).value(row);
+            UntaggedValue::decimal(decimal_from_unscaled(unscaled, *scale as i64))
+        }
+        DataType::Utf8 => UntaggedValue::string(downcast::<StringArray>(array).value(row)),
+        DataType::LargeUtf8 => UntaggedValue::string(downcast::<LargeStringArray>(array).value(row)),
+        DataType::Binary => UntaggedValue::binary(downcast::<BinaryArray>(array).value(row).to_vec()),
+        DataType::LargeBinary => UntaggedValue::binary(downcast::<LargeBinaryArray>(array).value(row).to_vec()),
+        DataType::FixedSizeBinary(_) => {
+            UntaggedValue::binary(downcast::<FixedSizeBinaryArray>(array).value(row).to_vec())
+        }
+        DataType::Date32(_) => {
+            date_or_nothing(days_to_date(i64::from(downcast::<Date32Array>(array).value(row))))
+        }
+        DataType::Date64(_) => {
+            date_or_nothing(timestamp_to_date(downcast::<Date64Array>(array).value(row), TimeUnit::Millis))
+        }
+        DataType::Timestamp(unit, timezone) => {
+            let date = timestamp_to_date(int64_value(array, row), time_unit(unit));
+            match timezone {
+                Some(timezone) => date_or_nothing(date.map(|date| in_timezone(date, timezone))),
+                None => date_or_nothing(date),
+            }
+        }
+        DataType::Time32(unit) | DataType::Time64(unit) => {
+            UntaggedValue::string(time_of_day(int64_value(array, row), time_unit(unit)))
+        }
+        DataType::Duration(unit) => {
+            let nanos = i128::from(int64_value(array, row)) * i128::from(1_000_000_000 / time_unit(unit).per_second());
+            UntaggedValue::duration(nanos)
+        }
+        DataType::Interval(IntervalUnit::YearMonth) => {
+            let months = downcast::<IntervalYearMonthArray>(array).value(row);
+            return Ok(interval_to_record(i64::from(months), 0, 0, tag.clone()));
+        }
+        DataType::Interval(IntervalUnit::DayTime) => {
+            // The days are in the high 32 bits and the milliseconds in the low ones.
+            let value = downcast::<IntervalDayTimeArray>(array).value(row);
+            return Ok(interval_to_record(0, value >> 32, i64::from(value as i32), tag.clone()));
+        }
+        DataType::List(_) => return list_to_nu(downcast::<ListArray>(array).value(row), tag),
+        DataType::LargeList(_) => return list_to_nu(downcast::<LargeListArray>(array).value(row), tag),
+        DataType::FixedSizeList(_, _) => return list_to_nu(downcast::<FixedSizeListArray>(array).value(row), tag),
+        DataType::Struct(fields) => {
+            let columns = downcast::<StructArray>(array);
+            let mut record = IndexMap::with_capacity(fields.len());
+            for (field, column) in fields.iter().zip(columns.columns()) {
+                record.insert(field.name().clone(), array_to_nu(column, row, tag)?);
+            }
+            UntaggedValue::row(record)
+        }
+        DataType::Dictionary(key_type, _) => {
+            let (key, values) = match key_type.as_ref() {
+                DataType::Int8 => dictionary_key::<Int8Type>(array, row)?,
+                DataType::Int16 => dictionary_key::<Int16Type>(array, row)?,
+                DataType::Int32 => dictionary_key::<Int32Type>(array, row)?,
+                DataType::Int64 => dictionary_key::<Int64Type>(array, row)?,
+                DataType::UInt8 => dictionary_key::<UInt8Type>(array, row)?,
+                DataType::UInt16 => dictionary_key::<UInt16Type>(array, row)?,
+                DataType::UInt32 => dictionary_key::<UInt32Type>(array, row)?,
+                DataType::UInt64 => dictionary_key::<UInt64Type>(array, row)?,
+                other => return Err(ArrowValueError::Unsupported(other.clone())),
+            };
+            return array_to_nu(&values, key, tag);
+        }
+        other => return Err(ArrowValueError::Unsupported(other.clone())),
+    };
+    Ok(value.into_value(tag))
+}
+
+fn convert_batch(batch: &RecordBatch, tag: &Tag, rows: &mut Vec<Value>) -> Result<(), ShellError> {
+    let schema = batch.schema();
+    for row in 0..batch.num_rows() {
+        let mut record = IndexMap::with_capacity(batch.num_columns());
+        for (field, column) in schema.fields().iter().zip(batch.columns()) {
+            let value = array_to_nu(column, row, tag).map_err(|error| match error {
+                ArrowValueError::Unsupported(data_type) => ShellError::labeled_error(
+                    format!("Cannot convert Arrow column '{}'", field.name()),
+                    format!("{:?} values are not supported", data_type),
+                    tag,
+                ),
+                ArrowValueError::DictionaryKey(key, len) => ShellError::labeled_error(
+                    format!("Invalid dictionary key in Arrow column '{}'", field.name()),
+                    format!("key {} in row {} is outside the dictionary of {} values", key, row, len),
+                    tag,
+                ),
+            })?;
+            record.insert(field.name().clone(), value);
+        }
+        rows.push(UntaggedValue::row(record).into_value(tag));
+    }
+    Ok(())
+}
+
+/// Converts Arrow IPC data, in either the file or the stream format, into a table.
+pub fn from_arrow_bytes(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let batches = if bytes.starts_with(FILE_MAGIC) {
+        FileReader::try_new(Cursor::new(bytes))
+            .map_err(|e| arrow_error("Failed to open Arrow file", e, &tag))?
+            .collect::<Result<Vec<_>, _>>()
+    } else {
+        StreamReader::try_new(Cursor::new(bytes))
+            .map_err(|e| arrow_error("Failed to open Arrow stream", e, &tag))?
+            .collect::<Result<Vec<_>, _>>()
+    }
+    .map_err(|e| arrow_error("Failed to read Arrow record batch", e, &tag))?;
+
+    let mut rows = Vec::new();
+    for batch in &batches {
+        convert_batch(batch, &tag, &mut rows)?;
+    }
+    Ok(UntaggedValue::Table(rows).into_value(tag))
+}
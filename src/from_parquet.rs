@@ -1,85 +1,3145 @@
-use parquet::record::{Field, Row};
+use parquet::data_type::Decimal;
+use parquet::record::{Field, List, Map, Row};
+use parquet::column::page::Page;
+use parquet::file::metadata::{ColumnChunkMetaData, FileMetaData, RowGroupMetaData};
+use parquet::file::reader::{ChunkReader, FileReader, RowGroupReader};
 use parquet::file::serialized_reader::{SerializedFileReader, SliceableCursor};
-use parquet::file::reader::FileReader;
-use nu_protocol::{UntaggedValue, Value, ReturnValue};
+use parquet::basic::{Encoding, LogicalType, Repetition, Type as PhysicalType};
+use parquet::schema::types::{SchemaDescPtr, SchemaDescriptor, Type, TypePtr};
+use parquet_format::{FileMetaData as ThriftFileMetaData, LogicalType as ThriftLogicalType, SchemaElement};
+use nu_errors::ShellError;
+use nu_protocol::{Dictionary, ShellTypeName, UntaggedValue, Value};
 use nu_source::Tag;
-use chrono::{Duration, FixedOffset, TimeZone, DateTime};
-use std::ops::Add;
-use bigdecimal::{BigDecimal, FromPrimitive};
+use chrono::{FixedOffset, LocalResult, Offset, TimeZone, DateTime, Utc};
+use chrono_tz::Tz;
+use bigdecimal::{BigDecimal, FromPrimitive, Signed};
 use indexmap::IndexMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Once};
+use std::time::Instant;
+use uuid::Uuid;
+use crate::convert::{decimal_from_be_bytes, interval_to_record, reads_back};
+use crate::readable::{read_schema, ReadableRowGroup};
+use crate::cast::Cast;
+use crate::describe::describe;
+use crate::group::{group_rows, Aggregation};
+use crate::expr::Select;
+use crate::filter::Predicate;
+use crate::input::read_file;
+use crate::locale::NumberFormat;
+use crate::mmap::map_file;
+use crate::page_index::{read_footer, PageStats};
+use crate::{open_source, parquet_error};
 
-fn convert_to_nu(field: &Field, tag: impl Into<Tag>) -> Value {
-    let epoch: DateTime<FixedOffset> = FixedOffset::west(0)
-        .ymd(1970, 1, 1)
-        .and_hms(0, 0, 0);
+pub use crate::convert::{days_to_date, time_of_day, timestamp_to_date, timestamp_to_extended_record, TimeUnit};
+
+/// Last element of lists cut by `max_list_elements`.
+const TRUNCATED_LIST: &str = "\u{2026}truncated\u{2026}";
+
+/// Default `truncation_marker`, an ellipsis.
+pub const DEFAULT_TRUNCATION_MARKER: &str = "\u{2026}";
+
+/// Options controlling how `from parquet` reads and converts a file.
+#[derive(Clone, Debug)]
+pub struct FromParquetOpts {
+    /// Only read these columns, in file order: top-level names or dotted paths of nested
+    /// columns.
+    pub columns: Option<Vec<String>>,
+    /// Only read these row groups, in the given order.
+    pub row_groups: Option<Vec<usize>>,
+    /// Number of rows to skip before collecting any.
+    pub skip: usize,
+    /// Maximum number of rows to collect.
+    pub limit: Option<usize>,
+    /// Convert string-keyed maps into records instead of lists of `[key, value]` pairs.
+    pub map_as_record: bool,
+    /// Lift the fields of nested records into their parent this many levels deep,
+    /// joining the names with `.`.
+    pub flatten: Option<usize>,
+    /// Add a column with this name holding the position of each row in the file.
+    pub row_index: Option<String>,
+    /// Add a column with this name holding the index of the row group each row comes from.
+    pub row_group: Option<String>,
+    /// Read INT8 and UINT8 values as single-byte binary instead of integers.
+    pub bytes_as_binary: bool,
+    /// How null values appear in the output.
+    pub null_representation: NullRepresentation,
+    /// Decode strings from columns annotated as JSON into nushell values.
+    pub parse_json: bool,
+    /// Return ENUM values as records holding the value and its dictionary ordinal.
+    pub extended_enum: bool,
+    /// How TIME_MILLIS and TIME_MICROS values appear in the output.
+    pub time_representation: TimeRepresentation,
+    /// How INT96 timestamps appear in the output.
+    pub int96_representation: Int96Representation,
+    /// Break dates and timestamps into records of their components.
+    pub extend_timestamp: bool,
+    /// How binary values appear in the output. Hex and base64 encode INT8 and UINT8
+    /// values as single bytes too.
+    pub binary_representation: BinaryRepresentation,
+    /// How BOOLEAN values appear in the output.
+    pub bool_representation: BoolRepresentation,
+    /// How FLOAT values appear in the output.
+    pub float_precision: FloatPrecision,
+    /// Return UINT64 values above `i64::MAX` as decimal strings. Nushell integers are
+    /// arbitrary precision, this is for consumers that expect signed 64-bit integers.
+    pub overflow_as_string: bool,
+    /// Fail on conversions that would lose precision.
+    pub strict: bool,
+    /// Decode row groups on several threads.
+    pub parallel: bool,
+    /// Return this many rows picked at random instead of all of them.
+    pub sample: Option<usize>,
+    /// Seed for `sample`, so the same rows are picked on every run.
+    pub seed: Option<u64>,
+    /// Add `_rg_index` and `_rg_num_rows` columns describing the row group of each row.
+    pub row_group_metadata: bool,
+    /// Return the footer key-value metadata instead of the rows.
+    pub kv_meta: bool,
+    /// Return the value of this footer key-value metadata key instead of the rows.
+    pub kv_meta_key: Option<String>,
+    /// Decode key-value metadata values holding JSON into nushell values.
+    pub parse_kv_json: bool,
+    /// Timezone of the wall clock times stored in INT96 timestamps and in TIMESTAMP
+    /// columns not adjusted to UTC. Columns adjusted to UTC ignore it.
+    pub timezone: Option<Tz>,
+    /// Return INTERVAL values as durations, counting a month as 30 days, instead of
+    /// records of their months, days and milliseconds.
+    pub interval_as_duration: bool,
+    /// Only return the rows matching this predicate, skipping the row groups whose
+    /// statistics rule out a match.
+    pub filter: Option<Predicate>,
+    /// How DECIMAL values appear in the output.
+    pub decimal_representation: DecimalRepresentation,
+    /// Wrap list elements in records of their index and value.
+    pub extend_list: bool,
+    /// Add `_encoding_<column>` columns listing the encodings of the column chunks each
+    /// row was read from.
+    pub encoding_info: bool,
+    /// Drop the trailing zeros of the `text` of extended decimals.
+    pub trim_decimal: bool,
+    /// Round the `text` of extended decimals to this many decimal places.
+    pub decimal_places: Option<u64>,
+    /// Write the `text` of extended decimals with the separators of a locale.
+    pub number_format: Option<NumberFormat>,
+    /// Casts applied to top-level columns once converted.
+    pub casts: Vec<Cast>,
+    /// Top-level columns renamed in the output, as `(old, new)` pairs.
+    pub renames: Vec<(String, String)>,
+    /// Top-level columns left out of the output. Unlike `columns` they are still read
+    /// and converted, which only saves the cost of carrying them in the table.
+    pub drop_columns: Vec<String>,
+    /// Return DATE and TIMESTAMP values as the integers they are stored as.
+    pub no_convert_dates: bool,
+    /// Top-level columns whose dates are returned as integers, as `no_convert_dates`
+    /// does for every column.
+    pub no_convert_dates_columns: Vec<String>,
+    /// Return the page encodings of every column chunk instead of the rows.
+    pub show_delta_encoding: bool,
+    /// Add `_compression_<column>` columns naming the codec of the column chunks each
+    /// row was read from.
+    pub compression_info: bool,
+    /// Computed columns appended to every row, evaluated in order after the casts.
+    pub selects: Vec<Select>,
+    /// Add `_page_min_<column>` and `_page_max_<column>` columns with the column index
+    /// statistics of the page each row was read from.
+    pub page_stats: bool,
+    /// AES key of encrypted files. parquet 3.0 cannot decrypt, the key only lets
+    /// encrypted files be reported as such.
+    pub decryption_key: Option<Vec<u8>>,
+    /// Id of `decryption_key` in the key metadata of the file.
+    pub decryption_key_id: Option<String>,
+    /// Return the Hugging Face dataset info of the key-value metadata instead of the rows.
+    pub hf_metadata: bool,
+    /// Return empty strings and binaries as nothing, for writers that stored them in
+    /// place of nulls.
+    pub empty_as_null: bool,
+    /// Return nulls of string and binary columns as empty strings and binaries.
+    pub null_as_empty: bool,
+    /// Cut strings longer than this many bytes at the last character that fits, then
+    /// append `truncation_marker`.
+    pub max_string_length: Option<usize>,
+    /// Appended to strings cut by `max_string_length`.
+    pub truncation_marker: String,
+    /// Cut binaries to this many bytes.
+    pub max_binary_length: Option<usize>,
+    /// Wrap list elements in records of their nushell type name and value, for lists
+    /// whose elements differ in type.
+    pub typed_lists: bool,
+    /// Keep this many elements of a list, followed by a `…truncated…` string when more
+    /// were left out.
+    pub max_list_elements: Option<usize>,
+    /// Return lists as records of their length, element type and first and last
+    /// elements instead of their contents.
+    pub list_stats: bool,
+    /// Read UTF8, ENUM and JSON columns as bytes and check that they are valid UTF-8,
+    /// failing with the offset of the first invalid byte instead of panicking.
+    pub validate_utf8: bool,
+    /// Put a record of the nushell type names of the columns of the first row before
+    /// the rows, with a `_schema` column set to true.
+    pub output_schema: bool,
+    /// Sort the top-level columns of every row by name.
+    pub sort_columns: bool,
+    /// Put these top-level columns first, in this order, followed by the other columns
+    /// sorted by name. Names are those of the output, after `renames`.
+    pub column_order: Vec<String>,
+    /// Prepended to the names of the top-level columns, after `renames` and
+    /// `column_order`.
+    pub prefix_columns: Option<String>,
+    /// Only return the rows with a null in at least one top-level column.
+    pub only_nulls: bool,
+    /// Only return the rows with a null in at least one of these top-level columns.
+    pub only_nulls_in: Vec<String>,
+    /// Only return the first row of every distinct combination of values of these
+    /// columns, once every other option is applied. Names are those of the output.
+    pub dedup: Vec<String>,
+    /// Return the last row of every combination of `dedup` values instead of the first.
+    pub dedup_keep_last: bool,
+    /// Read top-level INT32 and INT64 columns without annotation whose names end in
+    /// `_date`, `_at`, `_time`, `_ts` or `_timestamp` as dates: INT32 as days and INT64
+    /// as milliseconds since the epoch.
+    pub infer_types: bool,
+    /// Return a summary of every top-level column of the rows instead of the rows:
+    /// the null count, the min, max, sum, mean and standard deviation of numbers and
+    /// the distinct count and bounds of the lengths of strings.
+    pub describe: bool,
+    /// Return one record per distinct value of this top-level column instead of the
+    /// rows, holding the value and the `aggregations`, or the number of rows of the
+    /// value as `count` without any.
+    pub group_by: Option<String>,
+    /// Aggregations of the rows of every `group_by` value.
+    pub aggregations: Vec<Aggregation>,
+    /// Report on stderr what the read went through once it completes: bytes and row
+    /// groups read, row groups ruled out by the statistics of `filter`, rows decoded
+    /// and returned, an estimate of the memory used and the time taken.
+    pub verbose: bool,
+    /// Return a description of what the read would do instead of the rows: the columns
+    /// projected, the row groups read and those ruled out by the statistics of `filter`,
+    /// and estimates of the rows decoded and the memory used. Only the footer is read.
+    pub explain: bool,
+    /// Turn rows whose conversion fails into records of `_error` set to true, the
+    /// `_error_message`, the `_row_index` of the row in the file and the `_column` that
+    /// failed, with every column of the row null, instead of failing the read.
+    pub errors_as_rows: bool,
+    /// Return UUID columns as their 16 bytes instead of hyphenated strings.
+    pub no_decode_uuid: bool,
+}
+
+impl Default for FromParquetOpts {
+    fn default() -> Self {
+        FromParquetOpts {
+            columns: None,
+            row_groups: None,
+            skip: 0,
+            limit: None,
+            map_as_record: false,
+            flatten: None,
+            row_index: None,
+            row_group: None,
+            bytes_as_binary: false,
+            null_representation: NullRepresentation::Nothing,
+            parse_json: false,
+            extended_enum: false,
+            time_representation: TimeRepresentation::String,
+            int96_representation: Int96Representation::DateTime,
+            extend_timestamp: false,
+            binary_representation: BinaryRepresentation::Binary,
+            bool_representation: BoolRepresentation::Bool,
+            float_precision: FloatPrecision::Double,
+            overflow_as_string: false,
+            strict: false,
+            parallel: false,
+            sample: None,
+            seed: None,
+            row_group_metadata: false,
+            kv_meta: false,
+            kv_meta_key: None,
+            parse_kv_json: false,
+            timezone: None,
+            interval_as_duration: false,
+            filter: None,
+            decimal_representation: DecimalRepresentation::Decimal,
+            extend_list: false,
+            encoding_info: false,
+            trim_decimal: false,
+            decimal_places: None,
+            number_format: None,
+            casts: Vec::new(),
+            renames: Vec::new(),
+            drop_columns: Vec::new(),
+            no_convert_dates: false,
+            no_convert_dates_columns: Vec::new(),
+            show_delta_encoding: false,
+            compression_info: false,
+            selects: Vec::new(),
+            page_stats: false,
+            decryption_key: None,
+            decryption_key_id: None,
+            hf_metadata: false,
+            empty_as_null: false,
+            null_as_empty: false,
+            max_string_length: None,
+            truncation_marker: DEFAULT_TRUNCATION_MARKER.to_string(),
+            max_binary_length: None,
+            typed_lists: false,
+            max_list_elements: None,
+            list_stats: false,
+            validate_utf8: false,
+            output_schema: false,
+            sort_columns: false,
+            column_order: Vec::new(),
+            prefix_columns: None,
+            only_nulls: false,
+            only_nulls_in: Vec::new(),
+            dedup: Vec::new(),
+            dedup_keep_last: false,
+            infer_types: false,
+            describe: false,
+            group_by: None,
+            aggregations: Vec::new(),
+            verbose: false,
+            explain: false,
+            errors_as_rows: false,
+            no_decode_uuid: false,
+        }
+    }
+}
+
+/// Builds `FromParquetOpts` from the defaults, one option at a time.
+#[derive(Clone, Debug, Default)]
+pub struct FromParquetOptsBuilder {
+    opts: FromParquetOpts,
+}
+
+impl FromParquetOpts {
+    /// A builder starting from the default options.
+    pub fn builder() -> FromParquetOptsBuilder {
+        FromParquetOptsBuilder::default()
+    }
+
+    /// Whether rows are left out once read, so that `skip` and `limit` can only count
+    /// the rows that are kept as they are read.
+    fn filters_rows(&self) -> bool {
+        self.filter.is_some() || self.only_nulls || !self.only_nulls_in.is_empty()
+    }
+}
+
+impl FromParquetOptsBuilder {
+    /// Only read these columns, in file order: top-level names or dotted paths of nested
+    /// columns.
+    pub fn columns(mut self, columns: Vec<String>) -> Self {
+        self.opts.columns = Some(columns);
+        self
+    }
+
+    /// Only read these row groups, in the given order.
+    pub fn row_groups(mut self, row_groups: Vec<usize>) -> Self {
+        self.opts.row_groups = Some(row_groups);
+        self
+    }
+
+    /// Number of rows to skip before collecting any.
+    pub fn skip(mut self, skip: usize) -> Self {
+        self.opts.skip = skip;
+        self
+    }
+
+    /// Maximum number of rows to collect.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.opts.limit = Some(limit);
+        self
+    }
+
+    /// Convert string-keyed maps into records instead of lists of `[key, value]` pairs.
+    pub fn map_as_record(mut self, map_as_record: bool) -> Self {
+        self.opts.map_as_record = map_as_record;
+        self
+    }
+
+    /// Lift the fields of nested records into their parent this many levels deep,
+    /// joining the names with `.`.
+    pub fn flatten(mut self, flatten: usize) -> Self {
+        self.opts.flatten = Some(flatten);
+        self
+    }
+
+    /// Add a column with this name holding the position of each row in the file.
+    pub fn row_index(mut self, row_index: String) -> Self {
+        self.opts.row_index = Some(row_index);
+        self
+    }
+
+    /// Add a column with this name holding the index of the row group each row comes from.
+    pub fn row_group(mut self, row_group: String) -> Self {
+        self.opts.row_group = Some(row_group);
+        self
+    }
+
+    /// Read INT8 and UINT8 values as single-byte binary instead of integers.
+    pub fn bytes_as_binary(mut self, bytes_as_binary: bool) -> Self {
+        self.opts.bytes_as_binary = bytes_as_binary;
+        self
+    }
+
+    /// How null values appear in the output.
+    pub fn null_representation(mut self, null_representation: NullRepresentation) -> Self {
+        self.opts.null_representation = null_representation;
+        self
+    }
+
+    /// Decode strings from columns annotated as JSON into nushell values.
+    pub fn parse_json(mut self, parse_json: bool) -> Self {
+        self.opts.parse_json = parse_json;
+        self
+    }
+
+    /// Return ENUM values as records holding the value and its dictionary ordinal.
+    pub fn extended_enum(mut self, extended_enum: bool) -> Self {
+        self.opts.extended_enum = extended_enum;
+        self
+    }
+
+    /// How TIME_MILLIS and TIME_MICROS values appear in the output.
+    pub fn time_representation(mut self, time_representation: TimeRepresentation) -> Self {
+        self.opts.time_representation = time_representation;
+        self
+    }
+
+    /// How INT96 timestamps appear in the output.
+    pub fn int96_representation(mut self, int96_representation: Int96Representation) -> Self {
+        self.opts.int96_representation = int96_representation;
+        self
+    }
+
+    /// Break dates and timestamps into records of their components.
+    pub fn extend_timestamp(mut self, extend_timestamp: bool) -> Self {
+        self.opts.extend_timestamp = extend_timestamp;
+        self
+    }
+
+    /// How binary values appear in the output. Hex and base64 encode INT8 and UINT8
+    /// values as single bytes too.
+    pub fn binary_representation(mut self, binary_representation: BinaryRepresentation) -> Self {
+        self.opts.binary_representation = binary_representation;
+        self
+    }
+
+    /// How BOOLEAN values appear in the output.
+    pub fn bool_representation(mut self, bool_representation: BoolRepresentation) -> Self {
+        self.opts.bool_representation = bool_representation;
+        self
+    }
+
+    /// How FLOAT values appear in the output.
+    pub fn float_precision(mut self, float_precision: FloatPrecision) -> Self {
+        self.opts.float_precision = float_precision;
+        self
+    }
+
+    /// Return UINT64 values above `i64::MAX` as decimal strings. Nushell integers are
+    /// arbitrary precision, this is for consumers that expect signed 64-bit integers.
+    pub fn overflow_as_string(mut self, overflow_as_string: bool) -> Self {
+        self.opts.overflow_as_string = overflow_as_string;
+        self
+    }
+
+    /// Fail on conversions that would lose precision.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.opts.strict = strict;
+        self
+    }
+
+    /// Decode row groups on several threads.
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.opts.parallel = parallel;
+        self
+    }
+
+    /// Return this many rows picked at random instead of all of them.
+    pub fn sample(mut self, sample: usize) -> Self {
+        self.opts.sample = Some(sample);
+        self
+    }
+
+    /// Seed for `sample`, so the same rows are picked on every run.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.opts.seed = Some(seed);
+        self
+    }
+
+    /// Add `_rg_index` and `_rg_num_rows` columns describing the row group of each row.
+    pub fn row_group_metadata(mut self, row_group_metadata: bool) -> Self {
+        self.opts.row_group_metadata = row_group_metadata;
+        self
+    }
+
+    /// Return the footer key-value metadata instead of the rows.
+    pub fn kv_meta(mut self, kv_meta: bool) -> Self {
+        self.opts.kv_meta = kv_meta;
+        self
+    }
+
+    /// Return the value of this footer key-value metadata key instead of the rows.
+    pub fn kv_meta_key(mut self, kv_meta_key: String) -> Self {
+        self.opts.kv_meta_key = Some(kv_meta_key);
+        self
+    }
+
+    /// Decode key-value metadata values holding JSON into nushell values.
+    pub fn parse_kv_json(mut self, parse_kv_json: bool) -> Self {
+        self.opts.parse_kv_json = parse_kv_json;
+        self
+    }
+
+    /// Timezone of the wall clock times stored in INT96 timestamps and in TIMESTAMP
+    /// columns not adjusted to UTC. Columns adjusted to UTC ignore it.
+    pub fn timezone(mut self, timezone: Tz) -> Self {
+        self.opts.timezone = Some(timezone);
+        self
+    }
+
+    /// Return INTERVAL values as durations, counting a month as 30 days, instead of
+    /// records of their months, days and milliseconds.
+    pub fn interval_as_duration(mut self, interval_as_duration: bool) -> Self {
+        self.opts.interval_as_duration = interval_as_duration;
+        self
+    }
+
+    /// Only return the rows matching this predicate, skipping the row groups whose
+    /// statistics rule out a match.
+    pub fn filter(mut self, filter: Predicate) -> Self {
+        self.opts.filter = Some(filter);
+        self
+    }
+
+    /// How DECIMAL values appear in the output.
+    pub fn decimal_representation(mut self, decimal_representation: DecimalRepresentation) -> Self {
+        self.opts.decimal_representation = decimal_representation;
+        self
+    }
+
+    /// Return DECIMAL values as records of their value, text, precision and scale, the
+    /// `--extended-decimal` flag.
+    pub fn extended_decimal(mut self, enabled: bool) -> Self {
+        self.opts.decimal_representation = match (enabled, self.opts.decimal_representation) {
+            (true, _) => DecimalRepresentation::Extended,
+            (false, DecimalRepresentation::Extended) => DecimalRepresentation::Decimal,
+            (false, other) => other,
+        };
+        self
+    }
+
+    /// Wrap list elements in records of their index and value.
+    pub fn extend_list(mut self, extend_list: bool) -> Self {
+        self.opts.extend_list = extend_list;
+        self
+    }
+
+    /// Add `_encoding_<column>` columns listing the encodings of the column chunks each
+    /// row was read from.
+    pub fn encoding_info(mut self, encoding_info: bool) -> Self {
+        self.opts.encoding_info = encoding_info;
+        self
+    }
+
+    /// Drop the trailing zeros of the `text` of extended decimals.
+    pub fn trim_decimal(mut self, trim_decimal: bool) -> Self {
+        self.opts.trim_decimal = trim_decimal;
+        self
+    }
+
+    /// Round the `text` of extended decimals to this many decimal places.
+    pub fn decimal_places(mut self, decimal_places: u64) -> Self {
+        self.opts.decimal_places = Some(decimal_places);
+        self
+    }
+
+    /// Write the `text` of extended decimals with the separators of a locale.
+    pub fn number_format(mut self, number_format: NumberFormat) -> Self {
+        self.opts.number_format = Some(number_format);
+        self
+    }
+
+    /// Casts applied to top-level columns once converted.
+    pub fn casts(mut self, casts: Vec<Cast>) -> Self {
+        self.opts.casts = casts;
+        self
+    }
+
+    /// Top-level columns renamed in the output, as `(old, new)` pairs.
+    pub fn renames(mut self, renames: Vec<(String, String)>) -> Self {
+        self.opts.renames = renames;
+        self
+    }
+
+    /// Top-level columns left out of the output. Unlike `columns` they are still read
+    /// and converted, which only saves the cost of carrying them in the table.
+    pub fn drop_columns(mut self, drop_columns: Vec<String>) -> Self {
+        self.opts.drop_columns = drop_columns;
+        self
+    }
+
+    /// Return DATE and TIMESTAMP values as the integers they are stored as.
+    pub fn no_convert_dates(mut self, no_convert_dates: bool) -> Self {
+        self.opts.no_convert_dates = no_convert_dates;
+        self
+    }
+
+    /// Top-level columns whose dates are returned as integers, as `no_convert_dates`
+    /// does for every column.
+    pub fn no_convert_dates_columns(mut self, no_convert_dates_columns: Vec<String>) -> Self {
+        self.opts.no_convert_dates_columns = no_convert_dates_columns;
+        self
+    }
+
+    /// Return the page encodings of every column chunk instead of the rows.
+    pub fn show_delta_encoding(mut self, show_delta_encoding: bool) -> Self {
+        self.opts.show_delta_encoding = show_delta_encoding;
+        self
+    }
+
+    /// Add `_compression_<column>` columns naming the codec of the column chunks each
+    /// row was read from.
+    pub fn compression_info(mut self, compression_info: bool) -> Self {
+        self.opts.compression_info = compression_info;
+        self
+    }
+
+    /// Computed columns appended to every row, evaluated in order after the casts.
+    pub fn selects(mut self, selects: Vec<Select>) -> Self {
+        self.opts.selects = selects;
+        self
+    }
+
+    /// Add `_page_min_<column>` and `_page_max_<column>` columns with the column index
+    /// statistics of the page each row was read from.
+    pub fn page_stats(mut self, page_stats: bool) -> Self {
+        self.opts.page_stats = page_stats;
+        self
+    }
+
+    /// AES key of encrypted files. parquet 3.0 cannot decrypt, the key only lets
+    /// encrypted files be reported as such.
+    pub fn decryption_key(mut self, decryption_key: Vec<u8>) -> Self {
+        self.opts.decryption_key = Some(decryption_key);
+        self
+    }
+
+    /// Id of `decryption_key` in the key metadata of the file.
+    pub fn decryption_key_id(mut self, decryption_key_id: String) -> Self {
+        self.opts.decryption_key_id = Some(decryption_key_id);
+        self
+    }
+
+    /// Return the Hugging Face dataset info of the key-value metadata instead of the rows.
+    pub fn hf_metadata(mut self, hf_metadata: bool) -> Self {
+        self.opts.hf_metadata = hf_metadata;
+        self
+    }
+
+    /// Return empty strings and binaries as nothing, for writers that stored them in
+    /// place of nulls.
+    pub fn empty_as_null(mut self, empty_as_null: bool) -> Self {
+        self.opts.empty_as_null = empty_as_null;
+        self
+    }
+
+    /// Return nulls of string and binary columns as empty strings and binaries.
+    pub fn null_as_empty(mut self, null_as_empty: bool) -> Self {
+        self.opts.null_as_empty = null_as_empty;
+        self
+    }
+
+    /// Cut strings longer than this many bytes at the last character that fits, then
+    /// append `truncation_marker`.
+    pub fn max_string_length(mut self, max_string_length: usize) -> Self {
+        self.opts.max_string_length = Some(max_string_length);
+        self
+    }
+
+    /// Appended to strings cut by `max_string_length`.
+    pub fn truncation_marker(mut self, truncation_marker: impl Into<String>) -> Self {
+        self.opts.truncation_marker = truncation_marker.into();
+        self
+    }
+
+    /// Cut binaries to this many bytes.
+    pub fn max_binary_length(mut self, max_binary_length: usize) -> Self {
+        self.opts.max_binary_length = Some(max_binary_length);
+        self
+    }
+
+    /// Wrap list elements in records of their nushell type name and value, for lists
+    /// whose elements differ in type.
+    pub fn typed_lists(mut self, typed_lists: bool) -> Self {
+        self.opts.typed_lists = typed_lists;
+        self
+    }
+
+    /// Keep this many elements of a list, followed by a `…truncated…` string when more
+    /// were left out.
+    pub fn max_list_elements(mut self, max_list_elements: usize) -> Self {
+        self.opts.max_list_elements = Some(max_list_elements);
+        self
+    }
+
+    /// Return lists as records of their length, element type and first and last
+    /// elements instead of their contents.
+    pub fn list_stats(mut self, list_stats: bool) -> Self {
+        self.opts.list_stats = list_stats;
+        self
+    }
+
+    /// Read UTF8, ENUM and JSON columns as bytes and check that they are valid UTF-8,
+    /// failing with the offset of the first invalid byte instead of panicking.
+    pub fn validate_utf8(mut self, validate_utf8: bool) -> Self {
+        self.opts.validate_utf8 = validate_utf8;
+        self
+    }
+
+    /// Put a record of the nushell type names of the columns of the first row before
+    /// the rows, with a `_schema` column set to true.
+    pub fn output_schema(mut self, output_schema: bool) -> Self {
+        self.opts.output_schema = output_schema;
+        self
+    }
+
+    /// Sort the top-level columns of every row by name.
+    pub fn sort_columns(mut self, sort_columns: bool) -> Self {
+        self.opts.sort_columns = sort_columns;
+        self
+    }
+
+    /// Put these top-level columns first, in this order, followed by the other columns
+    /// sorted by name. Names are those of the output, after `renames`.
+    pub fn column_order(mut self, column_order: Vec<String>) -> Self {
+        self.opts.column_order = column_order;
+        self
+    }
+
+    /// Prepended to the names of the top-level columns, after `renames` and
+    /// `column_order`.
+    pub fn prefix_columns(mut self, prefix_columns: String) -> Self {
+        self.opts.prefix_columns = Some(prefix_columns);
+        self
+    }
+
+    /// Only return the rows with a null in at least one top-level column.
+    pub fn only_nulls(mut self, only_nulls: bool) -> Self {
+        self.opts.only_nulls = only_nulls;
+        self
+    }
+
+    /// Only return the rows with a null in at least one of these top-level columns.
+    pub fn only_nulls_in(mut self, only_nulls_in: Vec<String>) -> Self {
+        self.opts.only_nulls_in = only_nulls_in;
+        self
+    }
+
+    /// Only return the first row of every distinct combination of values of these
+    /// columns, once every other option is applied. Names are those of the output.
+    pub fn dedup(mut self, dedup: Vec<String>) -> Self {
+        self.opts.dedup = dedup;
+        self
+    }
+
+    /// Return the last row of every combination of `dedup` values instead of the first.
+    pub fn dedup_keep_last(mut self, dedup_keep_last: bool) -> Self {
+        self.opts.dedup_keep_last = dedup_keep_last;
+        self
+    }
+
+    /// Read top-level INT32 and INT64 columns without annotation whose names end in
+    /// `_date`, `_at`, `_time`, `_ts` or `_timestamp` as dates: INT32 as days and INT64
+    /// as milliseconds since the epoch.
+    pub fn infer_types(mut self, infer_types: bool) -> Self {
+        self.opts.infer_types = infer_types;
+        self
+    }
+
+    /// Return a summary of every top-level column of the rows instead of the rows:
+    /// the null count, the min, max, sum, mean and standard deviation of numbers and
+    /// the distinct count and bounds of the lengths of strings.
+    pub fn describe(mut self, describe: bool) -> Self {
+        self.opts.describe = describe;
+        self
+    }
+
+    /// Return one record per distinct value of this top-level column instead of the
+    /// rows, holding the value and the `aggregations`, or the number of rows of the
+    /// value as `count` without any.
+    pub fn group_by(mut self, group_by: String) -> Self {
+        self.opts.group_by = Some(group_by);
+        self
+    }
+
+    /// Aggregations of the rows of every `group_by` value.
+    pub fn aggregations(mut self, aggregations: Vec<Aggregation>) -> Self {
+        self.opts.aggregations = aggregations;
+        self
+    }
+
+    /// Report on stderr what the read went through once it completes: bytes and row
+    /// groups read, row groups ruled out by the statistics of `filter`, rows decoded
+    /// and returned, an estimate of the memory used and the time taken.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.opts.verbose = verbose;
+        self
+    }
+
+    /// Return a description of what the read would do instead of the rows: the columns
+    /// projected, the row groups read and those ruled out by the statistics of `filter`,
+    /// and estimates of the rows decoded and the memory used. Only the footer is read.
+    pub fn explain(mut self, explain: bool) -> Self {
+        self.opts.explain = explain;
+        self
+    }
+
+    /// Turn rows whose conversion fails into records of `_error` set to true, the
+    /// `_error_message`, the `_row_index` of the row in the file and the `_column` that
+    /// failed, with every column of the row null, instead of failing the read.
+    pub fn errors_as_rows(mut self, errors_as_rows: bool) -> Self {
+        self.opts.errors_as_rows = errors_as_rows;
+        self
+    }
+
+    /// Return UUID columns as their 16 bytes instead of hyphenated strings.
+    pub fn no_decode_uuid(mut self, no_decode_uuid: bool) -> Self {
+        self.opts.no_decode_uuid = no_decode_uuid;
+        self
+    }
+
+    /// The options set so far.
+    pub fn build(self) -> FromParquetOpts {
+        self.opts
+    }
+}
+
+/// Value produced for Parquet binaries.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryRepresentation {
+    /// Binaries stay binary.
+    Binary,
+    /// Binaries become lowercase hex strings.
+    Hex,
+    /// Binaries become base64 strings.
+    Base64,
+}
+
+/// Value produced for Parquet booleans.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoolRepresentation {
+    /// Booleans stay booleans.
+    Bool,
+    /// Booleans become the integers 0 and 1.
+    Int,
+    /// Booleans become the strings `true` and `false`.
+    String,
+}
+
+/// Value produced for Parquet decimals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecimalRepresentation {
+    /// Decimals become nushell decimals, which keep every digit.
+    Decimal,
+    /// Decimals become records of their value, text, precision and scale.
+    Extended,
+    /// Decimals become records of their unscaled big-endian bytes, scale and precision.
+    Raw,
+}
+
+/// Value produced for Parquet times of day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeRepresentation {
+    /// Times become strings like `12:34:56.789`.
+    String,
+    /// Times become the duration since midnight.
+    Duration,
+}
+
+/// Value produced for Parquet nulls.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NullRepresentation {
+    /// Nulls become nothing.
+    Nothing,
+    /// Nulls become the empty string.
+    String,
+    /// The first null fails the whole command.
+    Error,
+}
+
+impl NullRepresentation {
+    /// Looks up a representation by the name `--null-as` takes, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "nothing" => Some(NullRepresentation::Nothing),
+            "string" => Some(NullRepresentation::String),
+            "error" => Some(NullRepresentation::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Value produced for 32-bit Parquet floats.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FloatPrecision {
+    /// Floats become decimals of the float widened to 64 bits, so `1.1` comes out as
+    /// `1.100000023841858`.
+    Double,
+    /// Floats become strings of the fewest digits that read back as the same 32-bit
+    /// float, so `1.1` stays `1.1`.
+    Exact,
+}
+
+impl FloatPrecision {
+    /// Looks up a precision by the name `--float-precision` takes, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "double" => Some(FloatPrecision::Double),
+            "exact" => Some(FloatPrecision::Exact),
+            _ => None,
+        }
+    }
+}
+
+/// Value produced for INT96 timestamps, as written by Spark and Impala.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Int96Representation {
+    /// Timestamps become dates with their full nanosecond precision.
+    DateTime,
+    /// Timestamps become the hex string of their 12 raw bytes.
+    String,
+}
+
+fn time_unit(ty: Option<&Type>) -> Option<TimeUnit> {
+    match ty?.get_basic_info().logical_type() {
+        LogicalType::TIME_MILLIS => Some(TimeUnit::Millis),
+        LogicalType::TIME_MICROS => Some(TimeUnit::Micros),
+        _ => None,
+    }
+}
+
+fn time_to_nu(value: i64, unit: TimeUnit, tag: impl Into<Tag>, opts: &FromParquetOpts) -> Value {
+    match opts.time_representation {
+        TimeRepresentation::String => UntaggedValue::string(time_of_day(value, unit)).into_value(tag),
+        TimeRepresentation::Duration => {
+            let nanos = i128::from(value) * i128::from(1_000_000_000 / unit.per_second());
+            UntaggedValue::duration(nanos).into_value(tag)
+        }
+    }
+}
+
+const JULIAN_DAY_OF_EPOCH: i64 = 2_440_588;
+
+/// Converts the nanoseconds since midnight and Julian day stored in an INT96 timestamp
+/// into a UTC date.
+pub fn int96_to_date(nanos_of_day: i64, julian_day: i64) -> Option<DateTime<FixedOffset>> {
+    let seconds = (julian_day - JULIAN_DAY_OF_EPOCH)
+        .checked_mul(86_400)?
+        .checked_add(nanos_of_day.div_euclid(1_000_000_000))?;
+    match Utc.timestamp_opt(seconds, nanos_of_day.rem_euclid(1_000_000_000) as u32) {
+        LocalResult::Single(date) => Some(date.into()),
+        _ => None,
+    }
+}
+
+/// Reads the UTC date's wall clock time as a local time in `tz`. Times skipped by a
+/// daylight saving change have no such date, repeated ones take the earlier offset.
+fn localize(date: DateTime<FixedOffset>, tz: Tz) -> Option<DateTime<FixedOffset>> {
+    let local = tz.from_local_datetime(&date.naive_utc()).earliest()?;
+    Some(DateTime::from_utc(local.naive_utc(), local.offset().fix()))
+}
+
+static UTC_TIMESTAMPS: Once = Once::new();
+
+/// Reads a TIMESTAMP in `--timezone` when its column is not adjusted to UTC. Columns
+/// adjusted to UTC already name an instant, the flag is ignored for them with a warning.
+fn localize_timestamp(date: Option<DateTime<FixedOffset>>, local: bool, opts: &FromParquetOpts) -> Option<DateTime<FixedOffset>> {
+    match opts.timezone {
+        Some(tz) if local => date.and_then(|date| localize(date, tz)),
+        Some(_) => {
+            UTC_TIMESTAMPS.call_once(|| {
+                eprintln!("warning: --timezone is ignored for TIMESTAMP columns adjusted to UTC");
+            });
+            date
+        }
+        None => date,
+    }
+}
+
+fn is_int96(ty: Option<&Type>) -> bool {
+    ty.is_some_and(|ty| ty.is_primitive() && ty.get_physical_type() == PhysicalType::INT96)
+}
+
+fn is_interval(ty: Option<&Type>) -> bool {
+    ty.is_some_and(|ty| ty.is_primitive() && ty.get_basic_info().logical_type() == LogicalType::INTERVAL)
+}
+
+const NANOS_PER_DAY: i128 = 86_400_000_000_000;
+
+/// Converts the 12 raw bytes of an INTERVAL: little-endian unsigned months, days and
+/// milliseconds. Months and days vary in length, so durations are an approximation
+/// that takes every month as 30 days and every day as 24 hours.
+fn interval_to_nu(data: &[u8], tag: impl Into<Tag>, opts: &FromParquetOpts) -> Result<Value, ConvertError> {
+    let tag = tag.into();
+    if data.len() != 12 {
+        return Ok(binary_to_nu(data, tag, opts));
+    }
+    let part = |i: usize| {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&data[i * 4..i * 4 + 4]);
+        u32::from_le_bytes(bytes)
+    };
+    let (months, days, millis) = (part(0), part(1), part(2));
+
+    if opts.interval_as_duration {
+        if opts.strict && months != 0 {
+            return Err(ConvertError::lossy(format!("{} months", months), "duration"));
+        }
+        let nanos = (i128::from(months) * 30 + i128::from(days)) * NANOS_PER_DAY
+            + i128::from(millis) * 1_000_000;
+        return Ok(UntaggedValue::duration(nanos).into_value(tag));
+    }
+    Ok(interval_to_record(i64::from(months), i64::from(days), i64::from(millis), tag))
+}
+
+/// Converts the 12 raw bytes of an INT96 timestamp: the little-endian nanoseconds of
+/// the day followed by the little-endian Julian day.
+fn int96_to_nu(data: &[u8], tag: impl Into<Tag>, opts: &FromParquetOpts) -> Result<Value, ConvertError> {
+    let hex = hex::encode(data);
+    if opts.int96_representation == Int96Representation::String || data.len() != 12 {
+        return Ok(UntaggedValue::string(hex).into_value(tag));
+    }
+    let mut nanos = [0u8; 8];
+    nanos.copy_from_slice(&data[..8]);
+    let mut day = [0u8; 4];
+    day.copy_from_slice(&data[8..]);
+    let date = int96_to_date(i64::from_le_bytes(nanos), i64::from(u32::from_le_bytes(day)));
+    let date = match opts.timezone {
+        Some(tz) => date.and_then(|date| localize(date, tz)),
+        None => date,
+    };
+    date_to_nu(date, hex, tag, opts)
+}
+
+/// A failed conversion. The path of the column is collected while the error
+/// propagates out of nested values, the row is known once it reaches the row loop.
+struct ConvertError {
+    message: String,
+    label: String,
+    path: Vec<String>,
+}
+
+impl ConvertError {
+    fn new(message: impl Into<String>, label: impl Into<String>) -> Self {
+        ConvertError { message: message.into(), label: label.into(), path: Vec::new() }
+    }
+
+    fn lossy(raw: impl std::fmt::Display, target: &str) -> Self {
+        ConvertError::new(
+            "Conversion would lose precision",
+            format!("cannot convert {} to a {} exactly", raw, target),
+        )
+    }
+
+    /// NaN and the infinities have no decimal counterpart.
+    fn not_finite(raw: impl std::fmt::Display) -> Self {
+        ConvertError::new("Cannot convert a non-finite float", format!("{} has no decimal representation", raw))
+    }
+
+    fn in_column(mut self, name: &str) -> Self {
+        self.path.push(name.to_string());
+        self
+    }
+
+    /// The error of a conversion outside a file, where the row is not known.
+    fn into_field_error(self, tag: &Tag) -> ShellError {
+        let label = if self.path.is_empty() {
+            self.label
+        } else {
+            let column: Vec<&str> = self.path.iter().rev().map(|s| s.as_str()).collect();
+            format!("{} in column '{}'", self.label, column.join("."))
+        };
+        ShellError::labeled_error(self.message, label, tag)
+    }
+
+    /// The `--emit-errors-as-rows` columns of the failed conversion of a row.
+    fn into_columns(self, row: i64) -> Vec<(&'static str, Value)> {
+        let column: Vec<&str> = self.path.iter().rev().map(|s| s.as_str()).collect();
+        vec![
+            ("_error", UntaggedValue::boolean(true).into_value(Tag::unknown())),
+            ("_error_message", UntaggedValue::string(format!("{}: {}", self.message, self.label)).into_value(Tag::unknown())),
+            ("_row_index", UntaggedValue::int(row).into_value(Tag::unknown())),
+            ("_column", UntaggedValue::string(column.join(".")).into_value(Tag::unknown())),
+        ]
+    }
+
+    fn into_shell_error(self, row: i64, tag: &Tag) -> ShellError {
+        let column: Vec<&str> = self.path.iter().rev().map(|s| s.as_str()).collect();
+        ShellError::labeled_error(
+            self.message,
+            format!("{} in column '{}' at row {}", self.label, column.join("."), row),
+            tag,
+        )
+    }
+}
+
+fn binary_to_nu(bytes: &[u8], tag: impl Into<Tag>, opts: &FromParquetOpts) -> Value {
+    match opts.binary_representation {
+        BinaryRepresentation::Binary => UntaggedValue::binary(bytes.to_vec()).into_value(tag),
+        BinaryRepresentation::Hex => UntaggedValue::string(hex::encode(bytes)).into_value(tag),
+        BinaryRepresentation::Base64 => UntaggedValue::string(base64::encode(bytes)).into_value(tag),
+    }
+}
+
+/// Renders the `text` of an extended decimal, rounded half away from zero to
+/// `--decimal-places`, without trailing zeros with `--trim-decimal` and with the
+/// separators of the `--format-numbers` locale.
+fn render_bigdecimal(d: &BigDecimal, opts: &FromParquetOpts) -> String {
+    let mut d = d.clone();
+    if let Some(places) = opts.decimal_places {
+        let places = places as i64;
+        let truncated = d.with_scale(places);
+        let ulp = BigDecimal::new(1.into(), places);
+        let remainder = &d - &truncated;
+        d = if remainder.abs() * BigDecimal::from(2) >= ulp {
+            if d.is_negative() { truncated - ulp } else { truncated + ulp }
+        } else {
+            truncated
+        };
+    }
+    if opts.trim_decimal {
+        d = d.normalized();
+    }
+    match &opts.number_format {
+        Some(format) => format.format(&d.to_string()),
+        None => d.to_string(),
+    }
+}
+
+fn decimal_to_nu(decimal: &Decimal, tag: impl Into<Tag>, opts: &FromParquetOpts) -> Value {
+    let tag = tag.into();
+    let value = decimal_from_be_bytes(decimal.data(), i64::from(decimal.scale()));
+    if opts.decimal_representation == DecimalRepresentation::Decimal {
+        return UntaggedValue::decimal(value).into_value(tag);
+    }
+    let mut record = IndexMap::with_capacity(4);
+    if opts.decimal_representation == DecimalRepresentation::Extended {
+        record.insert("value".to_string(), UntaggedValue::decimal(value.clone()).into_value(tag.clone()));
+        record.insert("text".to_string(), UntaggedValue::string(render_bigdecimal(&value, opts)).into_value(tag.clone()));
+    } else {
+        record.insert("unscaled".to_string(), UntaggedValue::binary(decimal.data().to_vec()).into_value(tag.clone()));
+    }
+    record.insert("scale".to_string(), UntaggedValue::int(decimal.scale()).into_value(tag.clone()));
+    record.insert("precision".to_string(), UntaggedValue::int(decimal.precision()).into_value(tag.clone()));
+    UntaggedValue::row(record).into_value(tag)
+}
+
+fn date_to_nu(
+    date: Option<DateTime<FixedOffset>>,
+    raw: impl std::fmt::Display,
+    tag: impl Into<Tag>,
+    opts: &FromParquetOpts,
+) -> Result<Value, ConvertError> {
+    let tag = tag.into();
+    match date {
+        Some(date) if opts.extend_timestamp => Ok(timestamp_to_extended_record(date, tag)),
+        Some(date) => Ok(UntaggedValue::date(date).into_value(tag)),
+        None => Err(ConvertError::new(
+            "Timestamp is out of range",
+            format!("cannot represent {} as a date", raw),
+        )),
+    }
+}
+
+/// An ENUM column and the positions of its values in its dictionary.
+type EnumDictionary = (Arc<Type>, HashMap<String, usize>);
+
+/// Annotations of the columns of a row group that the rows do not carry: the
+/// dictionaries of the ENUM columns, mapping each value to its position in the column
+/// chunk's dictionary page, the UUID leaves and the timestamp leaves not adjusted to
+/// UTC.
+#[derive(Default)]
+struct ColumnAnnotations {
+    enums: Vec<EnumDictionary>,
+    uuids: Vec<TypePtr>,
+    local_timestamps: Vec<TypePtr>,
+}
+
+impl ColumnAnnotations {
+    fn read_enums(row_group: &dyn RowGroupReader, tag: &Tag) -> Result<Vec<EnumDictionary>, ShellError> {
+        let mut columns = Vec::new();
+        for (i, column) in row_group.metadata().schema_descr().columns().iter().enumerate() {
+            if column.logical_type() != LogicalType::ENUM || column.physical_type() != PhysicalType::BYTE_ARRAY {
+                continue;
+            }
+            let mut pages = row_group.get_column_page_reader(i)
+                .map_err(|e| parquet_error("Failed to read Parquet column", e, tag))?;
+            // The dictionary, when present, is always the first page of a column chunk.
+            if let Some(Page::DictionaryPage { buf, .. }) = pages.get_next_page()
+                .map_err(|e| parquet_error("Failed to read Parquet dictionary page", e, tag))? {
+                columns.push((column.self_type_ptr(), plain_strings(buf.data())));
+            }
+        }
+        Ok(columns)
+    }
+
+    fn ordinal(&self, ty: Option<&Type>, value: &str) -> Option<usize> {
+        let ty = ty?;
+        self.enums.iter()
+            .find(|(column, _)| std::ptr::eq(column.as_ref(), ty))
+            .and_then(|(_, dictionary)| dictionary.get(value).copied())
+    }
+
+    fn is_uuid(&self, ty: Option<&Type>) -> bool {
+        ty.is_some_and(|ty| self.uuids.iter().any(|uuid| std::ptr::eq(uuid.as_ref(), ty)))
+    }
+
+    fn is_local_timestamp(&self, ty: Option<&Type>) -> bool {
+        ty.is_some_and(|ty| self.local_timestamps.iter().any(|timestamp| std::ptr::eq(timestamp.as_ref(), ty)))
+    }
+}
+
+/// Decodes PLAIN encoded byte arrays, each prefixed by its little-endian length.
+fn plain_strings(mut data: &[u8]) -> HashMap<String, usize> {
+    let mut values = HashMap::new();
+    while data.len() >= 4 {
+        let len = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let value = match data.get(4..4 + len) {
+            Some(value) => value,
+            None => break,
+        };
+        let ordinal = values.len();
+        values.entry(String::from_utf8_lossy(value).into_owned()).or_insert(ordinal);
+        data = &data[4 + len..];
+    }
+    values
+}
+
+fn is_enum(ty: Option<&Type>) -> bool {
+    ty.is_some_and(|ty| ty.is_primitive() && ty.get_basic_info().logical_type() == LogicalType::ENUM)
+}
+
+static NON_STRING_MAP_KEYS: Once = Once::new();
+static INVALID_JSON: Once = Once::new();
+
+/// Returns the schema node of the `index`th child of a group, if the group is known.
+fn child_type(ty: Option<&Type>, index: usize) -> Option<&Type> {
+    match ty {
+        Some(ty) if ty.is_group() => ty.get_fields().get(index).map(|t| t.as_ref()),
+        _ => None,
+    }
+}
+
+fn is_string(ty: Option<&Type>) -> bool {
+    ty.is_some_and(|ty| {
+        ty.is_primitive() && matches!(ty.get_basic_info().logical_type(), LogicalType::UTF8 | LogicalType::ENUM | LogicalType::JSON)
+    })
+}
+
+/// The text of a string field. With `--validate-utf8` string columns arrive as bytes,
+/// which are checked here.
+fn field_str<'a>(field: &'a Field, ty: Option<&Type>) -> Result<Option<&'a str>, ConvertError> {
     match field {
-        Field::Null => UntaggedValue::nothing().into_value(tag),
-        Field::Bool(b) => UntaggedValue::boolean(*b).into_value(tag),
-        Field::Byte(b) => UntaggedValue::binary(vec![*b as u8]).into_value(tag),
-        Field::UByte(b) => UntaggedValue::binary(vec![*b]).into_value(tag),
+        Field::Str(s) => Ok(Some(s)),
+        Field::Bytes(bytes) if is_string(ty) => std::str::from_utf8(bytes.data()).map(Some).map_err(|e| {
+            ConvertError::new(
+                "Invalid UTF-8 in a string column",
+                format!("invalid byte sequence at byte offset {}", e.valid_up_to()),
+            )
+        }),
+        _ => Ok(None),
+    }
+}
+
+/// Whether `ty` is a binary column: a byte array without an annotation, which the
+/// row reader returns as bytes.
+fn is_binary(ty: Option<&Type>) -> bool {
+    ty.is_some_and(|ty| {
+        ty.is_primitive()
+            && matches!(ty.get_physical_type(), PhysicalType::BYTE_ARRAY | PhysicalType::FIXED_LEN_BYTE_ARRAY)
+            && matches!(ty.get_basic_info().logical_type(), LogicalType::NONE | LogicalType::BSON)
+    })
+}
+
+fn is_json(ty: Option<&Type>) -> bool {
+    ty.is_some_and(|ty| ty.is_primitive() && ty.get_basic_info().logical_type() == LogicalType::JSON)
+}
+
+fn json_to_nu(json: serde_json::Value, tag: Tag) -> Value {
+    match json {
+        serde_json::Value::Null => UntaggedValue::nothing().into_value(tag),
+        serde_json::Value::Bool(b) => UntaggedValue::boolean(b).into_value(tag),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                UntaggedValue::int(i).into_value(tag)
+            } else if let Some(u) = n.as_u64() {
+                UntaggedValue::int(u).into_value(tag)
+            } else {
+                match n.as_f64().and_then(BigDecimal::from_f64) {
+                    Some(d) => UntaggedValue::decimal(d).into_value(tag),
+                    None => UntaggedValue::string(n.to_string()).into_value(tag),
+                }
+            }
+        }
+        serde_json::Value::String(s) => UntaggedValue::string(s).into_value(tag),
+        serde_json::Value::Array(values) => {
+            let values = values.into_iter().map(|v| json_to_nu(v, tag.clone())).collect();
+            UntaggedValue::Table(values).into_value(tag)
+        }
+        serde_json::Value::Object(entries) => {
+            let record: IndexMap<String, Value> = entries.into_iter()
+                .map(|(k, v)| (k, json_to_nu(v, tag.clone())))
+                .collect();
+            UntaggedValue::row(record).into_value(tag)
+        }
+    }
+}
+
+/// Parses a JSON column value, keeping the raw string when it is not valid JSON.
+fn parse_json(s: &str, tag: Tag) -> Value {
+    match serde_json::from_str(s) {
+        Ok(json) => json_to_nu(json, tag),
+        Err(_) => {
+            INVALID_JSON.call_once(|| {
+                eprintln!("warning: JSON columns with invalid JSON are kept as strings");
+            });
+            UntaggedValue::string(s).into_value(tag)
+        }
+    }
+}
+
+fn convert_map(
+    map: &Map,
+    ty: Option<&Type>,
+    tag: Tag,
+    opts: &FromParquetOpts,
+    annotations: &ColumnAnnotations,
+) -> Result<Value, ConvertError> {
+    // MAP groups wrap a repeated key_value group holding the key and value fields.
+    let entry = child_type(ty, 0);
+    let (key_type, value_type) = (child_type(entry, 0), child_type(entry, 1));
+    let keys = map.entries().iter()
+        .map(|(key, _)| field_str(key, key_type))
+        .collect::<Result<Vec<_>, ConvertError>>()?;
+    if opts.map_as_record && keys.iter().all(Option::is_some) {
+        let mut record: IndexMap<String, Value> = IndexMap::with_capacity(map.len());
+        for (key, (_, value)) in keys.into_iter().flatten().zip(map.entries()) {
+            let value = convert_to_nu(value, value_type, tag.clone(), opts, annotations)
+                .map_err(|e| e.in_column(key))?;
+            record.insert(key.to_string(), value);
+        }
+        return Ok(UntaggedValue::row(record).into_value(tag));
+    }
+
+    if opts.map_as_record {
+        NON_STRING_MAP_KEYS.call_once(|| {
+            eprintln!("warning: maps with non-string keys are kept as lists of [key, value] pairs");
+        });
+    }
+    // Errors name the entry by its position, then `key` or `value`.
+    let pairs = map.entries().iter()
+        .enumerate()
+        .map(|(index, (key, value))| {
+            let pair = vec![
+                convert_to_nu(key, key_type, tag.clone(), opts, annotations)
+                    .map_err(|e| e.in_column("key").in_column(&index.to_string()))?,
+                convert_to_nu(value, value_type, tag.clone(), opts, annotations)
+                    .map_err(|e| e.in_column("value").in_column(&index.to_string()))?,
+            ];
+            Ok(UntaggedValue::Table(pair).into_value(tag.clone()))
+        })
+        .collect::<Result<Vec<_>, ConvertError>>()?;
+    Ok(UntaggedValue::Table(pairs).into_value(tag))
+}
+
+/// Whether the repeated group of a LIST is itself the element, as in the legacy
+/// two-level layouts the parquet row reader also accepts.
+fn is_legacy_element(repeated: &Type) -> bool {
+    repeated.is_primitive()
+        || repeated.get_fields().len() > 1
+        || repeated.name() == "array"
+        || repeated.name().ends_with("_tuple")
+}
+
+/// Returns the schema node of the elements of a list, if the list is known.
+fn list_element_type(ty: Option<&Type>) -> Option<&Type> {
+    let ty = ty.filter(|ty| ty.is_group())?;
+    let info = ty.get_basic_info();
+    if info.logical_type() == LogicalType::LIST {
+        let repeated = ty.get_fields().first()?;
+        return if is_legacy_element(repeated) {
+            Some(repeated)
+        } else {
+            child_type(Some(repeated), 0)
+        };
+    }
+    // Repeated groups outside of a LIST are lists of that group.
+    if info.has_repetition() && info.repetition() == Repetition::REPEATED {
+        Some(ty)
+    } else {
+        None
+    }
+}
+
+fn convert_list(
+    list: &List,
+    ty: Option<&Type>,
+    tag: Tag,
+    opts: &FromParquetOpts,
+    annotations: &ColumnAnnotations,
+) -> Result<Value, ConvertError> {
+    let element_type = list_element_type(ty);
+    if opts.list_stats {
+        return list_stats(list, element_type, tag, opts, annotations);
+    }
+    let elements = list.elements();
+    let kept = opts.max_list_elements.map_or(elements.len(), |max| max.min(elements.len()));
+    let mut values = elements[..kept].iter().enumerate()
+        .map(|(index, element)| {
+            let value = convert_to_nu(element, element_type, tag.clone(), opts, annotations)
+                .map_err(|e| e.in_column(&index.to_string()))?;
+            if !opts.extend_list && !opts.typed_lists {
+                return Ok(value);
+            }
+            let mut record = IndexMap::with_capacity(3);
+            if opts.extend_list {
+                record.insert("index".to_string(), UntaggedValue::int(index as u64).into_value(tag.clone()));
+            }
+            if opts.typed_lists {
+                record.insert("type".to_string(), UntaggedValue::string(value.type_name()).into_value(tag.clone()));
+            }
+            record.insert("value".to_string(), value);
+            Ok(UntaggedValue::row(record).into_value(tag.clone()))
+        })
+        .collect::<Result<Vec<_>, ConvertError>>()?;
+    if kept < elements.len() {
+        values.push(UntaggedValue::string(TRUNCATED_LIST).into_value(tag.clone()));
+    }
+    Ok(UntaggedValue::Table(values).into_value(tag))
+}
+
+/// The `--list-stats` record of a list: its length, the type of its elements in the
+/// schema and its first and last elements.
+fn list_stats(
+    list: &List,
+    element_type: Option<&Type>,
+    tag: Tag,
+    opts: &FromParquetOpts,
+    annotations: &ColumnAnnotations,
+) -> Result<Value, ConvertError> {
+    let elements = list.elements();
+    let element = |index: usize| match elements.get(index) {
+        Some(element) => convert_to_nu(element, element_type, tag.clone(), opts, annotations)
+            .map_err(|e| e.in_column(&index.to_string())),
+        None => Ok(UntaggedValue::nothing().into_value(tag.clone())),
+    };
+    let type_name = match element_type {
+        Some(ty) if ty.is_group() => UntaggedValue::string("GROUP"),
+        Some(ty) => match ty.get_basic_info().logical_type() {
+            LogicalType::NONE => UntaggedValue::string(ty.get_physical_type().to_string()),
+            logical_type => UntaggedValue::string(logical_type.to_string()),
+        },
+        None => UntaggedValue::nothing(),
+    };
+    let mut record = IndexMap::with_capacity(4);
+    record.insert("length".to_string(), UntaggedValue::int(elements.len() as u64).into_value(tag.clone()));
+    record.insert("element_type".to_string(), type_name.into_value(tag.clone()));
+    record.insert("first".to_string(), element(0)?);
+    record.insert("last".to_string(), element(elements.len().saturating_sub(1))?);
+    Ok(UntaggedValue::row(record).into_value(tag))
+}
+
+/// Converts a Parquet field into a nushell value. `ty` is the field's schema node, when
+/// known, and is used for conversions that depend on the column annotation.
+fn convert_to_nu(
+    field: &Field,
+    ty: Option<&Type>,
+    tag: impl Into<Tag>,
+    opts: &FromParquetOpts,
+    annotations: &ColumnAnnotations,
+) -> Result<Value, ConvertError> {
+    let value = match field {
+        Field::Null if opts.null_as_empty && is_string(ty) => UntaggedValue::string("").into_value(tag),
+        Field::Null if opts.null_as_empty && is_binary(ty) => binary_to_nu(&[], tag, opts),
+        Field::Null => match opts.null_representation {
+            NullRepresentation::Nothing => UntaggedValue::nothing().into_value(tag),
+            NullRepresentation::String => UntaggedValue::string("").into_value(tag),
+            NullRepresentation::Error => {
+                return Err(ConvertError::new(
+                    "Found a null value in the Parquet file",
+                    "nulls are not allowed with --null-as error",
+                ))
+            }
+        },
+        Field::Bool(b) => match opts.bool_representation {
+            BoolRepresentation::Bool => UntaggedValue::boolean(*b).into_value(tag),
+            BoolRepresentation::Int => UntaggedValue::int(u8::from(*b)).into_value(tag),
+            BoolRepresentation::String => UntaggedValue::string(b.to_string()).into_value(tag),
+        },
+        // `--binary-as-hex` and `--binary-as-base64` encode INT8 and UINT8 values as
+        // single bytes too.
+        Field::Byte(b) if opts.bytes_as_binary || opts.binary_representation != BinaryRepresentation::Binary => {
+            binary_to_nu(&[*b as u8], tag, opts)
+        }
+        Field::UByte(b) if opts.bytes_as_binary || opts.binary_representation != BinaryRepresentation::Binary => {
+            binary_to_nu(&[*b], tag, opts)
+        }
+        Field::Byte(b) => UntaggedValue::int(*b).into_value(tag),
+        Field::UByte(b) => UntaggedValue::int(*b).into_value(tag),
         Field::Short(s) => UntaggedValue::int(*s).into_value(tag),
         Field::UShort(s) => UntaggedValue::int(*s).into_value(tag),
-        Field::Int(i) => UntaggedValue::int(*i).into_value(tag),
+        Field::Int(i) => match time_unit(ty) {
+            Some(unit) => time_to_nu(i64::from(*i), unit, tag, opts),
+            None => UntaggedValue::int(*i).into_value(tag),
+        },
         Field::UInt(i) => UntaggedValue::int(*i).into_value(tag),
-        Field::Long(l) => UntaggedValue::int(*l).into_value(tag),
+        Field::Long(l) => match time_unit(ty) {
+            Some(unit) => time_to_nu(*l, unit, tag, opts),
+            None => UntaggedValue::int(*l).into_value(tag),
+        },
+        Field::ULong(l) if opts.overflow_as_string && *l > i64::MAX as u64 => {
+            UntaggedValue::string(l.to_string()).into_value(tag)
+        }
         Field::ULong(l) => UntaggedValue::int(*l).into_value(tag),
-        Field::Float(float) => {
-            if let Some(f) = BigDecimal::from_f32(*float) {
-                UntaggedValue::decimal(f).into_value(tag)
+        // Display writes the shortest digits that read back as the same f32.
+        Field::Float(float) if opts.float_precision == FloatPrecision::Exact => {
+            UntaggedValue::string(float.to_string()).into_value(tag)
+        }
+        // BigDecimal keeps a limited number of digits of a float, --strict checks that
+        // the decimal still reads back as the same float.
+        Field::Float(float) => match BigDecimal::from_f32(*float) {
+            Some(f) if !opts.strict || reads_back(&f, *float) => UntaggedValue::decimal(f).into_value(tag),
+            Some(_) => return Err(ConvertError::lossy(float, "decimal")),
+            None => return Err(ConvertError::not_finite(float)),
+        },
+        Field::Double(double) => match BigDecimal::from_f64(*double) {
+            Some(d) if !opts.strict || reads_back(&d, *double) => UntaggedValue::decimal(d).into_value(tag),
+            Some(_) => return Err(ConvertError::lossy(double, "decimal")),
+            None => return Err(ConvertError::not_finite(double)),
+        },
+        Field::Str(s) if opts.empty_as_null && s.is_empty() => UntaggedValue::nothing().into_value(tag),
+        Field::Str(s) if opts.extended_enum && is_enum(ty) => {
+            let tag = tag.into();
+            let ordinal = match annotations.ordinal(ty, s) {
+                Some(ordinal) => UntaggedValue::int(ordinal as u64),
+                None => UntaggedValue::nothing(),
+            };
+            let mut record = IndexMap::with_capacity(2);
+            record.insert("value".to_string(), UntaggedValue::string(s).into_value(tag.clone()));
+            record.insert("ordinal".to_string(), ordinal.into_value(tag.clone()));
+            UntaggedValue::row(record).into_value(tag)
+        }
+        Field::Str(s) if opts.parse_json && is_json(ty) => parse_json(s, tag.into()),
+        Field::Str(s) => match opts.max_string_length {
+            Some(max) if s.len() > max => {
+                let end = (0..=max).rev().find(|i| s.is_char_boundary(*i)).unwrap_or(0);
+                UntaggedValue::string(format!("{}{}", &s[..end], opts.truncation_marker)).into_value(tag)
+            }
+            _ => UntaggedValue::string(s).into_value(tag),
+        },
+        Field::Bytes(bytes) if annotations.is_uuid(ty) && bytes.data().len() == 16 => {
+            let uuid = Uuid::from_slice(bytes.data()).map_err(|e| ConvertError::new("Invalid UUID", e.to_string()))?;
+            UntaggedValue::string(uuid.hyphenated().to_string()).into_value(tag)
+        }
+        Field::Bytes(_) if is_string(ty) => {
+            let s = field_str(field, ty)?.unwrap_or_default().to_string();
+            return convert_to_nu(&Field::Str(s), ty, tag, opts, annotations);
+        }
+        Field::Bytes(bytes) if opts.empty_as_null && bytes.data().is_empty() => UntaggedValue::nothing().into_value(tag),
+        Field::Bytes(bytes) if is_int96(ty) => int96_to_nu(bytes.data(), tag, opts)?,
+        Field::Bytes(bytes) if is_interval(ty) => interval_to_nu(bytes.data(), tag, opts)?,
+        Field::Bytes(bytes) => {
+            let data = bytes.data();
+            let end = opts.max_binary_length.map_or(data.len(), |max| max.min(data.len()));
+            binary_to_nu(&data[..end], tag, opts)
+        }
+        // The parquet crate hands out the raw INT32/INT64 bits as unsigned values,
+        // casting back to signed keeps dates before the epoch.
+        Field::Date(days_since_epoch) if opts.no_convert_dates => {
+            UntaggedValue::int(*days_since_epoch as i32).into_value(tag)
+        }
+        Field::TimestampMillis(raw) | Field::TimestampMicros(raw) if opts.no_convert_dates => {
+            UntaggedValue::int(*raw as i64).into_value(tag)
+        }
+        Field::Date(days_since_epoch) => {
+            let days = *days_since_epoch as i32 as i64;
+            date_to_nu(days_to_date(days), days, tag, opts)?
+        }
+        Field::TimestampMillis(millis_since_epoch) => {
+            let millis = *millis_since_epoch as i64;
+            let date = localize_timestamp(timestamp_to_date(millis, TimeUnit::Millis), annotations.is_local_timestamp(ty), opts);
+            date_to_nu(date, millis, tag, opts)?
+        }
+        Field::TimestampMicros(micros_since_epoch) => {
+            let micros = *micros_since_epoch as i64;
+            let date = localize_timestamp(timestamp_to_date(micros, TimeUnit::Micros), annotations.is_local_timestamp(ty), opts);
+            date_to_nu(date, micros, tag, opts)?
+        }
+        Field::Decimal(decimal) => decimal_to_nu(decimal, tag, opts),
+        Field::Group(row) => convert_parquet_row(row, ty, tag, opts, annotations)?,
+        Field::ListInternal(list) => convert_list(list, ty, tag.into(), opts, annotations)?,
+        Field::MapInternal(map) => convert_map(map, ty, tag.into(), opts, annotations)?,
+    };
+    Ok(value)
+}
+
+/// Converts a row or nested group. Each field is converted with its own schema node from
+/// the group type `ty`, so annotations apply at every nesting level.
+fn convert_parquet_row(
+    row: &Row,
+    ty: Option<&Type>,
+    tag: impl Into<Tag>,
+    opts: &FromParquetOpts,
+    annotations: &ColumnAnnotations,
+) -> Result<Value, ConvertError> {
+    let mut map: IndexMap<String, Value> = IndexMap::with_capacity(row.len());
+    let tag = tag.into();
+    for (index, (name, field)) in row.get_column_iter().enumerate() {
+        let value = convert_to_nu(field, child_type(ty, index), tag.clone(), opts, annotations)
+            .map_err(|e| e.in_column(name))?;
+        map.insert(name.clone(), value);
+    }
+
+    Ok(UntaggedValue::row(map).into_value(tag))
+}
+
+/// Name endings of the integer columns `--infer-types` reads as dates.
+const DATE_SUFFIXES: &[&str] = &["_date", "_at", "_time", "_ts", "_timestamp"];
+
+/// The top-level INT32 and INT64 columns without annotation that `--infer-types` reads
+/// as dates, going by their names.
+fn inferred_dates(schema: &Type) -> Vec<&Type> {
+    schema.get_fields().iter()
+        .map(|field| &**field)
+        .filter(|field| {
+            field.is_primitive()
+                && matches!(field.get_physical_type(), PhysicalType::INT32 | PhysicalType::INT64)
+                && field.get_basic_info().logical_type() == LogicalType::NONE
+                && field.get_basic_info().repetition() != Repetition::REPEATED
+        })
+        .filter(|field| {
+            let name = field.name().to_ascii_lowercase();
+            DATE_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        })
+        .collect()
+}
+
+/// The field an integer of an `--infer-types` column would be with a DATE (INT32) or
+/// TIMESTAMP_MILLIS (INT64) annotation.
+fn infer_date(field: &Field) -> Field {
+    match field {
+        Field::Int(days) => Field::Date(*days as u32),
+        Field::Long(millis) => Field::TimestampMillis(*millis as u64),
+        field => field.clone(),
+    }
+}
+
+/// A row read with the parquet record API, converted into a nushell record with
+/// `Value::try_from` as `from parquet` converts the rows of a file. Conversions that need
+/// the schema of the column, such as those of TIME_MILLIS, JSON, ENUM, INT96 and
+/// INTERVAL annotations, are not applied.
+pub struct ParquetRowAdapter<'a>(pub &'a Row, pub Tag, pub &'a FromParquetOpts);
+
+impl TryFrom<ParquetRowAdapter<'_>> for Value {
+    type Error = ShellError;
+
+    fn try_from(ParquetRowAdapter(row, tag, opts): ParquetRowAdapter<'_>) -> Result<Self, Self::Error> {
+        convert_parquet_row(row, None, tag.clone(), opts, &ColumnAnnotations::default())
+            .map_err(|e| e.into_field_error(&tag))
+    }
+}
+
+/// A field of a row read with the parquet record API, converted into a nushell value
+/// with `Value::try_from` as `ParquetRowAdapter` converts the fields of rows.
+pub struct FieldAdapter<'a>(pub &'a Field, pub Tag, pub &'a FromParquetOpts);
+
+impl TryFrom<FieldAdapter<'_>> for Value {
+    type Error = ShellError;
+
+    fn try_from(FieldAdapter(field, tag, opts): FieldAdapter<'_>) -> Result<Self, Self::Error> {
+        convert_to_nu(field, None, tag.clone(), opts, &ColumnAnnotations::default())
+            .map_err(|e| e.into_field_error(&tag))
+    }
+}
+
+/// Converts a top-level row, like `convert_parquet_row`, with `raw_dates` used for the
+/// `--no-convert-dates-column` columns and the `inferred` columns read as dates.
+fn convert_record(
+    row: &Row,
+    schema: &Type,
+    tag: Tag,
+    opts: &FromParquetOpts,
+    raw_dates: Option<&FromParquetOpts>,
+    inferred: &[String],
+    annotations: &ColumnAnnotations,
+) -> Result<Value, ConvertError> {
+    if raw_dates.is_none() && inferred.is_empty() {
+        return convert_parquet_row(row, Some(schema), tag, opts, annotations);
+    }
+    let mut map: IndexMap<String, Value> = IndexMap::with_capacity(row.len());
+    for (index, (name, field)) in row.get_column_iter().enumerate() {
+        let opts = match raw_dates {
+            Some(raw_dates) if opts.no_convert_dates_columns.contains(name) => raw_dates,
+            _ => opts,
+        };
+        let inferred_field;
+        let field = if inferred.contains(name) {
+            inferred_field = infer_date(field);
+            &inferred_field
+        } else {
+            field
+        };
+        let value = convert_to_nu(field, child_type(Some(schema), index), tag.clone(), opts, annotations)
+            .map_err(|e| e.in_column(name))?;
+        map.insert(name.clone(), value);
+    }
+    Ok(UntaggedValue::row(map).into_value(tag))
+}
+
+fn flatten_into(flat: &mut IndexMap<String, Value>, prefix: Option<&str>, dict: Dictionary, depth: usize) {
+    for (name, value) in dict.entries {
+        let name = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, name),
+            None => name,
+        };
+        match value {
+            Value { value: UntaggedValue::Row(child), .. } if depth > 0 => {
+                flatten_into(flat, Some(&name), child, depth - 1)
+            }
+            value => {
+                flat.insert(name, value);
+            }
+        }
+    }
+}
+
+/// Lifts the fields of nested records `depth` levels deep into the top-level record,
+/// so `{address: {city: x}}` becomes `{address.city: x}`. Lists are left untouched.
+fn flatten_record(record: Value, depth: usize) -> Value {
+    match record {
+        Value { value: UntaggedValue::Row(dict), tag } => {
+            let mut flat = IndexMap::with_capacity(dict.length());
+            flatten_into(&mut flat, None, dict, depth);
+            UntaggedValue::row(flat).into_value(tag)
+        }
+        record => record,
+    }
+}
+
+/// The record of a row that failed to convert under `--emit-errors-as-rows`: every
+/// top-level column and `--select` column null, so that it goes through the column
+/// flags like the other rows.
+fn null_record(schema: &Type, selects: &[Select], tag: &Tag) -> Value {
+    let names = schema.get_fields().iter()
+        .map(|field| field.name())
+        .chain(selects.iter().map(|select| select.name.as_str()));
+    let map: IndexMap<String, Value> = names
+        .map(|name| (name.to_string(), UntaggedValue::nothing().into_value(tag)))
+        .collect();
+    UntaggedValue::row(map).into_value(tag)
+}
+
+/// Returns the record with `name` inserted as its first column.
+fn prepend_column(record: Value, name: &str, value: Value) -> Value {
+    match record {
+        Value { value: UntaggedValue::Row(dict), tag } => {
+            let mut map = IndexMap::with_capacity(dict.length() + 1);
+            map.insert(name.to_string(), value);
+            map.extend(dict.entries);
+            UntaggedValue::row(map).into_value(tag)
+        }
+        record => record,
+    }
+}
+
+/// The `--output-schema` record: `_schema` set to true, then the type name of every
+/// column of `first`.
+fn schema_row(first: Option<&Value>, tag: &Tag) -> Value {
+    let mut record = IndexMap::new();
+    record.insert("_schema".to_string(), UntaggedValue::boolean(true).into_value(tag));
+    if let Some(UntaggedValue::Row(dict)) = first.map(|row| &row.value) {
+        for (name, value) in &dict.entries {
+            record.insert(name.clone(), UntaggedValue::string(value.type_name()).into_value(tag));
+        }
+    }
+    UntaggedValue::row(record).into_value(tag)
+}
+
+fn append_columns(record: Value, columns: &[(String, Value)]) -> Value {
+    match record {
+        Value { value: UntaggedValue::Row(mut dict), tag } => {
+            dict.entries.extend(columns.iter().cloned());
+            UntaggedValue::Row(dict).into_value(tag)
+        }
+        record => record,
+    }
+}
+
+/// Builds a `<prefix><column>` column from the metadata of every chunk of the row group
+/// that belongs to the leaf columns being read.
+fn chunk_columns(
+    row_group: &RowGroupMetaData,
+    schema: &Type,
+    prefix: &str,
+    value: impl Fn(&ColumnChunkMetaData) -> UntaggedValue,
+) -> Vec<(String, Value)> {
+    row_group.columns().iter()
+        .filter(|column| {
+            let root = column.column_path().string();
+            schema.get_fields().iter().any(|f| root == f.name() || root.starts_with(&format!("{}.", f.name())))
+        })
+        .map(|column| {
+            let name = format!("{}{}", prefix, column.column_path().string());
+            (name, value(column).into_value(Tag::unknown()))
+        })
+        .collect()
+}
+
+/// The `_encoding_<column>` columns of a row group, listing the encodings of each chunk.
+fn encoding_columns(row_group: &RowGroupMetaData, schema: &Type) -> Vec<(String, Value)> {
+    chunk_columns(row_group, schema, "_encoding_", |column| {
+        UntaggedValue::Table(column.encodings().iter()
+            .map(|e| UntaggedValue::string(e.to_string()).into_value(Tag::unknown()))
+            .collect())
+    })
+}
+
+/// The `_compression_<column>` columns of a row group, naming the codec of each chunk.
+fn compression_columns(row_group: &RowGroupMetaData, schema: &Type) -> Vec<(String, Value)> {
+    chunk_columns(row_group, schema, "_compression_", |column| {
+        UntaggedValue::string(column.compression().to_string())
+    })
+}
+
+fn drop_columns(record: Value, columns: &[String]) -> Value {
+    match record {
+        Value { value: UntaggedValue::Row(mut dict), tag } => {
+            dict.entries.retain(|name, _| !columns.contains(name));
+            UntaggedValue::Row(dict).into_value(tag)
+        }
+        record => record,
+    }
+}
+
+fn rename_columns(record: Value, renames: &[(String, String)]) -> Value {
+    match record {
+        Value { value: UntaggedValue::Row(dict), tag } => {
+            let entries = dict.entries.into_iter()
+                .map(|(name, value)| match renames.iter().find(|(old, _)| *old == name) {
+                    Some((_, new)) => (new.clone(), value),
+                    None => (name, value),
+                })
+                .collect();
+            UntaggedValue::row(entries).into_value(tag)
+        }
+        record => record,
+    }
+}
+
+/// Whether the row has a null in one of the top-level `columns`, or in any top-level
+/// column when `columns` is empty. Nulls nested in groups and lists do not count.
+fn has_null(row: &Row, columns: &[String]) -> bool {
+    row.get_column_iter()
+        .any(|(name, field)| matches!(field, Field::Null) && (columns.is_empty() || columns.contains(name)))
+}
+
+/// Writes a value without its tags, so that equal values give equal text.
+pub(crate) fn write_key(value: &UntaggedValue, key: &mut String) {
+    match value {
+        UntaggedValue::Row(dict) => {
+            key.push('{');
+            for (name, value) in &dict.entries {
+                key.push_str(&format!("{:?}:", name));
+                write_key(&value.value, key);
+                key.push(',');
+            }
+            key.push('}');
+        }
+        UntaggedValue::Table(values) => {
+            key.push('[');
+            for value in values {
+                write_key(&value.value, key);
+                key.push(',');
+            }
+            key.push(']');
+        }
+        other => key.push_str(&format!("{:?}", other)),
+    }
+}
+
+/// The `--dedup` key of a row: the text of the values of its `keys` columns.
+fn dedup_key(row: &Value, keys: &[String], tag: &Tag) -> Result<String, ShellError> {
+    let dict = match &row.value {
+        UntaggedValue::Row(dict) => dict,
+        _ => return Ok(String::new()),
+    };
+    let mut key = String::new();
+    for name in keys {
+        match dict.entries.get(name) {
+            Some(value) => write_key(&value.value, &mut key),
+            None => {
+                let available: Vec<&str> = dict.entries.keys().map(|k| k.as_str()).collect();
+                return Err(ShellError::labeled_error(
+                    format!("Unknown column '{}' in --dedup", name),
+                    format!("available columns are: {}", available.join(", ")),
+                    tag,
+                ));
+            }
+        }
+        key.push('\u{1f}');
+    }
+    Ok(key)
+}
+
+/// Keeps the first row of every `--dedup` key, or the last one with `keep_last`, in
+/// the order the kept rows were read.
+fn dedup_rows(rows: Vec<Value>, keys: &[String], keep_last: bool, tag: &Tag) -> Result<Vec<Value>, ShellError> {
+    let mut seen = HashSet::new();
+    let mut kept = Vec::new();
+    let rows: Box<dyn Iterator<Item = Value>> = if keep_last {
+        Box::new(rows.into_iter().rev())
+    } else {
+        Box::new(rows.into_iter())
+    };
+    for row in rows {
+        if seen.insert(dedup_key(&row, keys, tag)?) {
+            kept.push(row);
+        }
+    }
+    if keep_last {
+        kept.reverse();
+    }
+    Ok(kept)
+}
+
+fn prefix_columns(record: Value, prefix: &str) -> Value {
+    match record {
+        Value { value: UntaggedValue::Row(dict), tag } => {
+            let entries = dict.entries.into_iter()
+                .map(|(name, value)| (format!("{}{}", prefix, name), value))
+                .collect();
+            UntaggedValue::row(entries).into_value(tag)
+        }
+        record => record,
+    }
+}
+
+/// Puts the columns listed in `order` first, in that order, and sorts the others by name.
+fn order_columns(record: Value, order: &[String]) -> Value {
+    match record {
+        Value { value: UntaggedValue::Row(dict), tag } => {
+            let mut entries: Vec<(String, Value)> = dict.entries.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| {
+                let rank = |name: &String| order.iter().position(|o| o == name).unwrap_or(order.len());
+                rank(a).cmp(&rank(b)).then_with(|| a.cmp(b))
+            });
+            UntaggedValue::row(entries.into_iter().collect()).into_value(tag)
+        }
+        record => record,
+    }
+}
+
+/// Warns about `--column-order` columns that are not in the output: the columns read,
+/// less the dropped ones, after the renames, and the `--select` columns.
+fn check_column_order(schema: &Type, opts: &FromParquetOpts) {
+    let output: Vec<&str> = schema.get_fields().iter()
+        .map(|f| f.name())
+        .filter(|name| !opts.drop_columns.iter().any(|c| c == name))
+        .map(|name| match opts.renames.iter().find(|(old, _)| old == name) {
+            Some((_, new)) => new.as_str(),
+            None => name,
+        })
+        .chain(opts.selects.iter().map(|s| s.name.as_str()))
+        .collect();
+    for name in &opts.column_order {
+        if !output.contains(&name.as_str()) {
+            eprintln!("warning: --column-order column '{}' is not in the output, it is ignored", name);
+        }
+    }
+}
+
+/// Warns about renamed columns that are not read and fails when a new name is taken
+/// by a column that keeps its name or by another rename.
+fn check_renames(schema: &Type, renames: &[(String, String)], tag: &Tag) -> Result<(), ShellError> {
+    let renamed = |name: &str| renames.iter().any(|(old, _)| old == name);
+    for (i, (old, new)) in renames.iter().enumerate() {
+        if !schema.get_fields().iter().any(|f| f.name() == old) {
+            eprintln!("warning: --rename column '{}' is not read, it is ignored", old);
+            continue;
+        }
+        let kept = schema.get_fields().iter().any(|f| f.name() == new && !renamed(f.name()));
+        let taken = renames[..i].iter().any(|(_, other)| other == new);
+        if kept || taken {
+            return Err(ShellError::labeled_error(
+                format!("Cannot rename column '{}' to '{}'", old, new),
+                format!("a column named '{}' is already in the output", new),
+                tag,
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Fails when a flag names columns that are not top-level columns of the schema.
+fn check_known_columns(schema: &Type, columns: &[String], flag: &str, tag: &Tag) -> Result<(), ShellError> {
+    let available: Vec<&str> = schema.get_fields().iter().map(|f| f.name()).collect();
+    let missing: Vec<&str> = columns.iter()
+        .map(|c| c.as_str())
+        .filter(|c| !available.contains(c))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    Err(ShellError::labeled_error(
+        format!("Unknown columns given to {}: {}", flag, missing.join(", ")),
+        format!("available columns are: {}", available.join(", ")),
+        tag,
+    ))
+}
+
+fn check_column_conflict(schema: &Type, name: &str, flag: &str, tag: &Tag) -> Result<(), ShellError> {
+    if schema.get_fields().iter().any(|f| f.name() == name) {
+        return Err(ShellError::labeled_error(
+            format!("Column '{}' already exists in the Parquet file", name),
+            format!("choose another column name for {}", flag),
+            tag,
+        ));
+    }
+    Ok(())
+}
+
+/// Keeps the parts of `ty` the dotted `columns` paths select, along with everything
+/// below them. Maps are kept whole, the row reader needs both their keys and values.
+fn prune(ty: &Arc<Type>, parent: &str, columns: &[String]) -> parquet::errors::Result<Option<Arc<Type>>> {
+    let path = if parent.is_empty() { ty.name().to_string() } else { format!("{}.{}", parent, ty.name()) };
+    if columns.contains(&path) {
+        return Ok(Some(ty.clone()));
+    }
+    let nested = format!("{}.", path);
+    if !ty.is_group() || !columns.iter().any(|c| c.starts_with(&nested)) {
+        return Ok(None);
+    }
+    let info = ty.get_basic_info();
+    if matches!(info.logical_type(), LogicalType::MAP | LogicalType::MAP_KEY_VALUE) {
+        return Ok(Some(ty.clone()));
+    }
+    let mut fields = Vec::new();
+    for field in ty.get_fields() {
+        if let Some(field) = prune(field, &path, columns)? {
+            fields.push(field);
+        }
+    }
+    let mut builder = Type::group_type_builder(ty.name())
+        .with_logical_type(info.logical_type())
+        .with_fields(&mut fields);
+    if info.has_repetition() {
+        builder = builder.with_repetition(info.repetition());
+    }
+    if info.has_id() {
+        builder = builder.with_id(info.id());
+    }
+    Ok(Some(Arc::new(builder.build()?)))
+}
+
+/// Builds a projection of the file schema that only keeps the requested columns. A
+/// column is a top-level name or the dotted path of a nested column, which keeps its
+/// parents with only the selected children. Every name is checked against the schema
+/// before any row is read.
+fn projection(schema: &Type, descr: &SchemaDescriptor, columns: &[String], tag: &Tag) -> Result<Type, ShellError> {
+    let leaves: Vec<String> = descr.columns().iter().map(|c| c.path().string()).collect();
+    let missing: Vec<&str> = columns.iter()
+        .map(|c| c.as_str())
+        .filter(|c| {
+            let nested = format!("{}.", c);
+            !leaves.iter().any(|leaf| leaf == c || leaf.starts_with(&nested))
+        })
+        .collect();
+    if !missing.is_empty() {
+        return Err(ShellError::labeled_error(
+            format!("Unknown columns: {}", missing.join(", ")),
+            format!("available leaf columns are: {}", leaves.join(", ")),
+            tag,
+        ));
+    }
+
+    let build_error = |e| parquet_error("Failed to build column projection", e, tag);
+    let mut fields = Vec::new();
+    for field in schema.get_fields() {
+        if let Some(field) = prune(field, "", columns).map_err(build_error)? {
+            fields.push(field);
+        }
+    }
+    Type::group_type_builder(schema.name())
+        .with_fields(&mut fields)
+        .build()
+        .map_err(build_error)
+}
+
+/// The rows to read from one row group.
+struct RowGroupSlice {
+    index: usize,
+    /// Position of the first row of the group in the file.
+    first_row: i64,
+    skip: usize,
+    take: usize,
+    /// Positions in the group to keep, sorted, when sampling.
+    positions: Option<Vec<usize>>,
+}
+
+/// The schemas rows are read and converted with.
+struct ReadSchema<'a> {
+    /// Schema of the converted columns, with all their annotations.
+    schema: &'a Type,
+    /// File schema handed to the row reader, when some columns need a readable one.
+    descr: Option<SchemaDescPtr>,
+    /// Projection handed to the row reader.
+    projection: Option<Type>,
+    /// UUID leaves of `schema`.
+    uuids: Vec<TypePtr>,
+    /// Timestamp leaves of `schema` not adjusted to UTC, when `--timezone` is set.
+    local_timestamps: Vec<TypePtr>,
+}
+
+fn read_row_group<R: ChunkReader + 'static>(
+    reader: &SerializedFileReader<R>,
+    slice: &RowGroupSlice,
+    read: &ReadSchema,
+    page_stats: Option<&PageStats>,
+    opts: &FromParquetOpts,
+    tag: &Tag,
+) -> Result<Vec<Value>, ShellError> {
+    let row_group = reader.get_row_group(slice.index)
+        .map_err(|e| parquet_error("Failed to read Parquet row group", e, tag))?;
+    let annotations = ColumnAnnotations {
+        enums: if opts.extended_enum { ColumnAnnotations::read_enums(row_group.as_ref(), tag)? } else { Vec::new() },
+        uuids: read.uuids.clone(),
+        local_timestamps: read.local_timestamps.clone(),
+    };
+    let row_group: Box<dyn RowGroupReader> = match &read.descr {
+        Some(descr) => Box::new(ReadableRowGroup::new(row_group, descr.clone())
+            .map_err(|e| parquet_error("Failed to read Parquet row group", e, tag))?),
+        None => row_group,
+    };
+    let rg_num_rows = reader.metadata().row_group(slice.index).num_rows();
+    let mut chunk_info = Vec::new();
+    if opts.encoding_info {
+        chunk_info.extend(encoding_columns(reader.metadata().row_group(slice.index), read.schema));
+    }
+    if opts.compression_info {
+        chunk_info.extend(compression_columns(reader.metadata().row_group(slice.index), read.schema));
+    }
+    let raw_dates = if opts.no_convert_dates_columns.is_empty() {
+        None
+    } else {
+        Some(FromParquetOpts { no_convert_dates: true, ..opts.clone() })
+    };
+    let inferred: Vec<String> = if opts.infer_types {
+        inferred_dates(read.schema).iter().map(|field| field.name().to_string()).collect()
+    } else {
+        Vec::new()
+    };
+    let iter = row_group.get_row_iter(read.projection.clone())
+        .map_err(|e| parquet_error("Failed to read Parquet rows", e, tag))?;
+
+    let mut rows = Vec::with_capacity(slice.take);
+    for (position, record) in iter.enumerate().skip(slice.skip).take(slice.take) {
+        if slice.positions.as_ref().is_some_and(|positions| positions.binary_search(&position).is_err()) {
+            continue;
+        }
+        if opts.filter.as_ref().is_some_and(|filter| !filter.matches(&record)) {
+            continue;
+        }
+        if (opts.only_nulls || !opts.only_nulls_in.is_empty()) && !has_null(&record, &opts.only_nulls_in) {
+            continue;
+        }
+        let converted = convert_record(&record, read.schema, tag.clone(), opts, raw_dates.as_ref(), &inferred, &annotations);
+        let (mut row, error) = match converted {
+            Ok(row) => (row, None),
+            Err(e) if opts.errors_as_rows => (null_record(read.schema, &opts.selects, tag), Some(e)),
+            Err(e) => return Err(e.into_shell_error(slice.first_row + position as i64, tag)),
+        };
+        // The values of a failed row are all null, there is nothing to cast or compute.
+        let (casts, selects) = match error {
+            Some(_) => (&[][..], &[][..]),
+            None => (&opts.casts[..], &opts.selects[..]),
+        };
+        for cast in casts {
+            cast.apply(&mut row).map_err(|label| {
+                ShellError::labeled_error(
+                    format!("Cannot cast column '{}' to {}", cast.column, cast.to),
+                    format!("{} at row {}", label, slice.first_row + position as i64),
+                    tag,
+                )
+            })?;
+        }
+        for select in selects {
+            select.apply(&mut row).map_err(|label| {
+                ShellError::labeled_error(
+                    format!("Cannot evaluate --select column '{}'", select.name),
+                    format!("{} at row {}", label, slice.first_row + position as i64),
+                    tag,
+                )
+            })?;
+        }
+        let row = if opts.drop_columns.is_empty() { row } else { drop_columns(row, &opts.drop_columns) };
+        let row = if opts.renames.is_empty() { row } else { rename_columns(row, &opts.renames) };
+        let row = if opts.sort_columns || !opts.column_order.is_empty() {
+            order_columns(row, &opts.column_order)
+        } else {
+            row
+        };
+        let row = match &opts.prefix_columns {
+            Some(prefix) => prefix_columns(row, prefix),
+            None => row,
+        };
+        let row = match opts.flatten {
+            Some(depth) => flatten_record(row, depth),
+            None => row,
+        };
+        let row = append_columns(row, &chunk_info);
+        let row = match page_stats {
+            Some(page_stats) => append_columns(row, &page_stats.columns(slice.index, position as i64)),
+            None => row,
+        };
+        let row = if opts.row_group_metadata {
+            let num_rows = UntaggedValue::int(rg_num_rows).into_value(Tag::unknown());
+            let index = UntaggedValue::int(slice.index as u64).into_value(Tag::unknown());
+            prepend_column(prepend_column(row, "_rg_num_rows", num_rows), "_rg_index", index)
+        } else {
+            row
+        };
+        let row = match &opts.row_group {
+            Some(name) => {
+                let row_group = UntaggedValue::int(slice.index as u64).into_value(Tag::unknown());
+                prepend_column(row, name, row_group)
+            }
+            None => row,
+        };
+        let row = match &opts.row_index {
+            Some(name) => {
+                let row_index = UntaggedValue::int(slice.first_row + position as i64).into_value(Tag::unknown());
+                prepend_column(row, name, row_index)
+            }
+            None => row,
+        };
+        let row = match error {
+            Some(error) => error.into_columns(slice.first_row + position as i64)
+                .into_iter()
+                .rev()
+                .fold(row, |row, (name, value)| prepend_column(row, name, value)),
+            None => row,
+        };
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Picks `amount` random rows out of the slices. Every sampled row group is still read
+/// up to its last picked row, so sampling costs a scan of those groups.
+fn sample_slices(slices: Vec<RowGroupSlice>, amount: usize, seed: Option<u64>) -> Vec<RowGroupSlice> {
+    let total: usize = slices.iter().map(|s| s.take).sum();
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut picked = rand::seq::index::sample(&mut rng, total, amount.min(total)).into_vec();
+    picked.sort_unstable();
+
+    let mut picked = picked.into_iter().peekable();
+    let mut offset = 0;
+    let mut sampled = Vec::new();
+    for slice in slices {
+        let end = offset + slice.take;
+        let mut positions = Vec::new();
+        while let Some(row) = picked.next_if(|row| *row < end) {
+            positions.push(slice.skip + row - offset);
+        }
+        offset = end;
+        if let (Some(first), Some(last)) = (positions.first(), positions.last()) {
+            sampled.push(RowGroupSlice {
+                skip: *first,
+                take: last - first + 1,
+                positions: Some(positions),
+                ..slice
+            });
+        }
+    }
+    sampled
+}
+
+/// The footer as thrift, when the read needs what parquet 3.0 leaves out of the parsed
+/// metadata: the UUID logical types, whether timestamps are adjusted to UTC for
+/// `--timezone`, and whether null counts were written at all for the statistics of a
+/// filter.
+fn thrift_footer<C: ChunkReader>(source: &C, opts: &FromParquetOpts, tag: &Tag) -> Result<Option<ThriftFileMetaData>, ShellError> {
+    if opts.no_decode_uuid && opts.timezone.is_none() && opts.filter.is_none() {
+        return Ok(None);
+    }
+    read_footer(source, tag).map(Some)
+}
+
+/// Paths of the leaf columns whose logical type is `annotated`. parquet 3.0 only keeps
+/// the converted types of the footer, which have no UUID and no timestamps that are
+/// not adjusted to UTC.
+fn annotated_columns(footer: &ThriftFileMetaData, annotated: fn(&ThriftLogicalType) -> bool) -> Vec<Vec<String>> {
+    let mut columns = Vec::new();
+    if let Some(root) = footer.schema.first() {
+        collect_annotated_columns(&footer.schema, root.num_children.unwrap_or(0), &mut 1, &mut Vec::new(), annotated, &mut columns);
+    }
+    columns
+}
+
+fn is_uuid_type(logical_type: &ThriftLogicalType) -> bool {
+    matches!(logical_type, ThriftLogicalType::UUID(_))
+}
+
+fn is_local_timestamp_type(logical_type: &ThriftLogicalType) -> bool {
+    matches!(logical_type, ThriftLogicalType::TIMESTAMP(timestamp) if !timestamp.is_adjusted_to_u_t_c)
+}
+
+/// Walks the `children` schema elements of a group from `next` on, elements being
+/// listed depth first, collecting the paths of the leaves whose logical type is
+/// `annotated`.
+fn collect_annotated_columns(
+    elements: &[SchemaElement],
+    children: i32,
+    next: &mut usize,
+    path: &mut Vec<String>,
+    annotated: fn(&ThriftLogicalType) -> bool,
+    columns: &mut Vec<Vec<String>>,
+) {
+    for _ in 0..children {
+        let element = match elements.get(*next) {
+            Some(element) => element,
+            None => return,
+        };
+        *next += 1;
+        path.push(element.name.clone());
+        match element.num_children {
+            Some(children) if children > 0 => collect_annotated_columns(elements, children, next, path, annotated, columns),
+            _ if element.logical_type.as_ref().is_some_and(annotated) => columns.push(path.clone()),
+            _ => {}
+        }
+        path.pop();
+    }
+}
+
+/// The leaves among `fields` below `path` whose paths are in `columns`.
+fn annotated_leaves(fields: &[TypePtr], path: &mut Vec<String>, columns: &[Vec<String>], leaves: &mut Vec<TypePtr>) {
+    for field in fields {
+        path.push(field.name().to_string());
+        if field.is_group() {
+            annotated_leaves(field.get_fields(), path, columns, leaves);
+        } else if columns.contains(path) {
+            leaves.push(field.clone());
+        }
+        path.pop();
+    }
+}
+
+type OpenedBytes = (SerializedFileReader<SliceableCursor>, Option<PageStats>, Option<ThriftFileMetaData>);
+
+fn open_bytes(bytes: Vec<u8>, opts: &FromParquetOpts, tag: &Tag) -> Result<OpenedBytes, ShellError> {
+    let cursor = SliceableCursor::new(bytes);
+    // The reader takes the cursor, the page index and the thrift footer are read
+    // through one sharing its bytes.
+    let footer_source = match cursor.len() {
+        len if len > 0 => Some(cursor.get_read(0, len as usize)
+            .map_err(|e| parquet_error("Failed to open Parquet file", e, tag))?),
+        _ => None,
+    };
+    let reader = open_source(cursor, opts.decryption_key.is_some(), tag)?;
+    let page_stats = match &footer_source {
+        Some(source) if opts.page_stats => Some(PageStats::read(source, reader.metadata(), tag)?),
+        _ => None,
+    };
+    let footer = match &footer_source {
+        Some(source) => thrift_footer(source, opts, tag)?,
+        None => None,
+    };
+    Ok((reader, page_stats, footer))
+}
+
+/// Reads the Parquet file `bytes` into a table of its rows, or the single value of the
+/// options that describe the file instead.
+pub fn from_parquet_bytes(bytes: Vec<u8>, tag: impl Into<Tag>, opts: &FromParquetOpts) -> Result<Value, ShellError> {
+    let started = Instant::now();
+    let tag = tag.into();
+    let (reader, page_stats, footer) = open_bytes(bytes, opts, &tag)?;
+    read_parquet(&reader, page_stats.as_ref(), footer.as_ref(), tag, opts, started)
+}
+
+/// Reads the Parquet file at `path` through a memory map instead of loading it whole.
+pub fn from_parquet_mmap(path: &Path, tag: impl Into<Tag>, opts: &FromParquetOpts) -> Result<Value, ShellError> {
+    let started = Instant::now();
+    let tag = tag.into();
+    let file = map_file(path, &tag)?;
+    let reader = open_source(file.clone(), opts.decryption_key.is_some(), &tag)?;
+    let page_stats = if opts.page_stats {
+        Some(PageStats::read(&file, reader.metadata(), &tag)?)
+    } else {
+        None
+    };
+    let footer = thrift_footer(&file, opts, &tag)?;
+    read_parquet(&reader, page_stats.as_ref(), footer.as_ref(), tag, opts, started)
+}
+
+/// Names the top-level columns two schemas disagree on: those missing from either
+/// one, and those of another type.
+pub(crate) fn schema_differences(a: &Type, b: &Type) -> Vec<String> {
+    let mut differences: Vec<String> = a.get_fields().iter()
+        .filter(|field| !b.get_fields().iter().any(|other| other == *field))
+        .map(|field| field.name().to_string())
+        .collect();
+    for field in b.get_fields() {
+        let name = field.name().to_string();
+        if !differences.contains(&name) && !a.get_fields().iter().any(|other| other == field) {
+            differences.push(name);
+        }
+    }
+    differences
+}
+
+/// Reads several Parquet files into one table, in order. Every file is read with the
+/// same options, so `--skip`, `--limit` and the like apply to each file on its own.
+/// Files must share the schema of the first one unless `ignore_schema_errors` is set.
+/// With `with_filename` every row gets a `_filename` column with the path of its file.
+pub fn from_parquet_files(
+    files: &[PathBuf],
+    tag: impl Into<Tag>,
+    opts: &FromParquetOpts,
+    with_filename: bool,
+    ignore_schema_errors: bool,
+) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let mut first: Option<(&PathBuf, Type)> = None;
+    let mut rows = Vec::new();
+    let mut plans = Vec::new();
+    // The schema record goes before the rows of all files, not those of each one, and
+    // duplicates are looked for across files.
+    let file_opts = FromParquetOpts {
+        output_schema: false,
+        dedup: Vec::new(),
+        describe: false,
+        group_by: None,
+        ..opts.clone()
+    };
+    for file in files {
+        let started = Instant::now();
+        let (reader, page_stats, footer) = open_bytes(read_file(file, &tag)?, opts, &tag)?;
+        let schema = reader.metadata().file_metadata().schema().clone();
+        if with_filename {
+            check_column_conflict(&schema, "_filename", "--with-filename", &tag)?;
+        }
+        if opts.output_schema {
+            check_column_conflict(&schema, "_schema", "--output-schema", &tag)?;
+        }
+        match &first {
+            Some((first_file, first_schema)) if !ignore_schema_errors => {
+                let differences = schema_differences(first_schema, &schema);
+                if !differences.is_empty() {
+                    return Err(ShellError::labeled_error(
+                        format!(
+                            "Incompatible Parquet schemas in {} and {}",
+                            first_file.display(),
+                            file.display()
+                        ),
+                        format!(
+                            "columns {} differ, pass --ignore-schema-errors to read them anyway",
+                            differences.join(", ")
+                        ),
+                        &tag,
+                    ));
+                }
+            }
+            Some(_) => {}
+            None => first = Some((file, schema)),
+        }
+        let value = read_parquet(&reader, page_stats.as_ref(), footer.as_ref(), tag.clone(), &file_opts, started)?;
+        if opts.explain {
+            plans.push(format!("{}:\n{}", file.display(), value.as_string()?));
+            continue;
+        }
+        let values = match value.value {
+            UntaggedValue::Table(values) => values,
+            _ => vec![value],
+        };
+        let filename = UntaggedValue::string(file.display().to_string()).into_value(&tag);
+        rows.extend(values.into_iter().map(|row| {
+            if with_filename {
+                prepend_column(row, "_filename", filename.clone())
             } else {
-                unreachable!("Internal error: protocol did not use f32-compatible decimal")
+                row
             }
+        }));
+    }
+    if opts.explain {
+        return Ok(UntaggedValue::string(plans.join("\n\n")).into_value(tag));
+    }
+    if !opts.dedup.is_empty() {
+        rows = dedup_rows(rows, &opts.dedup, opts.dedup_keep_last, &tag)?;
+    }
+    if let Some(key) = &opts.group_by {
+        return group_rows(&rows, key, &opts.aggregations, &tag);
+    }
+    if opts.describe {
+        return Ok(describe(&rows, &tag));
+    }
+    if opts.output_schema {
+        rows.insert(0, schema_row(rows.first(), &tag));
+    }
+    Ok(UntaggedValue::Table(rows).into_value(tag))
+}
+
+fn kv_meta_value(value: Option<&String>, parse_json: bool, tag: &Tag) -> Value {
+    match value {
+        Some(value) if parse_json => match serde_json::from_str(value) {
+            Ok(json) => json_to_nu(json, tag.clone()),
+            Err(_) => UntaggedValue::string(value).into_value(tag),
         },
-        Field::Double(double) => {
-            if let Some(d) = BigDecimal::from_f64(*double) {
-                UntaggedValue::decimal(d).into_value(tag)
-            } else {
-                unreachable!("Internal error: protocol did not use f64-compatible decimal")
+        Some(value) => UntaggedValue::string(value).into_value(tag),
+        None => UntaggedValue::nothing().into_value(tag),
+    }
+}
+
+/// Returns the footer key-value metadata as a record, or the value of a single key.
+fn kv_meta(metadata: &FileMetaData, opts: &FromParquetOpts, tag: &Tag) -> Result<Value, ShellError> {
+    let pairs = metadata.key_value_metadata().iter().flatten();
+    if let Some(key) = &opts.kv_meta_key {
+        return match pairs.clone().find(|kv| &kv.key == key) {
+            Some(kv) => Ok(kv_meta_value(kv.value.as_ref(), opts.parse_kv_json, tag)),
+            None => {
+                let available: Vec<&str> = pairs.map(|kv| kv.key.as_str()).collect();
+                Err(ShellError::labeled_error(
+                    format!("Unknown key-value metadata key '{}'", key),
+                    format!("available keys are: {}", available.join(", ")),
+                    tag,
+                ))
+            }
+        };
+    }
+
+    let record: IndexMap<String, Value> = pairs
+        .map(|kv| (kv.key.clone(), kv_meta_value(kv.value.as_ref(), opts.parse_kv_json, tag)))
+        .collect();
+    Ok(UntaggedValue::row(record).into_value(tag))
+}
+
+/// Converts a Hugging Face feature into a record of its `type` and attributes. Features
+/// holding other features, as `Sequence` does, are converted recursively.
+fn hf_feature(json: serde_json::Value, tag: &Tag) -> Value {
+    match json {
+        serde_json::Value::Object(mut entries) => match entries.remove("_type") {
+            Some(ty) => {
+                let mut record = IndexMap::with_capacity(entries.len() + 1);
+                record.insert("type".to_string(), json_to_nu(ty, tag.clone()));
+                for (key, value) in entries {
+                    let value = match key.as_str() {
+                        "feature" => hf_feature(value, tag),
+                        _ => json_to_nu(value, tag.clone()),
+                    };
+                    record.insert(key, value);
+                }
+                UntaggedValue::row(record).into_value(tag)
+            }
+            // A dict of named features.
+            None => {
+                let record: IndexMap<String, Value> = entries.into_iter()
+                    .map(|(name, feature)| (name, hf_feature(feature, tag)))
+                    .collect();
+                UntaggedValue::row(record).into_value(tag)
             }
         },
-        Field::Str(s) => UntaggedValue::string(s).into_value(tag),
-        Field::Bytes(bytes) => UntaggedValue::binary(bytes.data().to_vec()).into_value(tag),
-        Field::Date(days_since_epoch) => {
-            let value = epoch.add(Duration::days(*days_since_epoch as i64));
-            UntaggedValue::date(value).into_value(tag)
+        // A list of elements of the one feature it holds.
+        serde_json::Value::Array(features) => {
+            let features = features.into_iter().map(|feature| hf_feature(feature, tag)).collect();
+            UntaggedValue::Table(features).into_value(tag)
         }
-        Field::TimestampMillis(millis_since_epoch) => {
-            let value = epoch.add(Duration::milliseconds(*millis_since_epoch as i64));
-            UntaggedValue::date(value).into_value(tag)
+        other => json_to_nu(other, tag.clone()),
+    }
+}
+
+/// Returns the dataset info the Hugging Face `datasets` library stores as JSON in the
+/// `huggingface` key-value metadata key, under `info`. A `dataset_info` key holding the
+/// info itself is read as well.
+fn hf_metadata(metadata: &FileMetaData, tag: &Tag) -> Result<Value, ShellError> {
+    let pairs = metadata.key_value_metadata().iter().flatten();
+    let (key, value) = match pairs.clone()
+        .find(|kv| kv.key == "huggingface")
+        .or_else(|| pairs.clone().find(|kv| kv.key == "dataset_info"))
+    {
+        Some(kv) => (kv.key.as_str(), kv.value.as_deref().unwrap_or_default()),
+        None => {
+            let available: Vec<&str> = pairs.map(|kv| kv.key.as_str()).collect();
+            return Err(ShellError::labeled_error(
+                "No Hugging Face dataset info in the file",
+                format!("expected a huggingface key-value metadata key, available keys are: {}", available.join(", ")),
+                tag,
+            ));
         }
-        Field::TimestampMicros(micros_since_epoch) => {
-            let value = epoch.add(Duration::microseconds(*micros_since_epoch as i64));
-            UntaggedValue::date(value).into_value(tag)
+    };
+    let mut json: serde_json::Value = serde_json::from_str(value).map_err(|e| {
+        ShellError::labeled_error(format!("Invalid Hugging Face dataset info in key '{}'", key), e.to_string(), tag)
+    })?;
+    let mut info = match json.get_mut("info") {
+        Some(info) if key == "huggingface" => info.take(),
+        _ => json,
+    };
+    let mut field = |name: &str| info.get_mut(name).map(serde_json::Value::take).unwrap_or_default();
+    let dataset_name = match field("dataset_name") {
+        serde_json::Value::Null => field("builder_name"),
+        name => name,
+    };
+    let mut record = IndexMap::with_capacity(5);
+    record.insert("dataset_name".to_string(), json_to_nu(dataset_name, tag.clone()));
+    record.insert("description".to_string(), json_to_nu(field("description"), tag.clone()));
+    record.insert("features".to_string(), hf_feature(field("features"), tag));
+    record.insert("splits".to_string(), json_to_nu(field("splits"), tag.clone()));
+    record.insert("download_size".to_string(), json_to_nu(field("download_size"), tag.clone()));
+    Ok(UntaggedValue::row(record).into_value(tag))
+}
+
+fn is_delta(encoding: Encoding) -> bool {
+    matches!(
+        encoding,
+        Encoding::DELTA_BINARY_PACKED | Encoding::DELTA_LENGTH_BYTE_ARRAY | Encoding::DELTA_BYTE_ARRAY
+    )
+}
+
+/// Walks the pages of every column chunk and counts the pages of each encoding, along
+/// with the bytes of the PLAIN and delta encoded ones. Sizes are those of the pages
+/// once decompressed, as the page reader hands them out.
+fn delta_encoding<R: ChunkReader + 'static>(reader: &SerializedFileReader<R>, tag: &Tag) -> Result<Value, ShellError> {
+    let mut rows = Vec::new();
+    for index in 0..reader.num_row_groups() {
+        let row_group = reader.get_row_group(index)
+            .map_err(|e| parquet_error("Failed to read Parquet row group", e, tag))?;
+        for (i, column) in row_group.metadata().columns().iter().enumerate() {
+            let mut pages = row_group.get_column_page_reader(i)
+                .map_err(|e| parquet_error("Failed to read Parquet column", e, tag))?;
+            let mut counts: IndexMap<String, u64> = IndexMap::new();
+            let (mut plain_bytes, mut delta_bytes) = (0u64, 0u64);
+            while let Some(page) = pages.get_next_page()
+                .map_err(|e| parquet_error("Failed to read Parquet page", e, tag))? {
+                let size = page.buffer().len() as u64;
+                match page.encoding() {
+                    Encoding::PLAIN => plain_bytes += size,
+                    encoding if is_delta(encoding) => delta_bytes += size,
+                    _ => {}
+                }
+                *counts.entry(page.encoding().to_string()).or_insert(0) += 1;
+            }
+            let encodings = counts.into_iter()
+                .map(|(encoding, pages)| (encoding, UntaggedValue::int(pages).into_value(tag.clone())))
+                .collect();
+            let mut record = IndexMap::with_capacity(5);
+            record.insert("row_group".to_string(), UntaggedValue::int(index as u64).into_value(tag.clone()));
+            record.insert("column".to_string(), UntaggedValue::string(column.column_path().string()).into_value(tag.clone()));
+            record.insert("pages".to_string(), UntaggedValue::row(encodings).into_value(tag.clone()));
+            record.insert("plain_bytes".to_string(), UntaggedValue::int(plain_bytes).into_value(tag.clone()));
+            record.insert("delta_bytes".to_string(), UntaggedValue::int(delta_bytes).into_value(tag.clone()));
+            rows.push(UntaggedValue::row(record).into_value(tag.clone()));
         }
-        Field::Decimal(_d) => unimplemented!("Parquet DECIMAL is not handled yet"),
-        Field::Group(_row) => { unimplemented!("Nested structs not supported yet") }
-        Field::ListInternal(_list) => { unimplemented!("Lists not supported yet") }
-        Field::MapInternal(_map) => { unimplemented!("Maps not supported yet") }
     }
+    Ok(UntaggedValue::Table(rows).into_value(tag))
 }
 
-fn convert_parquet_row(row: Row, tag: impl Into<Tag>) -> Value {
-    let mut map: IndexMap<String, Value> = IndexMap::with_capacity(row.len());
-    let mut row_iter = row.get_column_iter();
-    let tag = tag.into();
-    while let Some(col) = row_iter.next() {
-        map.insert(col.0.clone(), convert_to_nu(col.1, tag.clone()));
+fn read_parquet<R: ChunkReader + Send + Sync + 'static>(
+    reader: &SerializedFileReader<R>,
+    page_stats: Option<&PageStats>,
+    footer: Option<&ThriftFileMetaData>,
+    tag: Tag,
+    opts: &FromParquetOpts,
+    started: Instant,
+) -> Result<Value, ShellError> {
+    if let Some(value) = file_info(reader, opts, &tag)? {
+        return Ok(value);
+    }
+    let plan = plan_read(reader, opts, footer, &tag)?;
+    if opts.explain {
+        return Ok(UntaggedValue::string(plan.explain(reader)).into_value(tag));
+    }
+    let read = plan.read_schema(reader.metadata().file_metadata().schema());
+    let row_groups = if opts.parallel {
+        plan.slices.par_iter()
+            .map(|slice| read_row_group(reader, slice, &read, page_stats, opts, &tag))
+            .collect::<Result<Vec<_>, ShellError>>()?
+    } else {
+        plan.slices.iter()
+            .map(|slice| read_row_group(reader, slice, &read, page_stats, opts, &tag))
+            .collect::<Result<Vec<_>, ShellError>>()?
+    };
+    let rows = row_groups.into_iter().flatten();
+    let mut result: Vec<Value> = if opts.filters_rows() {
+        rows.skip(opts.skip).take(opts.limit.unwrap_or(usize::MAX)).collect()
+    } else {
+        rows.collect()
+    };
+    if !opts.dedup.is_empty() {
+        result = dedup_rows(result, &opts.dedup, opts.dedup_keep_last, &tag)?;
+    }
+    if opts.verbose {
+        plan.report(reader, result.len(), started);
+    }
+    if let Some(key) = &opts.group_by {
+        return group_rows(&result, key, &opts.aggregations, &tag);
+    }
+    if opts.describe {
+        return Ok(describe(&result, &tag));
+    }
+    if opts.output_schema {
+        result.insert(0, schema_row(result.first(), &tag));
     }
 
-    UntaggedValue::row(map).into_value(tag)
+    Ok(UntaggedValue::Table(result).into_value(Tag::unknown()))
 }
 
-pub fn from_parquet_bytes(bytes: Vec<u8>) -> ReturnValue {
-    let cursor = SliceableCursor::new(bytes);
-    let reader = SerializedFileReader::new(cursor).unwrap();
-        let mut iter = reader.get_row_iter(None)
-            .unwrap();
-    let mut result = Vec::new();
-    while let Some(record) = iter.next() {
-        let row = convert_parquet_row(record, Tag::unknown());
-        result.push(row);
-    }
-
-    let value: Value = UntaggedValue::Table(result).into_value(Tag::unknown());
-    ReturnValue::from(value)
-}
\ No newline at end of file
+/// The single value of the flags that describe the file rather than read its rows.
+fn file_info<R: ChunkReader + 'static>(
+    reader: &SerializedFileReader<R>,
+    opts: &FromParquetOpts,
+    tag: &Tag,
+) -> Result<Option<Value>, ShellError> {
+    if opts.kv_meta || opts.kv_meta_key.is_some() {
+        return kv_meta(reader.metadata().file_metadata(), opts, tag).map(Some);
+    }
+    if opts.hf_metadata {
+        return hf_metadata(reader.metadata().file_metadata(), tag).map(Some);
+    }
+    if opts.show_delta_encoding {
+        return delta_encoding(reader, tag).map(Some);
+    }
+    Ok(None)
+}
+
+/// What to read from a file, resolved from its footer before any row is read.
+struct ReadPlan {
+    projection: Option<Type>,
+    descr: Option<SchemaDescPtr>,
+    read_projection: Option<Type>,
+    slices: Vec<RowGroupSlice>,
+    /// Row groups left out because the statistics of the filter ruled them out.
+    pruned: Vec<usize>,
+    /// Paths of the UUID leaf columns of the file.
+    uuid_columns: Vec<Vec<String>>,
+    /// Paths of the timestamp leaf columns not adjusted to UTC, when `--timezone` is set.
+    local_timestamp_columns: Vec<Vec<String>>,
+}
+
+/// Sizes of the column chunks a plan reads, from the footer.
+struct ReadCost {
+    /// Compressed bytes of the column chunks.
+    bytes: i64,
+    /// Compressed and uncompressed bytes of the column chunks, what decoding them holds
+    /// at most besides the converted rows.
+    memory: i64,
+    /// Rows decoded, those skipped within a row group included.
+    rows: usize,
+}
+
+impl ReadPlan {
+    /// Leaf columns read, `None` for all of them.
+    fn leaves(&self) -> Option<Vec<String>> {
+        self.projection.as_ref().map(|projection| {
+            SchemaDescriptor::new(Arc::new(projection.clone())).columns().iter()
+                .map(|column| column.path().string())
+                .collect()
+        })
+    }
+
+    fn cost<R: ChunkReader + 'static>(&self, reader: &SerializedFileReader<R>) -> ReadCost {
+        let leaves = self.leaves();
+        let mut cost = ReadCost { bytes: 0, memory: 0, rows: 0 };
+        for slice in &self.slices {
+            cost.rows += slice.skip + slice.take;
+            for chunk in reader.metadata().row_group(slice.index).columns() {
+                if leaves.as_ref().is_none_or(|leaves| leaves.contains(&chunk.column_path().string())) {
+                    cost.bytes += chunk.compressed_size();
+                    cost.memory += chunk.compressed_size() + chunk.uncompressed_size();
+                }
+            }
+        }
+        cost
+    }
+
+    /// Reports the `--verbose` statistics of a completed read on stderr.
+    fn report<R: ChunkReader + 'static>(&self, reader: &SerializedFileReader<R>, rows_returned: usize, started: Instant) {
+        let cost = self.cost(reader);
+        eprintln!("bytes read: {}", cost.bytes);
+        eprintln!("row groups read: {}", self.slices.len());
+        eprintln!("row groups skipped by statistics: {}", self.pruned.len());
+        eprintln!("rows decoded: {}", cost.rows);
+        eprintln!("rows returned: {}", rows_returned);
+        eprintln!("peak memory estimate: {} bytes", cost.memory);
+        eprintln!("elapsed: {} ms", started.elapsed().as_millis());
+    }
+
+    /// The `--explain` description of the plan, one line per item. Row groups that are
+    /// neither read nor ruled out by statistics were left out by `--row-groups`,
+    /// `--skip`, `--limit` or `--sample`.
+    fn explain<R: ChunkReader + 'static>(&self, reader: &SerializedFileReader<R>) -> String {
+        let all: Vec<String> = reader.metadata().file_metadata().schema_descr().columns().iter()
+            .map(|column| column.path().string())
+            .collect();
+        let mut lines = vec![match self.leaves() {
+            Some(leaves) => format!("columns: {} of {} leaf columns: {}", leaves.len(), all.len(), leaves.join(", ")),
+            None => format!("columns: all {} leaf columns: {}", all.len(), all.join(", ")),
+        }];
+        let num_row_groups = reader.num_row_groups();
+        lines.push(format!(
+            "row groups: {} of {} read, {} skipped by statistics",
+            self.slices.len(),
+            num_row_groups,
+            self.pruned.len()
+        ));
+        for index in 0..num_row_groups {
+            let num_rows = reader.metadata().row_group(index).num_rows();
+            let line = match self.slices.iter().find(|slice| slice.index == index) {
+                Some(RowGroupSlice { positions: Some(positions), .. }) => {
+                    format!("read {} sampled rows of {}", positions.len(), num_rows)
+                }
+                Some(slice) if slice.skip == 0 && slice.take as i64 == num_rows => format!("read all {} rows", num_rows),
+                Some(slice) => format!("read rows {} to {} of {}", slice.skip, slice.skip + slice.take, num_rows),
+                None if self.pruned.contains(&index) => format!("skipped by statistics, {} rows", num_rows),
+                None => format!("not read, {} rows", num_rows),
+            };
+            lines.push(format!("  row group {}: {}", index, line));
+        }
+        let cost = self.cost(reader);
+        lines.push(format!("estimated rows to decode: {}", cost.rows));
+        lines.push(format!("estimated bytes read: {}", cost.bytes));
+        lines.push(format!("estimated peak memory: {} bytes", cost.memory));
+        lines.join("\n")
+    }
+
+    fn read_schema<'a>(&'a self, file_schema: &'a Type) -> ReadSchema<'a> {
+        let schema = self.projection.as_ref().unwrap_or(file_schema);
+        let leaves = |columns: &[Vec<String>]| {
+            let mut leaves = Vec::new();
+            if !columns.is_empty() {
+                annotated_leaves(schema.get_fields(), &mut Vec::new(), columns, &mut leaves);
+            }
+            leaves
+        };
+        ReadSchema {
+            schema,
+            descr: self.descr.clone(),
+            projection: self.read_projection.clone(),
+            uuids: leaves(&self.uuid_columns),
+            local_timestamps: leaves(&self.local_timestamp_columns),
+        }
+    }
+}
+
+/// Checks the flags against the schema of the file and picks the rows to read.
+fn plan_read<R: ChunkReader + 'static>(
+    reader: &SerializedFileReader<R>,
+    opts: &FromParquetOpts,
+    footer: Option<&ThriftFileMetaData>,
+    tag: &Tag,
+) -> Result<ReadPlan, ShellError> {
+    let projection = match &opts.columns {
+        Some(columns) => {
+            let file_metadata = reader.metadata().file_metadata();
+            Some(projection(file_metadata.schema(), file_metadata.schema_descr(), columns, tag)?)
+        }
+        None => None,
+    };
+    if let Some(name) = &opts.row_index {
+        check_column_conflict(reader.metadata().file_metadata().schema(), name, "--with-row-index", tag)?;
+    }
+    if let Some(name) = &opts.row_group {
+        check_column_conflict(reader.metadata().file_metadata().schema(), name, "--with-row-group", tag)?;
+    }
+    for (enabled, prefix, flag) in &[
+        (opts.encoding_info, "_encoding_", "--encoding-info"),
+        (opts.compression_info, "_compression_", "--compression-info"),
+        (opts.page_stats, "_page_min_", "--page-stats"),
+        (opts.page_stats, "_page_max_", "--page-stats"),
+    ] {
+        if !enabled {
+            continue;
+        }
+        for column in reader.metadata().file_metadata().schema_descr().columns() {
+            let name = format!("{}{}", prefix, column.path().string());
+            check_column_conflict(reader.metadata().file_metadata().schema(), &name, flag, tag)?;
+        }
+    }
+    if opts.errors_as_rows {
+        for name in &["_error", "_error_message", "_row_index", "_column"] {
+            check_column_conflict(reader.metadata().file_metadata().schema(), name, "--emit-errors-as-rows", tag)?;
+        }
+    }
+    if opts.row_group_metadata {
+        for name in &["_rg_index", "_rg_num_rows"] {
+            check_column_conflict(reader.metadata().file_metadata().schema(), name, "--include-row-group-metadata", tag)?;
+        }
+    }
+    // Columns the row reader cannot decode are read through a schema without their
+    // annotations, `schema` keeps them for the conversion.
+    let read_error = |e| parquet_error("Failed to build Parquet read schema", e, tag);
+    let descr = read_schema(reader.metadata().file_metadata().schema(), opts.validate_utf8)
+        .map_err(read_error)?
+        .map(|readable| Arc::new(SchemaDescriptor::new(Arc::new(readable))));
+    let read_projection = match &projection {
+        Some(projection) => Some(read_schema(projection, opts.validate_utf8).map_err(read_error)?.unwrap_or_else(|| projection.clone())),
+        None => None,
+    };
+    let schema = projection.as_ref().unwrap_or_else(|| reader.metadata().file_metadata().schema());
+    let num_row_groups = reader.num_row_groups();
+    let row_groups = match &opts.row_groups {
+        Some(indices) => {
+            if let Some(index) = indices.iter().find(|i| **i >= num_row_groups) {
+                return Err(ShellError::labeled_error(
+                    format!("Row group {} is out of range", index),
+                    format!("valid row groups are 0 to {}", num_row_groups as i64 - 1),
+                    tag,
+                ));
+            }
+            indices.clone()
+        }
+        None => (0..num_row_groups).collect(),
+    };
+
+    if let Some(filter) = &opts.filter {
+        filter.check(schema, tag)?;
+    }
+    for cast in &opts.casts {
+        cast.check(schema, tag)?;
+    }
+    for (i, select) in opts.selects.iter().enumerate() {
+        check_column_conflict(schema, &select.name, "--select", tag)?;
+        select.check(schema, &opts.selects[..i], tag)?;
+    }
+    check_renames(schema, &opts.renames, tag)?;
+    check_column_order(schema, opts);
+    if opts.infer_types {
+        for field in inferred_dates(schema) {
+            let unit = match field.get_physical_type() {
+                PhysicalType::INT32 => "days",
+                _ => "milliseconds",
+            };
+            eprintln!("warning: --infer-types reads column '{}' as {} since the epoch", field.name(), unit);
+        }
+    }
+    check_known_columns(schema, &opts.drop_columns, "--drop-columns", tag)?;
+    check_known_columns(schema, &opts.only_nulls_in, "--only-nulls-in", tag)?;
+    check_known_columns(schema, &opts.no_convert_dates_columns, "--no-convert-dates-column", tag)?;
+    if opts.output_schema {
+        check_column_conflict(schema, "_schema", "--output-schema", tag)?;
+    }
+
+    // Skip and limit are resolved up front from the row counts in the footer, so that
+    // every row group can be read on its own. With a filter they count matching rows,
+    // which are only known once read.
+    let mut slices = Vec::new();
+    let (mut skip, mut remaining) = if opts.filters_rows() {
+        (0, usize::MAX)
+    } else {
+        (opts.skip, opts.limit.unwrap_or(usize::MAX))
+    };
+    let mut pruned = Vec::new();
+    for index in row_groups {
+        if remaining == 0 {
+            break;
+        }
+        if opts.filter.as_ref().is_some_and(|filter| !filter.may_match(reader.metadata().row_group(index), footer.and_then(|footer| footer.row_groups.get(index)))) {
+            pruned.push(index);
+            continue;
+        }
+        let num_rows = reader.metadata().row_group(index).num_rows() as usize;
+        if skip >= num_rows {
+            skip -= num_rows;
+            continue;
+        }
+        let first_row: i64 = reader.metadata().row_groups()[..index].iter()
+            .map(|rg| rg.num_rows())
+            .sum();
+        let take = (num_rows - skip).min(remaining);
+        slices.push(RowGroupSlice { index, first_row, skip, take, positions: None });
+        remaining -= take;
+        skip = 0;
+    }
+    if let Some(amount) = opts.sample {
+        slices = sample_slices(slices, amount, opts.seed);
+    }
+
+    let uuid_columns = match footer {
+        Some(footer) if !opts.no_decode_uuid => annotated_columns(footer, is_uuid_type),
+        _ => Vec::new(),
+    };
+    let local_timestamp_columns = match footer {
+        Some(footer) if opts.timezone.is_some() => annotated_columns(footer, is_local_timestamp_type),
+        _ => Vec::new(),
+    };
+    Ok(ReadPlan { projection, descr, read_projection, slices, pruned, uuid_columns, local_timestamp_columns })
+}
+
+/// Rows of a Parquet file read one row group at a time, so that only the converted
+/// rows of the current group are held. Flags describing the file yield their single
+/// value.
+struct ParquetRows {
+    reader: SerializedFileReader<SliceableCursor>,
+    page_stats: Option<PageStats>,
+    plan: ReadPlan,
+    opts: FromParquetOpts,
+    tag: Tag,
+    next_slice: usize,
+    rows: std::vec::IntoIter<Value>,
+    /// Rows still to skip and to yield when a filter makes them known only once read.
+    skip: usize,
+    remaining: usize,
+    schema_pending: bool,
+    /// The first row, read ahead to build the `--output-schema` record from it.
+    first: Option<Value>,
+    /// `--dedup` keys of the rows yielded so far, when keeping the first row of each.
+    seen: Option<HashSet<String>>,
+    /// Start of the read until `--verbose` has reported it.
+    started: Option<Instant>,
+    returned: usize,
+    done: bool,
+}
+
+impl ParquetRows {
+    fn load_next(&mut self) -> Result<bool, ShellError> {
+        while self.rows.len() == 0 {
+            let slice = match self.plan.slices.get(self.next_slice) {
+                Some(slice) => slice,
+                None => return Ok(false),
+            };
+            self.next_slice += 1;
+            let read = self.plan.read_schema(self.reader.metadata().file_metadata().schema());
+            let rows = read_row_group(&self.reader, slice, &read, self.page_stats.as_ref(), &self.opts, &self.tag)?;
+            self.rows = rows.into_iter();
+        }
+        Ok(true)
+    }
+
+    /// The next row after skip, limit and dedup.
+    fn next_row(&mut self) -> Option<Result<Value, ShellError>> {
+        while !self.done {
+            match self.load_next() {
+                Ok(true) => {}
+                Ok(false) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    self.started = None;
+                    return Some(Err(e));
+                }
+            }
+            if self.done || self.remaining == 0 {
+                self.done = true;
+                break;
+            }
+            let row = self.rows.next();
+            if self.skip > 0 {
+                self.skip -= 1;
+                continue;
+            }
+            self.remaining -= 1;
+            if let (Some(seen), Some(row)) = (&mut self.seen, &row) {
+                match dedup_key(row, &self.opts.dedup, &self.tag) {
+                    Ok(key) => {
+                        if !seen.insert(key) {
+                            continue;
+                        }
+                    }
+                    Err(e) => {
+                        self.done = true;
+                        self.started = None;
+                        return Some(Err(e));
+                    }
+                }
+            }
+            self.returned += 1;
+            return row.map(Ok);
+        }
+        if let (true, Some(started)) = (self.opts.verbose, self.started.take()) {
+            self.plan.report(&self.reader, self.returned, started);
+        }
+        None
+    }
+}
+
+impl Iterator for ParquetRows {
+    type Item = Result<Value, ShellError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.schema_pending {
+            self.schema_pending = false;
+            self.first = match self.next_row() {
+                Some(Ok(row)) => Some(row),
+                Some(Err(e)) => return Some(Err(e)),
+                None => None,
+            };
+            return Some(Ok(schema_row(self.first.as_ref(), &self.tag)));
+        }
+        match self.first.take() {
+            Some(row) => Some(Ok(row)),
+            None => self.next_row(),
+        }
+    }
+}
+
+/// Reads a Parquet file as a stream of rows. nu 0.26 plugins answer with all their
+/// values at once, so the plugin reads whole tables with `from_parquet_bytes`; this
+/// lets callers that consume rows as they come hold one row group at a time.
+pub fn from_parquet_stream(
+    bytes: Vec<u8>,
+    tag: impl Into<Tag>,
+    opts: &FromParquetOpts,
+) -> Result<Box<dyn Iterator<Item = Result<Value, ShellError>> + Send>, ShellError> {
+    let started = Instant::now();
+    let tag = tag.into();
+    let (reader, page_stats, footer) = open_bytes(bytes, opts, &tag)?;
+    if let Some(value) = file_info(&reader, opts, &tag)? {
+        return Ok(Box::new(std::iter::once(Ok(value))));
+    }
+    // Summaries, groups and the explained plan are single values, they are made as
+    // for the table.
+    if opts.describe || opts.group_by.is_some() || opts.explain {
+        let summary = read_parquet(&reader, page_stats.as_ref(), footer.as_ref(), tag, opts, started)?;
+        return Ok(Box::new(std::iter::once(Ok(summary))));
+    }
+    let plan = plan_read(&reader, opts, footer.as_ref(), &tag)?;
+    let (skip, remaining) = if opts.filters_rows() {
+        (opts.skip, opts.limit.unwrap_or(usize::MAX))
+    } else {
+        (0, usize::MAX)
+    };
+    let keep_first = !opts.dedup.is_empty() && !opts.dedup_keep_last;
+    let rows = ParquetRows {
+        reader,
+        page_stats,
+        plan,
+        opts: opts.clone(),
+        tag: tag.clone(),
+        next_slice: 0,
+        rows: Vec::new().into_iter(),
+        skip,
+        remaining,
+        schema_pending: opts.output_schema && (opts.dedup.is_empty() || keep_first),
+        first: None,
+        seen: if keep_first { Some(HashSet::new()) } else { None },
+        started: Some(started),
+        returned: 0,
+        done: false,
+    };
+    if opts.dedup.is_empty() || keep_first {
+        return Ok(Box::new(rows));
+    }
+
+    // The last row of a key is only known once every row is read.
+    let rows = rows.collect::<Result<Vec<_>, ShellError>>()?;
+    let mut rows = dedup_rows(rows, &opts.dedup, true, &tag)?;
+    if opts.output_schema {
+        rows.insert(0, schema_row(rows.first(), &tag));
+    }
+    Ok(Box::new(rows.into_iter().map(Ok)))
+}
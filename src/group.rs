@@ -0,0 +1,208 @@
+use nu_errors::ShellError;
+use nu_protocol::{Primitive, ShellTypeName, UntaggedValue, Value};
+use nu_source::Tag;
+use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
+use indexmap::IndexMap;
+use std::cmp::Ordering;
+use std::fmt;
+use crate::from_parquet::write_key;
+
+/// Aggregation of the values of a column within a `--group-by` group. Nulls are left
+/// out of every aggregation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AggOp {
+    /// Sum of numbers, an integer when every value is one.
+    Sum,
+    /// Smallest number, string, date or boolean.
+    Min,
+    /// Largest number, string, date or boolean.
+    Max,
+    /// Number of values that are not null.
+    Count,
+    /// Mean of numbers, as a decimal.
+    Mean,
+    /// First value that is not null.
+    First,
+}
+
+impl AggOp {
+    /// Looks up an aggregation by the name `--agg` takes, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sum" => Some(AggOp::Sum),
+            "min" => Some(AggOp::Min),
+            "max" => Some(AggOp::Max),
+            "count" => Some(AggOp::Count),
+            "mean" => Some(AggOp::Mean),
+            "first" => Some(AggOp::First),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AggOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            AggOp::Sum => "sum",
+            AggOp::Min => "min",
+            AggOp::Max => "max",
+            AggOp::Count => "count",
+            AggOp::Mean => "mean",
+            AggOp::First => "first",
+        };
+        f.write_str(name)
+    }
+}
+
+/// An `--agg` of a top-level column, eg `amount:sum`. Its result is the column
+/// `<column>_<op>` of the group records.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregation {
+    /// Top-level column to aggregate.
+    pub column: String,
+    /// How its values are aggregated.
+    pub op: AggOp,
+}
+
+impl Aggregation {
+    /// Parses `<column>:<op>`. The op follows the last colon, so column names may
+    /// contain colons.
+    pub fn parse(text: &str) -> Result<Aggregation, String> {
+        let (column, op) = match text.rfind(':') {
+            Some(at) => (&text[..at], &text[at + 1..]),
+            None => return Err(format!("expected <column>:<op>, found '{}'", text)),
+        };
+        if column.is_empty() {
+            return Err("expected a column before the op".to_string());
+        }
+        let op = AggOp::from_name(op)
+            .ok_or_else(|| format!("unknown op '{}', expected sum, min, max, count, mean or first", op))?;
+        Ok(Aggregation { column: column.to_string(), op })
+    }
+
+    fn name(&self) -> String {
+        format!("{}_{}", self.column, self.op)
+    }
+
+    /// Aggregates the values of the column in a group, returning why they cannot be
+    /// otherwise.
+    fn apply(&self, values: &[&Value]) -> Result<UntaggedValue, String> {
+        let values: Vec<&Value> = values.iter()
+            .copied()
+            .filter(|value| !matches!(value.value, UntaggedValue::Primitive(Primitive::Nothing)))
+            .collect();
+        match self.op {
+            AggOp::Count => Ok(UntaggedValue::int(values.len() as u64)),
+            AggOp::First => Ok(values.first().map_or_else(UntaggedValue::nothing, |value| value.value.clone())),
+            AggOp::Sum | AggOp::Mean => {
+                let mut sum = BigDecimal::from(0);
+                let mut decimals = false;
+                for value in &values {
+                    sum += number(value).ok_or_else(|| format!("found {}", value.type_name()))?;
+                    decimals |= matches!(value.value, UntaggedValue::Primitive(Primitive::Decimal(_)));
+                }
+                if self.op == AggOp::Sum {
+                    return Ok(match decimals {
+                        false => UntaggedValue::int(sum.with_scale(0).as_bigint_and_exponent().0),
+                        true => UntaggedValue::decimal(sum),
+                    });
+                }
+                let mean = sum.to_f64().map(|sum| sum / values.len() as f64);
+                Ok(match mean.and_then(BigDecimal::from_f64) {
+                    Some(mean) => UntaggedValue::decimal(mean),
+                    None => UntaggedValue::nothing(),
+                })
+            }
+            AggOp::Min | AggOp::Max => {
+                let wanted = if self.op == AggOp::Min { Ordering::Less } else { Ordering::Greater };
+                let mut best: Option<&Value> = None;
+                for value in values {
+                    best = match best {
+                        Some(current) => match compare(value, current) {
+                            Some(ordering) if ordering == wanted => Some(value),
+                            Some(_) => Some(current),
+                            None => return Err(format!("cannot compare {} with {}", value.type_name(), current.type_name())),
+                        },
+                        None => Some(value),
+                    };
+                }
+                Ok(best.map_or_else(UntaggedValue::nothing, |value| value.value.clone()))
+            }
+        }
+    }
+}
+
+fn number(value: &Value) -> Option<BigDecimal> {
+    match &value.value {
+        UntaggedValue::Primitive(Primitive::Int(i)) => Some(BigDecimal::from(i.clone())),
+        UntaggedValue::Primitive(Primitive::Decimal(d)) => Some(d.clone()),
+        _ => None,
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    if let (Some(a), Some(b)) = (number(a), number(b)) {
+        return a.partial_cmp(&b);
+    }
+    match (&a.value, &b.value) {
+        (UntaggedValue::Primitive(Primitive::String(a)), UntaggedValue::Primitive(Primitive::String(b))) => Some(a.cmp(b)),
+        (UntaggedValue::Primitive(Primitive::Date(a)), UntaggedValue::Primitive(Primitive::Date(b))) => Some(a.cmp(b)),
+        (UntaggedValue::Primitive(Primitive::Boolean(a)), UntaggedValue::Primitive(Primitive::Boolean(b))) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn unknown_column(flag: &str, name: &str, record: &IndexMap<String, Value>, tag: &Tag) -> ShellError {
+    let available: Vec<&str> = record.keys().map(|k| k.as_str()).collect();
+    ShellError::labeled_error(
+        format!("Unknown column '{}' in {}", name, flag),
+        format!("available columns are: {}", available.join(", ")),
+        tag,
+    )
+}
+
+/// The key value of a group and the records of its rows.
+type Group<'a> = (Value, Vec<&'a IndexMap<String, Value>>);
+
+/// Groups `rows` by the value of their `key` column, in the order the keys first
+/// appear, into records of the key and the aggregations. Without aggregations the
+/// records hold the key and the number of rows as `count`.
+pub(crate) fn group_rows(rows: &[Value], key: &str, aggs: &[Aggregation], tag: &Tag) -> Result<Value, ShellError> {
+    let mut groups: IndexMap<String, Group> = IndexMap::new();
+    for row in rows {
+        let record = match &row.value {
+            UntaggedValue::Row(dict) => &dict.entries,
+            _ => continue,
+        };
+        let value = record.get(key).ok_or_else(|| unknown_column("--group-by", key, record, tag))?;
+        for agg in aggs {
+            if !record.contains_key(&agg.column) {
+                return Err(unknown_column("--agg", &agg.column, record, tag));
+            }
+        }
+        let mut text = String::new();
+        write_key(&value.value, &mut text);
+        groups.entry(text)
+            .or_insert_with(|| (value.clone(), Vec::new()))
+            .1
+            .push(record);
+    }
+
+    let mut records = Vec::with_capacity(groups.len());
+    for (_, (value, members)) in groups {
+        let mut record = IndexMap::with_capacity(aggs.len() + 1);
+        record.insert(key.to_string(), value);
+        if aggs.is_empty() {
+            record.insert("count".to_string(), UntaggedValue::int(members.len() as u64).into_value(tag));
+        }
+        for agg in aggs {
+            let values: Vec<&Value> = members.iter().map(|member| &member[&agg.column]).collect();
+            let result = agg.apply(&values).map_err(|label| {
+                ShellError::labeled_error(format!("Cannot aggregate column '{}' with {}", agg.column, agg.op), label, tag)
+            })?;
+            record.insert(agg.name(), result.into_value(tag));
+        }
+        records.push(UntaggedValue::row(record).into_value(tag));
+    }
+    Ok(UntaggedValue::Table(records).into_value(tag))
+}
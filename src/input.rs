@@ -0,0 +1,86 @@
+use nu_errors::ShellError;
+use nu_protocol::{Primitive, UntaggedValue, Value};
+use nu_source::Tag;
+use std::path::{Path, PathBuf};
+
+/// Accumulates the binary chunks piped into a Parquet reading command.
+#[derive(Default)]
+pub struct BinaryInput {
+    bytes: Vec<u8>,
+    tag: Option<Tag>,
+}
+
+impl BinaryInput {
+    /// An input holding no bytes yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a binary piped in, failing on any other value.
+    pub fn push(&mut self, input: Value, name_tag: &Tag) -> Result<(), ShellError> {
+        match input {
+            Value {
+                value: UntaggedValue::Primitive(Primitive::Binary(b)),
+                tag,
+            } => {
+                self.bytes.extend_from_slice(&b);
+                self.tag.get_or_insert(tag);
+                Ok(())
+            }
+            Value { tag, .. } => Err(ShellError::labeled_error_with_secondary(
+                "Expected binary from pipeline",
+                "requires binary input",
+                name_tag.clone(),
+                "value originates from here",
+                tag,
+            )),
+        }
+    }
+
+    /// Whether nothing has been piped in.
+    pub fn is_empty(&self) -> bool {
+        self.tag.is_none()
+    }
+
+    /// Returns the collected bytes along with the tag of the input they came from,
+    /// falling back to `name_tag` when nothing was piped in.
+    pub fn take(&mut self, name_tag: &Tag) -> (Vec<u8>, Tag) {
+        let tag = self.tag.take().unwrap_or_else(|| name_tag.clone());
+        (std::mem::take(&mut self.bytes), tag)
+    }
+}
+
+/// Reads a Parquet file from disk, for commands given a path instead of piped binary.
+pub fn read_file(path: &Path, tag: &Tag) -> Result<Vec<u8>, ShellError> {
+    std::fs::read(path).map_err(|e| {
+        ShellError::labeled_error(
+            format!("Failed to read {}", path.display()),
+            e.to_string(),
+            tag,
+        )
+    })
+}
+
+/// Expands a glob pattern such as `year=*/month=*/*.parquet` into the files it matches,
+/// in alphabetical order.
+pub fn glob_files(pattern: &str, tag: &Tag) -> Result<Vec<PathBuf>, ShellError> {
+    let paths = glob::glob(pattern)
+        .map_err(|e| ShellError::labeled_error("Invalid glob pattern", e.to_string(), tag))?;
+    let mut files = Vec::new();
+    for path in paths {
+        let path = path.map_err(|e| {
+            ShellError::labeled_error(format!("Failed to read {}", e.path().display()), e.error().to_string(), tag)
+        })?;
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    if files.is_empty() {
+        return Err(ShellError::labeled_error(
+            "No Parquet files found",
+            format!("no file matches '{}'", pattern),
+            tag,
+        ));
+    }
+    Ok(files)
+}
@@ -0,0 +1,120 @@
+//! Reading and writing Parquet files as Nushell values.
+//!
+//! The plugins in `src/bin` are thin wrappers around the functions of this crate, which
+//! can also be used on their own:
+//!
+//! ```no_run
+//! use nu_plugin_from_parquet::{from_parquet_bytes, FromParquetOpts};
+//!
+//! let bytes = std::fs::read("data.parquet").unwrap();
+//! let table = from_parquet_bytes(bytes, nu_source::Tag::unknown(), &FromParquetOpts::default()).unwrap();
+//! println!("{} rows", table.table_entries().count());
+//! ```
+#![warn(missing_docs)]
+/// Bloom filters written by `to parquet` and checked by `parquet bloom-check`.
+pub mod bloom;
+/// Casts of columns to other types, for `--cast`.
+pub mod cast;
+/// Conversion of Parquet values into Nushell values.
+pub mod convert;
+mod describe;
+/// Schema and row differences of two files, for `parquet diff`.
+pub mod diff;
+/// Computed columns, for `--select`.
+pub mod expr;
+/// Row predicates, for `--filter`.
+pub mod filter;
+/// Schema hashes, for `parquet fingerprint`.
+pub mod fingerprint;
+/// Arrow IPC files and streams, for `from arrow`.
+pub mod from_arrow;
+/// Reading Parquet files into tables, for `from parquet`.
+pub mod from_parquet;
+/// Per-group aggregates, for `--group-by`.
+pub mod group;
+/// Collection of the binary input of a plugin.
+pub mod input;
+/// Number separators of locales, for the text of extended decimals.
+pub mod locale;
+/// Concatenation of Parquet files, for `parquet merge`.
+pub mod merge;
+/// File-level metadata, for `parquet meta`.
+pub mod meta;
+mod mmap;
+mod page_index;
+/// Page headers of a column chunk, for `parquet pages`.
+pub mod pages;
+mod readable;
+/// Row group metadata, for `parquet rowgroups`.
+pub mod rowgroups;
+/// The schema tree, for `parquet schema`.
+pub mod schema;
+/// Hive-style partitioning, for `parquet split`.
+pub mod split;
+/// Column chunk statistics, for `parquet stats`.
+pub mod stats;
+/// Writing tables as Parquet files, for `to parquet`.
+pub mod to_parquet;
+/// Structural checks, for `parquet validate`.
+pub mod validate;
+
+pub use from_parquet::{
+    from_parquet_bytes, from_parquet_stream, FieldAdapter, FromParquetOpts, FromParquetOptsBuilder, ParquetRowAdapter,
+};
+
+use nu_errors::ShellError;
+use nu_source::Tag;
+use parquet::errors::ParquetError;
+use parquet::file::reader::ChunkReader;
+use parquet::file::serialized_reader::{SerializedFileReader, SliceableCursor};
+use std::io::Read;
+
+/// Files whose footer is encrypted end with this magic instead of `PAR1`.
+const ENCRYPTED_MAGIC: &[u8] = b"PARE";
+
+pub(crate) fn parquet_error(msg: &str, error: ParquetError, tag: &Tag) -> ShellError {
+    ShellError::labeled_error(msg, error.to_string(), tag)
+}
+
+fn encrypted_error(decryption_key: bool, tag: &Tag) -> ShellError {
+    let label = if decryption_key {
+        "the file uses Parquet modular encryption, which the parquet 3.0 reader cannot decrypt"
+    } else {
+        "the file uses Parquet modular encryption, it needs a --decryption-key and a reader with encryption support, which parquet 3.0 lacks"
+    };
+    ShellError::labeled_error("Cannot read encrypted Parquet file", label, tag)
+}
+
+/// Opens a Parquet file, telling encrypted files apart from corrupt ones. Those with
+/// an encrypted footer end with `PARE`, those with a plaintext footer have encrypted
+/// columns whose metadata parquet 3.0 finds missing.
+pub(crate) fn open_source<C: ChunkReader + 'static>(
+    source: C,
+    decryption_key: bool,
+    tag: &Tag,
+) -> Result<SerializedFileReader<C>, ShellError> {
+    let mut tail = Vec::with_capacity(ENCRYPTED_MAGIC.len());
+    if source.len() >= ENCRYPTED_MAGIC.len() as u64 {
+        source.get_read(source.len() - ENCRYPTED_MAGIC.len() as u64, ENCRYPTED_MAGIC.len())
+            .map_err(|e| parquet_error("Failed to open Parquet file", e, tag))?
+            .read_to_end(&mut tail)
+            .map_err(|e| ShellError::labeled_error("Failed to open Parquet file", e.to_string(), tag))?;
+    }
+    if tail == ENCRYPTED_MAGIC {
+        return Err(encrypted_error(decryption_key, tag));
+    }
+    let reader = SerializedFileReader::new(source).map_err(|e| match e {
+        ParquetError::General(message) if message == "Expected to have column metadata" => {
+            encrypted_error(decryption_key, tag)
+        }
+        e => parquet_error("Failed to open Parquet file", e, tag),
+    })?;
+    if decryption_key {
+        eprintln!("warning: the file is not encrypted, --decryption-key is ignored");
+    }
+    Ok(reader)
+}
+
+pub(crate) fn open_reader(bytes: Vec<u8>, tag: &Tag) -> Result<SerializedFileReader<SliceableCursor>, ShellError> {
+    open_source(SliceableCursor::new(bytes), false, tag)
+}
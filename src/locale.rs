@@ -0,0 +1,73 @@
+use num_format::Locale;
+
+pub use num_format::Grouping;
+
+/// Decimal and thousands separators of a locale, for the `text` of extended decimals.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NumberFormat {
+    /// Locale as it was asked for.
+    pub locale: String,
+    /// Separator of the fraction.
+    pub decimal: &'static str,
+    /// Separator of the digit groups.
+    pub thousands: &'static str,
+    /// How the digits are grouped.
+    pub grouping: Grouping,
+}
+
+impl NumberFormat {
+    /// Looks up a CLDR locale such as `de`, `de-CH` or `de_CH`, ignoring case. Locales
+    /// with a region fall back to their language when the region is not known.
+    pub fn for_locale(locale: &str) -> Result<NumberFormat, String> {
+        let normalized = locale.replace('_', "-");
+        let language = normalized.split('-').next().unwrap_or_default();
+        let known = |name: &str| Locale::available_names().iter().find(|known| known.eq_ignore_ascii_case(name)).copied();
+        let found = known(&normalized).or_else(|| known(language)).map(Locale::from_name);
+        match found {
+            Some(Ok(found)) => Ok(NumberFormat {
+                locale: locale.to_string(),
+                decimal: found.decimal(),
+                thousands: found.separator(),
+                grouping: found.grouping(),
+            }),
+            _ => Err(format!("unknown locale '{}', expected a CLDR locale such as en, de-CH or fr", locale)),
+        }
+    }
+
+    /// Rewrites a number as `Display` writes it, `-1234.5`, with the separators of the
+    /// locale.
+    pub fn format(&self, number: &str) -> String {
+        let (sign, digits) = match number.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", number),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+
+        // Group sizes from the right.
+        let mut groups = Vec::new();
+        let mut rest = integer;
+        let mut size = 3;
+        while self.grouping != Grouping::Posix && rest.len() > size {
+            let (head, tail) = rest.split_at(rest.len() - size);
+            groups.push(tail);
+            rest = head;
+            if self.grouping == Grouping::Indian {
+                size = 2;
+            }
+        }
+        groups.push(rest);
+        groups.reverse();
+
+        let mut formatted = String::with_capacity(number.len() + groups.len() * self.thousands.len());
+        formatted.push_str(sign);
+        formatted.push_str(&groups.join(self.thousands));
+        if let Some(fraction) = fraction {
+            formatted.push_str(self.decimal);
+            formatted.push_str(fraction);
+        }
+        formatted
+    }
+}
@@ -1,66 +1,962 @@
-mod from_parquet;
-
 use nu_source::{Tag};
 
 use nu_errors::ShellError;
 use nu_plugin::{serve_plugin, Plugin};
+use nu_plugin_from_parquet::from_parquet::{from_parquet_bytes, from_parquet_files, from_parquet_mmap, FromParquetOpts, BinaryRepresentation, BoolRepresentation, DecimalRepresentation, FloatPrecision, Int96Representation, NullRepresentation, TimeRepresentation};
+use nu_plugin_from_parquet::cast::Cast;
+use nu_plugin_from_parquet::expr::Select;
+use nu_plugin_from_parquet::filter::Predicate;
+use nu_plugin_from_parquet::group::Aggregation;
+use nu_plugin_from_parquet::input::{glob_files, read_file, BinaryInput};
+use nu_plugin_from_parquet::locale::NumberFormat;
+use std::path::PathBuf;
 use nu_protocol::{
-    CallInfo, Primitive, ReturnValue, Signature, UntaggedValue, Value,
+    CallInfo, ReturnSuccess, ReturnValue, Signature, SyntaxShape, UntaggedValue, Value,
 };
 
 struct FromParquet {
-    bytes: Vec<u8>,
-    name_tag: Tag
+    input: BinaryInput,
+    file: Option<(PathBuf, Tag)>,
+    glob: Option<(String, Tag)>,
+    with_filename: bool,
+    ignore_schema_errors: bool,
+    mmap: bool,
+    batch_size: Option<usize>,
+    name_tag: Tag,
+    opts: FromParquetOpts
 }
 
 impl FromParquet {
     fn new() -> Self {
         Self {
-            bytes: Vec::new(),
-            name_tag: Tag::unknown()
+            input: BinaryInput::new(),
+            file: None,
+            glob: None,
+            with_filename: false,
+            ignore_schema_errors: false,
+            mmap: false,
+            batch_size: None,
+            name_tag: Tag::unknown(),
+            opts: FromParquetOpts::default()
+        }
+    }
+
+    /// Splits the table into batches of `--batch-size` rows, each returned as its own
+    /// value. nu 0.26 plugins answer with every value in one response, so this shapes
+    /// the output but does not lower the peak memory of reading a large file.
+    fn output(&self, value: Value) -> Vec<ReturnValue> {
+        match (self.batch_size, value) {
+            (Some(size), Value { value: UntaggedValue::Table(rows), tag }) => {
+                let mut rows = rows.into_iter().peekable();
+                let mut batches = Vec::new();
+                while rows.peek().is_some() {
+                    let batch: Vec<Value> = rows.by_ref().take(size).collect();
+                    batches.push(ReturnSuccess::value(UntaggedValue::Table(batch).into_value(&tag)));
+                }
+                batches
+            }
+            (_, value) => vec![ReturnSuccess::value(value)],
         }
     }
 }
 
+/// Reads a flag given either as a single string or as a table of strings.
+fn string_list(value: &Value) -> Result<Vec<String>, ShellError> {
+    match &value.value {
+        UntaggedValue::Table(values) => values.iter().map(|v| v.as_string()).collect(),
+        _ => Ok(vec![value.as_string()?]),
+    }
+}
+
+/// Reads a flag given either as a single integer or as a table of integers.
+fn index_list(value: &Value) -> Result<Vec<usize>, ShellError> {
+    match &value.value {
+        UntaggedValue::Table(values) => values.iter().map(|v| Ok(v.as_u64()? as usize)).collect(),
+        _ => Ok(vec![value.as_u64()? as usize]),
+    }
+}
+
 impl Plugin for FromParquet {
     fn config(&mut self) -> Result<Signature, ShellError> {
         Ok(Signature::build("from parquet")
             .desc("Convert from .parquet binary into table")
+            .named(
+                "file",
+                SyntaxShape::FilePath,
+                "read the given Parquet file instead of binary from the pipeline",
+                None,
+            )
+            .named(
+                "glob",
+                SyntaxShape::String,
+                "read and concatenate every Parquet file matching the pattern, eg 'year=*/month=*/*.parquet'",
+                None,
+            )
+            .switch(
+                "with-filename",
+                "add a _filename column with the path of the --glob file each row comes from",
+                None,
+            )
+            .switch(
+                "ignore-schema-errors",
+                "read --glob files even when their schemas differ",
+                None,
+            )
+            .switch(
+                "mmap",
+                "memory map the --file instead of loading it whole, for very large files",
+                None,
+            )
+            .named(
+                "columns",
+                SyntaxShape::Table,
+                "only read the given columns, eg [id name address.city], nested ones by their dotted path",
+                Some('c'),
+            )
+            .named(
+                "drop-columns",
+                SyntaxShape::Table,
+                "leave the given columns out of the output; they are still decoded, prefer --columns for speed",
+                None,
+            )
+            .named(
+                "row-groups",
+                SyntaxShape::Table,
+                "only read the given zero-based row groups, eg [0 2]",
+                Some('g'),
+            )
+            .named(
+                "skip",
+                SyntaxShape::Int,
+                "skip the first n rows",
+                Some('s'),
+            )
+            .named(
+                "limit",
+                SyntaxShape::Int,
+                "read at most n rows, combine with --row-groups for page-like access",
+                Some('l'),
+            )
+            .switch(
+                "map-as-record",
+                "convert maps with string keys into records instead of lists of [key, value] pairs",
+                Some('m'),
+            )
+            .switch(
+                "flatten",
+                "lift the fields of nested records into the row, eg address.city",
+                Some('f'),
+            )
+            .named(
+                "flatten-depth",
+                SyntaxShape::Int,
+                "how many levels of nested records --flatten lifts (default 1)",
+                None,
+            )
+            .switch(
+                "with-row-index",
+                "add a _row_index column with the zero-based position of each row in the file",
+                Some('i'),
+            )
+            .named(
+                "row-index-name",
+                SyntaxShape::String,
+                "name of the --with-row-index column (default _row_index)",
+                None,
+            )
+            .switch(
+                "with-row-group",
+                "add a _row_group column with the zero-based row group each row comes from",
+                None,
+            )
+            .named(
+                "row-group-name",
+                SyntaxShape::String,
+                "name of the --with-row-group column (default _row_group)",
+                None,
+            )
+            .switch(
+                "bytes-as-binary",
+                "read INT8 and UINT8 columns as single-byte binary instead of integers",
+                Some('b'),
+            )
+            .named(
+                "null-as",
+                SyntaxShape::String,
+                "how nulls appear: nothing (default), string for an empty string, or error to fail on the first null",
+                None,
+            )
+            .switch(
+                "parse-json",
+                "decode columns annotated as JSON into tables and records, invalid JSON stays a string",
+                Some('j'),
+            )
+            .switch(
+                "extended-enum",
+                "return ENUM values as {value, ordinal} records, ordinal is the position in the column's dictionary and needs dictionary encoding",
+                Some('e'),
+            )
+            .switch(
+                "time-as-string",
+                "return TIME columns as strings like 12:34:56.789 (default)",
+                None,
+            )
+            .switch(
+                "time-as-duration",
+                "return TIME columns as the duration since midnight",
+                None,
+            )
+            .switch(
+                "int96-as-datetime",
+                "return INT96 timestamps as dates, keeping their nanoseconds (default)",
+                None,
+            )
+            .switch(
+                "int96-as-string",
+                "return INT96 timestamps as the hex string of their 12 raw bytes",
+                None,
+            )
+            .switch(
+                "extend-timestamp",
+                "return dates and timestamps as records of year, month, day, hour, minute, second, nanosecond, timezone and unix_epoch_ms",
+                Some('t'),
+            )
+            .switch(
+                "binary-as-hex",
+                "return binary values, INT8 and UINT8 ones too, as lowercase hex strings",
+                Some('x'),
+            )
+            .switch(
+                "binary-as-base64",
+                "return binary values, INT8 and UINT8 ones too, as base64 strings",
+                None,
+            )
+            .named(
+                "float-precision",
+                SyntaxShape::String,
+                "how FLOAT values appear: double (default) for decimals of the widened value, or exact for strings of their shortest round-trip digits",
+                None,
+            )
+            .switch(
+                "bool-as-int",
+                "return booleans as the integers 0 and 1",
+                None,
+            )
+            .switch(
+                "bool-as-string",
+                "return booleans as the strings true and false",
+                None,
+            )
+            .switch(
+                "overflow-as-string",
+                "return UINT64 values that do not fit a signed 64-bit integer as strings",
+                None,
+            )
+            .switch(
+                "strict",
+                "fail instead of approximating floats",
+                None,
+            )
+            .switch(
+                "parallel",
+                "decode row groups on several threads",
+                Some('p'),
+            )
+            .named(
+                "sample",
+                SyntaxShape::Int,
+                "return n random rows, this scans every sampled row group so combine with --row-groups on large files",
+                None,
+            )
+            .named(
+                "seed",
+                SyntaxShape::Int,
+                "seed for --sample to pick the same rows on every run",
+                None,
+            )
+            .switch(
+                "include-row-group-metadata",
+                "add _rg_index and _rg_num_rows columns with the row group of each row and its row count",
+                None,
+            )
+            .switch(
+                "kv-meta",
+                "return the key-value metadata of the file instead of its rows",
+                None,
+            )
+            .named(
+                "kv-meta-key",
+                SyntaxShape::String,
+                "return the value of a single key-value metadata key instead of the rows",
+                None,
+            )
+            .switch(
+                "empty-as-null",
+                "return empty strings and binaries as nothing",
+                None,
+            )
+            .switch(
+                "null-as-empty",
+                "return nulls of string and binary columns as empty strings and binaries",
+                None,
+            )
+            .named(
+                "max-string-length",
+                SyntaxShape::Int,
+                "cut strings longer than this many bytes, at a character boundary, and append the --truncation-marker",
+                None,
+            )
+            .named(
+                "truncation-marker",
+                SyntaxShape::String,
+                "appended to strings cut by --max-string-length (default …)",
+                None,
+            )
+            .named(
+                "max-binary-length",
+                SyntaxShape::Int,
+                "cut binary values to this many bytes",
+                None,
+            )
+            .switch(
+                "typed-lists",
+                "wrap list elements in records of their type and value, with --extend-list their index too",
+                None,
+            )
+            .named(
+                "max-list-elements",
+                SyntaxShape::Int,
+                "keep this many elements of each list, followed by a …truncated… string when more were left out",
+                None,
+            )
+            .switch(
+                "list-stats",
+                "return lists as records of their length, element_type, first and last elements instead of their contents",
+                None,
+            )
+            .switch(
+                "validate-utf8",
+                "check that string columns hold valid UTF-8, failing with the offset of the first invalid byte",
+                None,
+            )
+            .switch(
+                "output-schema",
+                "put a record of the type of every column of the first row, with _schema set to true, before the rows",
+                None,
+            )
+            .switch(
+                "hf-metadata",
+                "return the Hugging Face dataset info of the file, its name, description, features, splits and download size, instead of its rows",
+                None,
+            )
+            .switch(
+                "parse-kv-json",
+                "decode key-value metadata values holding JSON, like the pandas schema",
+                None,
+            )
+            .switch(
+                "no-convert-dates",
+                "return DATE and TIMESTAMP values as their stored days or milliseconds/microseconds since the epoch",
+                None,
+            )
+            .switch(
+                "no-decode-uuid",
+                "return UUID columns as their 16 bytes instead of hyphenated strings",
+                None,
+            )
+            .named(
+                "no-convert-dates-column",
+                SyntaxShape::Table,
+                "like --no-convert-dates, for the given columns only",
+                None,
+            )
+            .named(
+                "decryption-key",
+                SyntaxShape::String,
+                "hex AES key of an encrypted file, parquet 3.0 cannot decrypt yet so encrypted files are reported as such",
+                None,
+            )
+            .named(
+                "decryption-key-id",
+                SyntaxShape::String,
+                "id of the --decryption-key in the key metadata of the file",
+                None,
+            )
+            .named(
+                "timezone",
+                SyntaxShape::String,
+                "timezone of INT96 timestamps and of TIMESTAMP columns not adjusted to UTC, eg America/New_York",
+                None,
+            )
+            .switch(
+                "interval-as-duration",
+                "return INTERVAL values as durations, approximating a month as 30 days",
+                None,
+            )
+            .switch(
+                "extended-decimal",
+                "return decimals as records of their value, text, precision and scale",
+                None,
+            )
+            .switch(
+                "decimal-as-raw",
+                "return decimals as records of their unscaled big-endian bytes, scale and precision",
+                None,
+            )
+            .switch(
+                "trim-decimal",
+                "drop trailing zeros from the text of --extended-decimal records",
+                None,
+            )
+            .named(
+                "decimal-places",
+                SyntaxShape::Int,
+                "round the text of --extended-decimal records to this many decimal places",
+                None,
+            )
+            .named(
+                "format-numbers",
+                SyntaxShape::String,
+                "write the text of --extended-decimal records with the separators of a locale, eg de-DE for 1.234,5",
+                None,
+            )
+            .switch(
+                "extend-list",
+                "wrap list elements in records of their index and value",
+                None,
+            )
+            .switch(
+                "show-delta-encoding",
+                "return the number of pages of each encoding and the PLAIN and delta encoded bytes of every column chunk instead of the rows",
+                None,
+            )
+            .switch(
+                "compression-info",
+                "add _compression_<column> columns with the codec of the column chunk each row comes from",
+                None,
+            )
+            .switch(
+                "page-stats",
+                "add _page_min_<column> and _page_max_<column> columns with the column index statistics of the page each row comes from",
+                None,
+            )
+            .switch(
+                "encoding-info",
+                "add _encoding_<column> columns with the encodings of the column chunk each row comes from",
+                None,
+            )
+            .named(
+                "cast",
+                SyntaxShape::Table,
+                "cast columns once read, eg [day:date id:string], to string, int, float, bool or date",
+                None,
+            )
+            .named(
+                "select",
+                SyntaxShape::Table,
+                "append computed columns, eg \"full_name = first_name + ' ' + last_name\", with + - * / %, comparisons, and, or and not",
+                None,
+            )
+            .named(
+                "rename",
+                SyntaxShape::Table,
+                "rename columns in the output, eg [userId:user_id]",
+                None,
+            )
+            .switch(
+                "sort-columns",
+                "sort the columns of every row by name",
+                None,
+            )
+            .named(
+                "column-order",
+                SyntaxShape::Table,
+                "put the given columns first, in order, followed by the others sorted by name, eg [id name]",
+                None,
+            )
+            .named(
+                "prefix-columns",
+                SyntaxShape::String,
+                "prepend a prefix to every column name, eg \"orders.\"",
+                None,
+            )
+            .named(
+                "filter",
+                SyntaxShape::String,
+                "only return rows matching \"<column> <op> <value>\", op is eq, ne, lt, gt, le, ge, is_null or not_null",
+                None,
+            )
+            .switch(
+                "verbose",
+                "report bytes and row groups read, row groups skipped by --filter statistics, rows decoded and returned, a memory estimate and the time taken on stderr",
+                None,
+            )
+            .switch(
+                "explain",
+                "describe the columns and row groups the read would go through and estimate its rows and memory, reading only the footer",
+                None,
+            )
+            .switch(
+                "emit-errors-as-rows",
+                "turn rows that fail to convert into records of _error, _error_message, _row_index and _column with null columns instead of failing",
+                None,
+            )
+            .switch(
+                "only-nulls",
+                "only return rows with a null in at least one column",
+                None,
+            )
+            .named(
+                "only-nulls-in",
+                SyntaxShape::Table,
+                "only return rows with a null in at least one of the given columns, eg [email phone]",
+                None,
+            )
+            .named(
+                "dedup",
+                SyntaxShape::Table,
+                "only return the first row of every distinct combination of the given columns, eg [id]",
+                None,
+            )
+            .switch(
+                "dedup-keep-last",
+                "with --dedup, return the last row of every combination instead of the first",
+                None,
+            )
+            .switch(
+                "describe",
+                "return a summary of every column instead of the rows: null_count, min, max, sum, mean and stddev of numbers, distinct_count and length bounds of strings",
+                None,
+            )
+            .named(
+                "group-by",
+                SyntaxShape::String,
+                "return one record per distinct value of the given column instead of the rows, with the --agg results or the row count",
+                None,
+            )
+            .named(
+                "agg",
+                SyntaxShape::Table,
+                "aggregate columns within each --group-by group, eg [amount:sum amount:mean], with sum, min, max, count, mean or first",
+                None,
+            )
+            .switch(
+                "infer-types",
+                "read plain integer columns named *_date, *_at, *_time, *_ts or *_timestamp as dates, INT32 as days and INT64 as milliseconds since the epoch",
+                None,
+            )
+            .named(
+                "batch-size",
+                SyntaxShape::Int,
+                "return the rows as several tables of at most this many rows",
+                None,
+            )
             .filter())
     }
 
     fn begin_filter(&mut self, call_info: CallInfo) -> Result<Vec<ReturnValue>, ShellError> {
+        if let Some(file) = call_info.args.get("file") {
+            self.file = Some((file.as_path()?, file.tag.clone()));
+        }
+        if let Some(pattern) = call_info.args.get("glob") {
+            if self.file.is_some() {
+                return Err(ShellError::labeled_error(
+                    "Conflicting input flags",
+                    "use either --file or --glob",
+                    &call_info.name_tag,
+                ));
+            }
+            self.glob = Some((pattern.as_string()?, pattern.tag.clone()));
+        }
+        self.with_filename = call_info.args.has("with-filename");
+        self.ignore_schema_errors = call_info.args.has("ignore-schema-errors");
+        if (self.with_filename || self.ignore_schema_errors) && self.glob.is_none() {
+            return Err(ShellError::labeled_error(
+                "--with-filename and --ignore-schema-errors need --glob",
+                "pass the files to read with --glob",
+                &call_info.name_tag,
+            ));
+        }
+        self.mmap = call_info.args.has("mmap");
+        if self.mmap && self.file.is_none() {
+            return Err(ShellError::labeled_error(
+                "--mmap needs a file",
+                "pass the Parquet file with --file",
+                &call_info.name_tag,
+            ));
+        }
+        if let Some(columns) = call_info.args.get("columns") {
+            self.opts.columns = Some(string_list(columns)?);
+        }
+        if let Some(columns) = call_info.args.get("drop-columns") {
+            self.opts.drop_columns = string_list(columns)?;
+        }
+        if let Some(row_groups) = call_info.args.get("row-groups") {
+            self.opts.row_groups = Some(index_list(row_groups)?);
+        }
+        if let Some(skip) = call_info.args.get("skip") {
+            self.opts.skip = skip.as_u64()? as usize;
+        }
+        if let Some(limit) = call_info.args.get("limit") {
+            self.opts.limit = Some(limit.as_u64()? as usize);
+        }
+        self.opts.map_as_record = call_info.args.has("map-as-record");
+        if let Some(depth) = call_info.args.get("flatten-depth") {
+            self.opts.flatten = Some(depth.as_u64()? as usize);
+        } else if call_info.args.has("flatten") {
+            self.opts.flatten = Some(1);
+        }
+        if let Some(name) = call_info.args.get("row-index-name") {
+            self.opts.row_index = Some(name.as_string()?);
+        } else if call_info.args.has("with-row-index") {
+            self.opts.row_index = Some("_row_index".to_string());
+        }
+        if let Some(name) = call_info.args.get("row-group-name") {
+            self.opts.row_group = Some(name.as_string()?);
+        } else if call_info.args.has("with-row-group") {
+            self.opts.row_group = Some("_row_group".to_string());
+        }
+        self.opts.bytes_as_binary = call_info.args.has("bytes-as-binary");
+        if let Some(value) = call_info.args.get("null-as") {
+            let name = value.as_string()?;
+            self.opts.null_representation = NullRepresentation::from_name(&name).ok_or_else(|| {
+                ShellError::labeled_error(
+                    "Unknown null representation",
+                    "expected one of nothing, string or error",
+                    &value.tag,
+                )
+            })?;
+        }
+        self.opts.parse_json = call_info.args.has("parse-json");
+        self.opts.extended_enum = call_info.args.has("extended-enum");
+        if call_info.args.has("time-as-string") && call_info.args.has("time-as-duration") {
+            return Err(ShellError::labeled_error(
+                "Conflicting time flags",
+                "use either --time-as-string or --time-as-duration",
+                &call_info.name_tag,
+            ));
+        }
+        if call_info.args.has("time-as-duration") {
+            self.opts.time_representation = TimeRepresentation::Duration;
+        }
+        if call_info.args.has("int96-as-datetime") && call_info.args.has("int96-as-string") {
+            return Err(ShellError::labeled_error(
+                "Conflicting INT96 flags",
+                "use either --int96-as-datetime or --int96-as-string",
+                &call_info.name_tag,
+            ));
+        }
+        if call_info.args.has("int96-as-string") {
+            self.opts.int96_representation = Int96Representation::String;
+        }
+        self.opts.extend_timestamp = call_info.args.has("extend-timestamp");
+        match (call_info.args.has("binary-as-hex"), call_info.args.has("binary-as-base64")) {
+            (true, true) => {
+                return Err(ShellError::labeled_error(
+                    "Conflicting binary flags",
+                    "use either --binary-as-hex or --binary-as-base64",
+                    &call_info.name_tag,
+                ))
+            }
+            (true, false) => self.opts.binary_representation = BinaryRepresentation::Hex,
+            (false, true) => self.opts.binary_representation = BinaryRepresentation::Base64,
+            (false, false) => {}
+        }
+        if let Some(value) = call_info.args.get("float-precision") {
+            let name = value.as_string()?;
+            self.opts.float_precision = FloatPrecision::from_name(&name).ok_or_else(|| {
+                ShellError::labeled_error(
+                    "Unknown float precision",
+                    "expected one of double or exact",
+                    &value.tag,
+                )
+            })?;
+        }
+        match (call_info.args.has("bool-as-int"), call_info.args.has("bool-as-string")) {
+            (true, true) => {
+                return Err(ShellError::labeled_error(
+                    "Conflicting boolean flags",
+                    "use either --bool-as-int or --bool-as-string",
+                    &call_info.name_tag,
+                ))
+            }
+            (true, false) => self.opts.bool_representation = BoolRepresentation::Int,
+            (false, true) => self.opts.bool_representation = BoolRepresentation::String,
+            (false, false) => {}
+        }
+        self.opts.overflow_as_string = call_info.args.has("overflow-as-string");
+        self.opts.strict = call_info.args.has("strict");
+        self.opts.parallel = call_info.args.has("parallel");
+        if let Some(sample) = call_info.args.get("sample") {
+            self.opts.sample = Some(sample.as_u64()? as usize);
+        }
+        if let Some(seed) = call_info.args.get("seed") {
+            self.opts.seed = Some(seed.as_u64()?);
+        }
+        self.opts.row_group_metadata = call_info.args.has("include-row-group-metadata");
+        self.opts.kv_meta = call_info.args.has("kv-meta");
+        self.opts.hf_metadata = call_info.args.has("hf-metadata");
+        self.opts.output_schema = call_info.args.has("output-schema");
+        self.opts.validate_utf8 = call_info.args.has("validate-utf8");
+        if let Some(max) = call_info.args.get("max-string-length") {
+            self.opts.max_string_length = Some(max.as_u64()? as usize);
+        }
+        if let Some(marker) = call_info.args.get("truncation-marker") {
+            if self.opts.max_string_length.is_none() {
+                return Err(ShellError::labeled_error(
+                    "--truncation-marker needs --max-string-length",
+                    "the marker is only appended to strings cut by --max-string-length",
+                    &marker.tag,
+                ));
+            }
+            self.opts.truncation_marker = marker.as_string()?;
+        }
+        if let Some(max) = call_info.args.get("max-binary-length") {
+            self.opts.max_binary_length = Some(max.as_u64()? as usize);
+        }
+        self.opts.empty_as_null = call_info.args.has("empty-as-null");
+        self.opts.null_as_empty = call_info.args.has("null-as-empty");
+        if self.opts.empty_as_null && self.opts.null_as_empty {
+            return Err(ShellError::labeled_error(
+                "Conflicting empty value flags",
+                "use either --empty-as-null or --null-as-empty",
+                &call_info.name_tag,
+            ));
+        }
+        if let Some(key) = call_info.args.get("kv-meta-key") {
+            self.opts.kv_meta_key = Some(key.as_string()?);
+        }
+        self.opts.parse_kv_json = call_info.args.has("parse-kv-json");
+        self.opts.no_convert_dates = call_info.args.has("no-convert-dates");
+        self.opts.no_decode_uuid = call_info.args.has("no-decode-uuid");
+        if let Some(columns) = call_info.args.get("no-convert-dates-column") {
+            self.opts.no_convert_dates_columns = string_list(columns)?;
+        }
+        if let Some(key) = call_info.args.get("decryption-key") {
+            let key_bytes = hex::decode(key.as_string()?.trim())
+                .map_err(|e| ShellError::labeled_error("Invalid decryption key", e.to_string(), &key.tag))?;
+            if ![16, 24, 32].contains(&key_bytes.len()) {
+                return Err(ShellError::labeled_error(
+                    "Invalid decryption key",
+                    format!("AES keys are 16, 24 or 32 bytes, found {}", key_bytes.len()),
+                    &key.tag,
+                ));
+            }
+            self.opts.decryption_key = Some(key_bytes);
+        }
+        if let Some(key_id) = call_info.args.get("decryption-key-id") {
+            if self.opts.decryption_key.is_none() {
+                return Err(ShellError::labeled_error(
+                    "--decryption-key-id needs a key",
+                    "pass the key with --decryption-key",
+                    &key_id.tag,
+                ));
+            }
+            self.opts.decryption_key_id = Some(key_id.as_string()?);
+        }
+        if let Some(timezone) = call_info.args.get("timezone") {
+            let name = timezone.as_string()?;
+            self.opts.timezone = Some(name.parse().map_err(|e: String| {
+                ShellError::labeled_error("Unknown timezone", e, &timezone.tag)
+            })?);
+        }
+        self.opts.interval_as_duration = call_info.args.has("interval-as-duration");
+        match (call_info.args.has("extended-decimal"), call_info.args.has("decimal-as-raw")) {
+            (true, true) => {
+                return Err(ShellError::labeled_error(
+                    "Conflicting decimal flags",
+                    "use either --extended-decimal or --decimal-as-raw",
+                    &call_info.name_tag,
+                ))
+            }
+            (true, false) => self.opts.decimal_representation = DecimalRepresentation::Extended,
+            (false, true) => self.opts.decimal_representation = DecimalRepresentation::Raw,
+            (false, false) => {}
+        }
+        self.opts.trim_decimal = call_info.args.has("trim-decimal");
+        if let Some(places) = call_info.args.get("decimal-places") {
+            self.opts.decimal_places = Some(places.as_u64()?);
+        }
+        if let Some(locale) = call_info.args.get("format-numbers") {
+            let format = NumberFormat::for_locale(&locale.as_string()?)
+                .map_err(|e| ShellError::labeled_error("Invalid number format", e, &locale.tag))?;
+            self.opts.number_format = Some(format);
+        }
+        if self.opts.decimal_representation != DecimalRepresentation::Extended
+            && (self.opts.trim_decimal || self.opts.decimal_places.is_some() || self.opts.number_format.is_some())
+        {
+            return Err(ShellError::labeled_error(
+                "Decimal rendering flags need --extended-decimal",
+                "--trim-decimal, --decimal-places and --format-numbers format the text of --extended-decimal records",
+                &call_info.name_tag,
+            ));
+        }
+        self.opts.extend_list = call_info.args.has("extend-list");
+        self.opts.typed_lists = call_info.args.has("typed-lists");
+        if let Some(max) = call_info.args.get("max-list-elements") {
+            self.opts.max_list_elements = Some(max.as_u64()? as usize);
+        }
+        self.opts.list_stats = call_info.args.has("list-stats");
+        if self.opts.list_stats && (self.opts.max_list_elements.is_some() || self.opts.extend_list || self.opts.typed_lists) {
+            return Err(ShellError::labeled_error(
+                "Conflicting list flags",
+                "--list-stats replaces the elements, use it without --max-list-elements, --extend-list and --typed-lists",
+                &call_info.name_tag,
+            ));
+        }
+        self.opts.encoding_info = call_info.args.has("encoding-info");
+        self.opts.compression_info = call_info.args.has("compression-info");
+        self.opts.page_stats = call_info.args.has("page-stats");
+        self.opts.show_delta_encoding = call_info.args.has("show-delta-encoding");
+        if let Some(casts) = call_info.args.get("cast") {
+            self.opts.casts = string_list(casts)?.iter()
+                .map(|text| Cast::parse(text).map_err(|e| ShellError::labeled_error("Invalid cast", e, &casts.tag)))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(selects) = call_info.args.get("select") {
+            self.opts.selects = string_list(selects)?.iter()
+                .map(|text| {
+                    Select::parse(text).map_err(|e| ShellError::labeled_error("Invalid select expression", e, &selects.tag))
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(renames) = call_info.args.get("rename") {
+            self.opts.renames = string_list(renames)?.iter()
+                .map(|text| match text.split_once(':') {
+                    Some((old, new)) if !old.is_empty() && !new.is_empty() => Ok((old.to_string(), new.to_string())),
+                    _ => Err(ShellError::labeled_error(
+                        "Invalid rename",
+                        format!("expected <old>:<new>, found '{}'", text),
+                        &renames.tag,
+                    )),
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        self.opts.sort_columns = call_info.args.has("sort-columns");
+        if let Some(columns) = call_info.args.get("column-order") {
+            self.opts.column_order = string_list(columns)?;
+        }
+        if let Some(prefix) = call_info.args.get("prefix-columns") {
+            self.opts.prefix_columns = Some(prefix.as_string()?);
+        }
+        if let Some(filter) = call_info.args.get("filter") {
+            if self.opts.sample.is_some() {
+                return Err(ShellError::labeled_error(
+                    "Conflicting filter flags",
+                    "use either --filter or --sample",
+                    &call_info.name_tag,
+                ));
+            }
+            let predicate = Predicate::parse(&filter.as_string()?)
+                .map_err(|e| ShellError::labeled_error("Invalid filter", e, &filter.tag))?;
+            self.opts.filter = Some(predicate);
+        }
+        self.opts.verbose = call_info.args.has("verbose");
+        self.opts.explain = call_info.args.has("explain");
+        self.opts.errors_as_rows = call_info.args.has("emit-errors-as-rows");
+        self.opts.only_nulls = call_info.args.has("only-nulls");
+        if let Some(columns) = call_info.args.get("only-nulls-in") {
+            self.opts.only_nulls_in = string_list(columns)?;
+        }
+        if let Some(columns) = call_info.args.get("dedup") {
+            self.opts.dedup = string_list(columns)?;
+        }
+        self.opts.dedup_keep_last = call_info.args.has("dedup-keep-last");
+        self.opts.infer_types = call_info.args.has("infer-types");
+        self.opts.describe = call_info.args.has("describe");
+        if let Some(column) = call_info.args.get("group-by") {
+            self.opts.group_by = Some(column.as_string()?);
+        }
+        if let Some(aggregations) = call_info.args.get("agg") {
+            if self.opts.group_by.is_none() {
+                return Err(ShellError::labeled_error(
+                    "--agg needs --group-by",
+                    "pass the column to group by with --group-by",
+                    &aggregations.tag,
+                ));
+            }
+            self.opts.aggregations = string_list(aggregations)?.iter()
+                .map(|text| Aggregation::parse(text).map_err(|e| ShellError::labeled_error("Invalid aggregation", e, &aggregations.tag)))
+                .collect::<Result<_, _>>()?;
+        }
+        if self.opts.group_by.is_some() && self.opts.describe {
+            return Err(ShellError::labeled_error(
+                "Conflicting summary flags",
+                "use either --group-by or --describe",
+                &call_info.name_tag,
+            ));
+        }
+        if self.opts.dedup_keep_last && self.opts.dedup.is_empty() {
+            return Err(ShellError::labeled_error(
+                "--dedup-keep-last needs --dedup",
+                "pass the key columns with --dedup",
+                &call_info.name_tag,
+            ));
+        }
+        if self.opts.sample.is_some() && (self.opts.only_nulls || !self.opts.only_nulls_in.is_empty()) {
+            return Err(ShellError::labeled_error(
+                "Conflicting filter flags",
+                "use either --only-nulls or --sample",
+                &call_info.name_tag,
+            ));
+        }
+        if let Some(batch_size) = call_info.args.get("batch-size") {
+            let size = batch_size.as_u64()? as usize;
+            if size == 0 {
+                return Err(ShellError::labeled_error(
+                    "Batch size must be at least 1",
+                    "batch size of 0",
+                    &batch_size.tag,
+                ));
+            }
+            self.batch_size = Some(size);
+        }
         self.name_tag = call_info.name_tag;
         Ok(vec![])
     }
 
     fn filter(&mut self, input: Value) -> Result<Vec<ReturnValue>, ShellError> {
-        match input {
-            Value {
-                value: UntaggedValue::Primitive(Primitive::Binary(b)),
-                ..
-            } => {
-                self.bytes.extend_from_slice(&b);
-            }
-            Value { tag, .. } => {
-                return Err(ShellError::labeled_error_with_secondary(
-                    "Expected binary from pipeline",
-                    "requires binary input",
-                    self.name_tag.clone(),
-                    "value originates from here",
-                    tag,
-                ));
-            }
-        }
+        self.input.push(input, &self.name_tag)?;
         Ok(vec![])
     }
 
     fn end_filter(&mut self) -> Result<Vec<ReturnValue>, ShellError> {
-        let result = vec![
-            crate::from_parquet::from_parquet_bytes(self.bytes.clone())
-        ];
-        self.bytes.clear();
-        Ok(result)
+        if let Some((pattern, tag)) = self.glob.take() {
+            if !self.input.is_empty() {
+                return Err(ShellError::labeled_error(
+                    "Both --glob and pipeline input given",
+                    "read either the files or the piped binary",
+                    &self.name_tag,
+                ));
+            }
+            let files = glob_files(&pattern, &tag)?;
+            let value = from_parquet_files(&files, tag, &self.opts, self.with_filename, self.ignore_schema_errors)?;
+            return Ok(self.output(value));
+        }
+        let (bytes, tag) = match self.file.take() {
+            Some(_) if !self.input.is_empty() => {
+                return Err(ShellError::labeled_error(
+                    "Both --file and pipeline input given",
+                    "read either the file or the piped binary",
+                    &self.name_tag,
+                ))
+            }
+            Some((path, tag)) if self.mmap => {
+                let value = from_parquet_mmap(&path, tag, &self.opts)?;
+                return Ok(self.output(value));
+            }
+            Some((path, tag)) => (read_file(&path, &tag)?, tag),
+            None if self.input.is_empty() => {
+                return Err(ShellError::labeled_error(
+                    "No Parquet data given",
+                    "pipe in binary with `open --raw file.parquet` or pass --file",
+                    &self.name_tag,
+                ))
+            }
+            None => self.input.take(&self.name_tag),
+        };
+        let value = from_parquet_bytes(bytes, tag, &self.opts)?;
+        Ok(self.output(value))
     }
 }
 
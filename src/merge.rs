@@ -0,0 +1,233 @@
+use parquet::column::reader::{ColumnReader, ColumnReaderImpl};
+use parquet::column::writer::{ColumnWriter, ColumnWriterImpl};
+use parquet::data_type::DataType;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::{FileReader, RowGroupReader};
+use parquet::file::serialized_reader::{SerializedFileReader, SliceableCursor};
+use parquet::file::writer::{FileWriter, InMemoryWriteableCursor, RowGroupWriter, SerializedFileWriter};
+use parquet::schema::types::ColumnDescriptor;
+use parquet_format::FileMetaData;
+use thrift::protocol::{TCompactInputProtocol, TCompactOutputProtocol, TOutputProtocol};
+use nu_errors::ShellError;
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+use std::io::Cursor;
+use std::sync::Arc;
+use crate::from_parquet::schema_differences;
+use crate::{open_reader, parquet_error};
+
+const MAGIC: &[u8] = b"PAR1";
+/// Values copied per batch when re-encoding a column chunk.
+const BATCH_SIZE: usize = 4096;
+
+/// How `parquet merge` carries the row groups of its inputs into the output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MergeMode {
+    /// Decode every column chunk and encode it again, compressed with the codec of
+    /// the first input file.
+    ReEncode,
+    /// Copy the bytes of every column chunk as they are, only rewriting the footer.
+    RawCopy,
+}
+
+fn thrift_error(msg: &str, error: thrift::Error, tag: &Tag) -> ShellError {
+    ShellError::labeled_error(msg, error.to_string(), tag)
+}
+
+/// Checks that every file has the top-level columns of the first one, with the same types.
+fn check_schemas(readers: &[SerializedFileReader<SliceableCursor>], tag: &Tag) -> Result<(), ShellError> {
+    let first = readers[0].metadata().file_metadata().schema();
+    for (index, reader) in readers.iter().enumerate().skip(1) {
+        let differences = schema_differences(first, reader.metadata().file_metadata().schema());
+        if !differences.is_empty() {
+            return Err(ShellError::labeled_error(
+                format!("Incompatible Parquet schemas in inputs 0 and {}", index),
+                format!("columns {} differ", differences.join(", ")),
+                tag,
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn copy_column<T: DataType>(
+    reader: &mut ColumnReaderImpl<T>,
+    writer: &mut ColumnWriterImpl<T>,
+    descr: &ColumnDescriptor,
+) -> parquet::errors::Result<()> {
+    let mut values = vec![T::T::default(); BATCH_SIZE];
+    let mut def_levels = vec![0; BATCH_SIZE];
+    let mut rep_levels = vec![0; BATCH_SIZE];
+    let (has_def, has_rep) = (descr.max_def_level() > 0, descr.max_rep_level() > 0);
+    loop {
+        let (values_read, levels_read) = reader.read_batch(
+            BATCH_SIZE,
+            if has_def { Some(&mut def_levels) } else { None },
+            if has_rep { Some(&mut rep_levels) } else { None },
+            &mut values,
+        )?;
+        if values_read == 0 && levels_read == 0 {
+            return Ok(());
+        }
+        writer.write_batch(
+            &values[..values_read],
+            if has_def { Some(&def_levels[..levels_read]) } else { None },
+            if has_rep { Some(&rep_levels[..levels_read]) } else { None },
+        )?;
+    }
+}
+
+fn copy_row_group(
+    row_group: &dyn RowGroupReader,
+    writer: &mut dyn RowGroupWriter,
+) -> parquet::errors::Result<()> {
+    for i in 0..row_group.num_columns() {
+        let descr = row_group.metadata().column(i).column_descr_ptr();
+        let mut column_writer = match writer.next_column()? {
+            Some(column_writer) => column_writer,
+            None => unreachable!("Internal error: output schema has fewer columns than its inputs"),
+        };
+        match (row_group.get_column_reader(i)?, &mut column_writer) {
+            (ColumnReader::BoolColumnReader(mut r), ColumnWriter::BoolColumnWriter(w)) => copy_column(&mut r, w, &descr)?,
+            (ColumnReader::Int32ColumnReader(mut r), ColumnWriter::Int32ColumnWriter(w)) => copy_column(&mut r, w, &descr)?,
+            (ColumnReader::Int64ColumnReader(mut r), ColumnWriter::Int64ColumnWriter(w)) => copy_column(&mut r, w, &descr)?,
+            (ColumnReader::Int96ColumnReader(mut r), ColumnWriter::Int96ColumnWriter(w)) => copy_column(&mut r, w, &descr)?,
+            (ColumnReader::FloatColumnReader(mut r), ColumnWriter::FloatColumnWriter(w)) => copy_column(&mut r, w, &descr)?,
+            (ColumnReader::DoubleColumnReader(mut r), ColumnWriter::DoubleColumnWriter(w)) => copy_column(&mut r, w, &descr)?,
+            (ColumnReader::ByteArrayColumnReader(mut r), ColumnWriter::ByteArrayColumnWriter(w)) => copy_column(&mut r, w, &descr)?,
+            (ColumnReader::FixedLenByteArrayColumnReader(mut r), ColumnWriter::FixedLenByteArrayColumnWriter(w)) => {
+                copy_column(&mut r, w, &descr)?
+            }
+            _ => unreachable!("Internal error: column reader and writer types differ"),
+        }
+        writer.close_column(column_writer)?;
+    }
+    Ok(())
+}
+
+fn re_encode(readers: &[SerializedFileReader<SliceableCursor>], tag: &Tag) -> Result<Vec<u8>, ShellError> {
+    let first = readers[0].metadata();
+    let mut props = WriterProperties::builder()
+        .set_key_value_metadata(first.file_metadata().key_value_metadata().clone());
+    if let Some(row_group) = first.row_groups().first() {
+        for column in row_group.columns() {
+            props = props.set_column_compression(column.column_path().clone(), column.compression());
+        }
+    }
+    let cursor = InMemoryWriteableCursor::default();
+    let schema = first.file_metadata().schema_descr().root_schema_ptr();
+    let mut writer = SerializedFileWriter::new(cursor.clone(), schema, Arc::new(props.build()))
+        .map_err(|e| parquet_error("Failed to create Parquet writer", e, tag))?;
+    for reader in readers {
+        for i in 0..reader.num_row_groups() {
+            let row_group = reader.get_row_group(i)
+                .map_err(|e| parquet_error("Failed to read Parquet row group", e, tag))?;
+            let mut row_group_writer = writer.next_row_group()
+                .map_err(|e| parquet_error("Failed to create Parquet row group", e, tag))?;
+            copy_row_group(row_group.as_ref(), row_group_writer.as_mut())
+                .map_err(|e| parquet_error("Failed to copy Parquet row group", e, tag))?;
+            writer.close_row_group(row_group_writer)
+                .map_err(|e| parquet_error("Failed to write Parquet row group", e, tag))?;
+        }
+    }
+    writer.close()
+        .map_err(|e| parquet_error("Failed to finish Parquet file", e, tag))?;
+    Ok(cursor.data())
+}
+
+fn read_footer(bytes: &[u8], tag: &Tag) -> Result<FileMetaData, ShellError> {
+    // open_reader has already checked the magic and the footer length.
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+    let footer_start = bytes.len() - 8 - u32::from_le_bytes(footer_len) as usize;
+    let mut protocol = TCompactInputProtocol::new(Cursor::new(&bytes[footer_start..bytes.len() - 8]));
+    FileMetaData::read_from_in_protocol(&mut protocol)
+        .map_err(|e| thrift_error("Failed to read Parquet footer", e, tag))
+}
+
+fn raw_copy(files: &[Vec<u8>], tag: &Tag) -> Result<Vec<u8>, ShellError> {
+    let mut output = MAGIC.to_vec();
+    let mut merged: Option<FileMetaData> = None;
+    let mut row_groups = Vec::new();
+    for bytes in files {
+        let mut footer = read_footer(bytes, tag)?;
+        for mut row_group in std::mem::take(&mut footer.row_groups) {
+            for column in &mut row_group.columns {
+                let meta = match (&column.file_path, &mut column.meta_data) {
+                    (None, Some(meta)) => meta,
+                    _ => {
+                        return Err(ShellError::labeled_error(
+                            "Cannot copy Parquet column chunk",
+                            "column chunks stored in other files or without metadata are not supported",
+                            tag,
+                        ))
+                    }
+                };
+                // Some writers put a dictionary offset of 0 on chunks without a dictionary.
+                let start = match meta.dictionary_page_offset {
+                    Some(offset) if offset > 0 && offset < meta.data_page_offset => offset,
+                    _ => meta.data_page_offset,
+                };
+                let end = start.checked_add(meta.total_compressed_size)
+                    .filter(|end| start >= MAGIC.len() as i64 && *end as usize <= bytes.len())
+                    .ok_or_else(|| {
+                        ShellError::labeled_error(
+                            "Cannot copy Parquet column chunk",
+                            format!("chunk spans bytes {} to {}, outside the file", start, start.saturating_add(meta.total_compressed_size)),
+                            tag,
+                        )
+                    })?;
+                let shift = output.len() as i64 - start;
+                output.extend_from_slice(&bytes[start as usize..end as usize]);
+                meta.data_page_offset += shift;
+                meta.dictionary_page_offset = meta.dictionary_page_offset.filter(|offset| *offset > 0).map(|o| o + shift);
+                meta.index_page_offset = meta.index_page_offset.map(|offset| offset + shift);
+                column.file_offset += shift;
+                // Page indexes live outside the column chunks and are not copied.
+                column.offset_index_offset = None;
+                column.offset_index_length = None;
+                column.column_index_offset = None;
+                column.column_index_length = None;
+            }
+            row_groups.push(row_group);
+        }
+        merged.get_or_insert(footer);
+    }
+
+    let mut footer = merged.expect("Internal error: no Parquet files to merge");
+    footer.num_rows = row_groups.iter().map(|rg| rg.num_rows).sum();
+    footer.row_groups = row_groups;
+    let mut footer_bytes = Vec::new();
+    {
+        let mut protocol = TCompactOutputProtocol::new(&mut footer_bytes);
+        footer.write_to_out_protocol(&mut protocol)
+            .and_then(|_| protocol.flush())
+            .map_err(|e| thrift_error("Failed to write Parquet footer", e, tag))?;
+    }
+    output.extend_from_slice(&footer_bytes);
+    output.extend_from_slice(&(footer_bytes.len() as u32).to_le_bytes());
+    output.extend_from_slice(MAGIC);
+    Ok(output)
+}
+
+/// Merges Parquet files sharing a schema into one file holding the row groups of
+/// each of them, in order.
+pub fn parquet_merge(files: Vec<Vec<u8>>, tag: impl Into<Tag>, mode: MergeMode) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    if files.is_empty() {
+        return Err(ShellError::labeled_error(
+            "No Parquet data given",
+            "pipe in binaries or pass --files",
+            &tag,
+        ));
+    }
+    let readers = files.iter()
+        .map(|bytes| open_reader(bytes.clone(), &tag))
+        .collect::<Result<Vec<_>, ShellError>>()?;
+    check_schemas(&readers, &tag)?;
+    let merged = match mode {
+        MergeMode::ReEncode => re_encode(&readers, &tag)?,
+        MergeMode::RawCopy => raw_copy(&files, &tag)?,
+    };
+    Ok(UntaggedValue::binary(merged).into_value(tag))
+}
@@ -0,0 +1,50 @@
+use parquet::file::metadata::FileMetaData;
+use parquet::file::reader::FileReader;
+use nu_errors::ShellError;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use crate::open_reader;
+
+/// Converts the footer key-value pairs into a record, keys without a value map to nothing.
+pub(crate) fn key_value_metadata(metadata: &FileMetaData, tag: &Tag) -> Value {
+    let mut dict = TaggedDictBuilder::new(tag);
+    for kv in metadata.key_value_metadata().iter().flatten() {
+        match &kv.value {
+            Some(value) => dict.insert_untagged(kv.key.clone(), UntaggedValue::string(value)),
+            None => dict.insert_untagged(kv.key.clone(), UntaggedValue::nothing()),
+        }
+    }
+    dict.into_value()
+}
+
+/// Reads the file-level metadata from the footer without decoding any rows.
+pub fn parquet_meta(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+    let metadata = reader.metadata();
+    let file_metadata = metadata.file_metadata();
+
+    let mut dict = TaggedDictBuilder::new(&tag);
+    dict.insert_untagged("row_count", UntaggedValue::int(file_metadata.num_rows()));
+    dict.insert_untagged("row_groups", UntaggedValue::int(metadata.num_row_groups() as u64));
+    match file_metadata.created_by() {
+        Some(created_by) => dict.insert_untagged("created_by", UntaggedValue::string(created_by)),
+        None => dict.insert_untagged("created_by", UntaggedValue::nothing()),
+    }
+    dict.insert_untagged("version", UntaggedValue::int(file_metadata.version()));
+    dict.insert_value("key_value_metadata", key_value_metadata(file_metadata, &tag));
+    Ok(dict.into_value())
+}
+
+/// Returns the row and row group counts from the footer, a cheap alternative to
+/// `from parquet | length`.
+pub fn parquet_count(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+    let metadata = reader.metadata();
+
+    let mut dict = TaggedDictBuilder::new(&tag);
+    dict.insert_untagged("row_count", UntaggedValue::int(metadata.file_metadata().num_rows()));
+    dict.insert_untagged("row_group_count", UntaggedValue::int(metadata.num_row_groups() as u64));
+    Ok(dict.into_value())
+}
@@ -0,0 +1,65 @@
+use memmap2::Mmap;
+use nu_errors::ShellError;
+use nu_source::Tag;
+use parquet::errors::{ParquetError, Result};
+use parquet::file::reader::{ChunkReader, Length};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A memory mapped file that the Parquet reader reads chunks out of, so only the pages
+/// being decoded have to be paged in.
+#[derive(Clone)]
+pub(crate) struct MmapFile {
+    map: Arc<Mmap>,
+}
+
+/// A chunk of a memory mapped file.
+pub(crate) struct MmapChunk {
+    map: Arc<Mmap>,
+    pos: usize,
+    end: usize,
+}
+
+impl Read for MmapChunk {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = buf.len().min(self.end - self.pos);
+        buf[..n].copy_from_slice(&self.map[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Length for MmapFile {
+    fn len(&self) -> u64 {
+        self.map.len() as u64
+    }
+}
+
+impl ChunkReader for MmapFile {
+    type T = MmapChunk;
+
+    fn get_read(&self, start: u64, length: usize) -> Result<MmapChunk> {
+        let start = start as usize;
+        match start.checked_add(length) {
+            Some(end) if end <= self.map.len() => Ok(MmapChunk { map: self.map.clone(), pos: start, end }),
+            _ => Err(ParquetError::EOF(format!(
+                "chunk of {} bytes at {} is past the end of the file",
+                length, start
+            ))),
+        }
+    }
+}
+
+pub(crate) fn map_file(path: &Path, tag: &Tag) -> std::result::Result<MmapFile, ShellError> {
+    let read_error = |e: io::Error| {
+        ShellError::labeled_error(format!("Failed to read {}", path.display()), e.to_string(), tag)
+    };
+    let file = File::open(path).map_err(read_error)?;
+    // The map is only valid while nobody truncates or rewrites the file, the same
+    // caveat every mmap based reader has.
+    let map = unsafe { Mmap::map(&file) }.map_err(read_error)?;
+    Ok(MmapFile { map: Arc::new(map) })
+}
+
@@ -0,0 +1,439 @@
+use parquet::basic::Type as PhysicalType;
+use parquet::column::reader::{ColumnReader, ColumnReaderImpl};
+use parquet::data_type::DataType;
+use parquet::file::metadata::ParquetMetaData;
+use parquet::file::reader::{ChunkReader, FileReader};
+use parquet::file::statistics::from_thrift;
+use parquet_format::{
+    BoundaryOrder, ColumnChunk, ColumnIndex, ColumnMetaData, FileMetaData, OffsetIndex, PageHeader, PageLocation,
+    Statistics as TStatistics,
+};
+use thrift::protocol::{TCompactInputProtocol, TCompactOutputProtocol, TOutputProtocol};
+use nu_errors::ShellError;
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+use std::io::{Cursor, Read};
+use std::sync::Once;
+use crate::{open_reader, parquet_error};
+use crate::stats::min_max;
+
+static NO_COLUMN_INDEX: Once = Once::new();
+/// Longest binary bound kept in a written column index, longer ones are shortened to a
+/// prefix that still bounds the page.
+const COLUMN_INDEX_TRUNCATE_LENGTH: usize = 64;
+/// Levels read per batch when counting the rows of a repeated column chunk.
+const BATCH_SIZE: usize = 1024;
+
+/// The statistics of a data page, which holds the rows from `first_row` up to the
+/// first row of the next page.
+struct Page {
+    first_row: i64,
+    min: UntaggedValue,
+    max: UntaggedValue,
+}
+
+/// The pages of a column chunk, empty when the chunk has no column index.
+struct ColumnPages {
+    name: String,
+    pages: Vec<Page>,
+}
+
+/// The page statistics of every column chunk of a file, read from its column and
+/// offset indexes.
+pub(crate) struct PageStats {
+    row_groups: Vec<Vec<ColumnPages>>,
+}
+
+fn read_chunk<C: ChunkReader>(source: &C, start: i64, length: i32, tag: &Tag) -> Result<Vec<u8>, ShellError> {
+    let mut bytes = Vec::with_capacity(length.max(0) as usize);
+    source.get_read(start.max(0) as u64, length.max(0) as usize)
+        .map_err(|e| parquet_error("Failed to read Parquet page index", e, tag))?
+        .read_to_end(&mut bytes)
+        .map_err(|e| ShellError::labeled_error("Failed to read Parquet page index", e.to_string(), tag))?;
+    Ok(bytes)
+}
+
+fn thrift_error(error: thrift::Error, tag: &Tag) -> ShellError {
+    ShellError::labeled_error("Failed to read Parquet page index", error.to_string(), tag)
+}
+
+/// Reads the footer as thrift, since the parsed metadata of parquet 3.0 leaves the
+/// page index offsets and the logical types out.
+pub(crate) fn read_footer<C: ChunkReader>(source: &C, tag: &Tag) -> Result<FileMetaData, ShellError> {
+    let tail = read_chunk(source, source.len() as i64 - 8, 8, tag)?;
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&tail[..4]);
+    let footer_len = u32::from_le_bytes(footer_len) as i32;
+    let footer = read_chunk(source, source.len() as i64 - 8 - i64::from(footer_len), footer_len, tag)?;
+    FileMetaData::read_from_in_protocol(&mut TCompactInputProtocol::new(footer.as_slice()))
+        .map_err(|e| ShellError::labeled_error("Failed to read Parquet footer", e.to_string(), tag))
+}
+
+fn read_indexes<C: ChunkReader>(
+    source: &C,
+    column: &ColumnChunk,
+    tag: &Tag,
+) -> Result<Option<(ColumnIndex, OffsetIndex)>, ShellError> {
+    let (column_index, offset_index) = match (
+        column.column_index_offset.zip(column.column_index_length),
+        column.offset_index_offset.zip(column.offset_index_length),
+    ) {
+        (Some(column_index), Some(offset_index)) => (column_index, offset_index),
+        _ => return Ok(None),
+    };
+    let bytes = read_chunk(source, column_index.0, column_index.1, tag)?;
+    let column_index = ColumnIndex::read_from_in_protocol(&mut TCompactInputProtocol::new(bytes.as_slice()))
+        .map_err(|e| thrift_error(e, tag))?;
+    let bytes = read_chunk(source, offset_index.0, offset_index.1, tag)?;
+    let offset_index = OffsetIndex::read_from_in_protocol(&mut TCompactInputProtocol::new(bytes.as_slice()))
+        .map_err(|e| thrift_error(e, tag))?;
+    Ok(Some((column_index, offset_index)))
+}
+
+impl PageStats {
+    pub(crate) fn read<C: ChunkReader>(source: &C, metadata: &ParquetMetaData, tag: &Tag) -> Result<PageStats, ShellError> {
+        let footer = read_footer(source, tag)?;
+        let mut row_groups = Vec::with_capacity(footer.row_groups.len());
+        let mut indexed = false;
+        for (row_group, raw) in metadata.row_groups().iter().zip(&footer.row_groups) {
+            let mut columns = Vec::with_capacity(row_group.num_columns());
+            for (column, raw) in row_group.columns().iter().zip(&raw.columns) {
+                let mut pages = Vec::new();
+                if let Some((index, offsets)) = read_indexes(source, raw, tag)? {
+                    indexed = true;
+                    let values = index.null_pages.iter().zip(index.min_values).zip(index.max_values);
+                    for (location, ((null_page, min), max)) in offsets.page_locations.iter().zip(values) {
+                        // Pages holding only nulls have empty bounds.
+                        let (min, max) = if *null_page {
+                            (UntaggedValue::nothing(), UntaggedValue::nothing())
+                        } else {
+                            let stats = TStatistics::new(None, None, None, None, max, min);
+                            match from_thrift(column.column_type(), Some(stats)) {
+                                Some(stats) => min_max(&stats, column.column_descr()),
+                                None => (UntaggedValue::nothing(), UntaggedValue::nothing()),
+                            }
+                        };
+                        pages.push(Page { first_row: location.first_row_index, min, max });
+                    }
+                }
+                columns.push(ColumnPages { name: column.column_path().string(), pages });
+            }
+            row_groups.push(columns);
+        }
+        if !indexed {
+            NO_COLUMN_INDEX.call_once(|| {
+                eprintln!("warning: the file has no column index, the --page-stats columns are empty");
+            });
+        }
+        Ok(PageStats { row_groups })
+    }
+
+    /// The `_page_min_<column>` and `_page_max_<column>` columns of a row, given by its
+    /// position in its row group.
+    pub(crate) fn columns(&self, row_group: usize, row: i64) -> Vec<(String, Value)> {
+        let mut columns = Vec::new();
+        for column in &self.row_groups[row_group] {
+            let page = match column.pages.partition_point(|page| page.first_row <= row) {
+                0 => None,
+                n => column.pages.get(n - 1),
+            };
+            let (min, max) = match page {
+                Some(page) => (page.min.clone(), page.max.clone()),
+                None => (UntaggedValue::nothing(), UntaggedValue::nothing()),
+            };
+            columns.push((format!("_page_min_{}", column.name), min.into_value(Tag::unknown())));
+            columns.push((format!("_page_max_{}", column.name), max.into_value(Tag::unknown())));
+        }
+        columns
+    }
+}
+
+/// What adding page indexes to a file cost.
+pub(crate) struct PageIndexReport {
+    /// Data pages listed in the offset indexes.
+    pub(crate) pages: u64,
+    /// Bytes taken by the column and offset indexes.
+    pub(crate) index_bytes: u64,
+}
+
+/// A page of a column chunk: its header and where it lies in the file.
+struct ChunkPage {
+    header: PageHeader,
+    offset: usize,
+    header_len: usize,
+}
+
+impl ChunkPage {
+    fn end(&self) -> usize {
+        self.offset + self.header_len + self.header.compressed_page_size.max(0) as usize
+    }
+}
+
+/// A data page of a rewritten column chunk, with its statistics from the original header.
+struct DataPage {
+    location: PageLocation,
+    num_values: i64,
+    statistics: Option<TStatistics>,
+}
+
+/// The pages of the column chunk `column`, read from the headers in `bytes`.
+fn chunk_pages(bytes: &[u8], meta: &ColumnMetaData) -> thrift::Result<Vec<ChunkPage>> {
+    // Some writers put a dictionary offset of 0 on chunks without a dictionary.
+    let start = match meta.dictionary_page_offset {
+        Some(offset) if offset > 0 && offset < meta.data_page_offset => offset,
+        _ => meta.data_page_offset,
+    };
+    let end = start.saturating_add(meta.total_compressed_size).min(bytes.len() as i64) as usize;
+    let mut offset = start.max(0) as usize;
+    let mut pages = Vec::new();
+    while offset < end {
+        let mut cursor = Cursor::new(&bytes[offset..end]);
+        let header = PageHeader::read_from_in_protocol(&mut TCompactInputProtocol::new(&mut cursor))?;
+        let page = ChunkPage { header, offset, header_len: cursor.position() as usize };
+        offset = page.end();
+        pages.push(page);
+    }
+    Ok(pages)
+}
+
+fn read_rep_levels<T: DataType>(reader: &mut ColumnReaderImpl<T>, levels: &mut Vec<i16>) -> parquet::errors::Result<()> {
+    let mut values = vec![T::T::default(); BATCH_SIZE];
+    let mut def_levels = vec![0; BATCH_SIZE];
+    let mut rep_levels = vec![0; BATCH_SIZE];
+    loop {
+        let (values_read, levels_read) = reader.read_batch(BATCH_SIZE, Some(&mut def_levels), Some(&mut rep_levels), &mut values)?;
+        if values_read == 0 && levels_read == 0 {
+            return Ok(());
+        }
+        levels.extend_from_slice(&rep_levels[..levels_read]);
+    }
+}
+
+/// The repetition levels of a repeated column chunk, which tell where its rows start.
+fn rep_levels(reader: ColumnReader) -> parquet::errors::Result<Vec<i16>> {
+    let mut levels = Vec::new();
+    match reader {
+        ColumnReader::BoolColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+        ColumnReader::Int32ColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+        ColumnReader::Int64ColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+        ColumnReader::Int96ColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+        ColumnReader::FloatColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+        ColumnReader::DoubleColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+        ColumnReader::ByteArrayColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+        ColumnReader::FixedLenByteArrayColumnReader(mut r) => read_rep_levels(&mut r, &mut levels)?,
+    }
+    Ok(levels)
+}
+
+/// The smallest value above every value starting with the first `len` bytes of `max`:
+/// that prefix with its last byte below 0xff incremented. `None` when there is none.
+fn truncate_max(max: &[u8], len: usize) -> Option<Vec<u8>> {
+    let mut prefix = max[..len].to_vec();
+    while let Some(last) = prefix.pop() {
+        if last < 0xff {
+            prefix.push(last + 1);
+            return Some(prefix);
+        }
+    }
+    None
+}
+
+/// The column index of a chunk, when every one of its data pages has statistics. parquet
+/// 3.0 leaves null counts out of pages without nulls, so they are only written when
+/// every page has one.
+fn column_index(pages: &[DataPage], physical: PhysicalType) -> Option<ColumnIndex> {
+    let mut null_pages = Vec::with_capacity(pages.len());
+    let mut min_values = Vec::with_capacity(pages.len());
+    let mut max_values = Vec::with_capacity(pages.len());
+    let mut null_counts = Some(Vec::with_capacity(pages.len()));
+    for page in pages {
+        let stats = page.statistics.as_ref()?;
+        let null_page = stats.null_count == Some(page.num_values);
+        let (mut min, mut max) = match (&stats.min_value, &stats.max_value) {
+            _ if null_page => (Vec::new(), Vec::new()),
+            (Some(min), Some(max)) => (min.clone(), max.clone()),
+            _ => return None,
+        };
+        if physical == PhysicalType::BYTE_ARRAY {
+            if min.len() > COLUMN_INDEX_TRUNCATE_LENGTH {
+                min.truncate(COLUMN_INDEX_TRUNCATE_LENGTH);
+            }
+            if max.len() > COLUMN_INDEX_TRUNCATE_LENGTH {
+                if let Some(truncated) = truncate_max(&max, COLUMN_INDEX_TRUNCATE_LENGTH) {
+                    max = truncated;
+                }
+            }
+        }
+        null_pages.push(null_page);
+        min_values.push(min);
+        max_values.push(max);
+        null_counts = null_counts.zip(stats.null_count).map(|(mut counts, count)| {
+            counts.push(count);
+            counts
+        });
+    }
+    Some(ColumnIndex::new(null_pages, min_values, max_values, BoundaryOrder::Unordered, null_counts))
+}
+
+fn write_index(out: &mut Vec<u8>, write: impl FnOnce(&mut TCompactOutputProtocol<&mut Vec<u8>>) -> thrift::Result<()>) -> thrift::Result<(i64, i32)> {
+    let offset = out.len();
+    let mut protocol = TCompactOutputProtocol::new(&mut *out);
+    write(&mut protocol)?;
+    protocol.flush()?;
+    Ok((offset as i64, (out.len() - offset) as i32))
+}
+
+/// What `rewrite_pages` changes in a file.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PageRewrite {
+    /// Keep the statistics of the page headers.
+    pub(crate) page_statistics: bool,
+    /// Keep the statistics of the column chunks.
+    pub(crate) chunk_statistics: bool,
+    /// Add a column index and an offset index to every column chunk.
+    pub(crate) page_indexes: bool,
+}
+
+/// Rewrites the pages of the Parquet file `bytes` for the settings parquet 3.0 has no
+/// writer for: it ignores `set_statistics_enabled` and writes no page indexes. Page headers
+/// are written again without their statistics when those are dropped, which moves the
+/// pages after them, so the footer offsets are rewritten as well.
+///
+/// Page indexes take their bounds from the statistics of the original page headers;
+/// chunks with a page lacking them only get an offset index.
+pub(crate) fn rewrite_pages(bytes: Vec<u8>, rewrite: PageRewrite, tag: &Tag) -> Result<(Vec<u8>, Option<PageIndexReport>), ShellError> {
+    let write_error = |e: thrift::Error| ShellError::labeled_error("Failed to write Parquet pages", e.to_string(), tag);
+    let reader = open_reader(bytes.clone(), tag)?;
+    let descr = reader.metadata().file_metadata().schema_descr();
+
+    // open_reader has already checked the magic and the footer length.
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+    let footer_start = bytes.len() - 8 - u32::from_le_bytes(footer_len) as usize;
+    let mut footer = FileMetaData::read_from_in_protocol(&mut TCompactInputProtocol::new(&bytes[footer_start..bytes.len() - 8]))
+        .map_err(|e| thrift_error(e, tag))?;
+
+    let mut output = Vec::with_capacity(bytes.len());
+    // Bytes between chunks, such as the magic and the copies of the chunk metadata
+    // parquet 3.0 writes after each chunk, are kept as they are.
+    let mut copied = 0;
+    let mut chunks = Vec::new();
+    for (i, row_group) in footer.row_groups.iter_mut().enumerate() {
+        for (j, column) in row_group.columns.iter_mut().enumerate() {
+            let meta = match column.meta_data.as_mut() {
+                Some(meta) => meta,
+                None => continue,
+            };
+            let pages = chunk_pages(&bytes, meta).map_err(|e| thrift_error(e, tag))?;
+            let start = match pages.first() {
+                Some(page) if page.offset >= copied => page.offset,
+                _ => continue,
+            };
+            output.extend_from_slice(&bytes[copied..start]);
+
+            let mut data_pages = Vec::new();
+            let mut grown = 0;
+            let (data_page_offset, dictionary_page_offset) = (meta.data_page_offset, meta.dictionary_page_offset);
+            for page in pages {
+                let offset = output.len() as i64;
+                if page.offset as i64 == data_page_offset {
+                    meta.data_page_offset = offset;
+                }
+                if dictionary_page_offset == Some(page.offset as i64) {
+                    meta.dictionary_page_offset = Some(offset);
+                }
+                let mut header = page.header.clone();
+                let statistics = match (&mut header.data_page_header, &mut header.data_page_header_v2) {
+                    (Some(data), _) => Some((i64::from(data.num_values), data.statistics.take())),
+                    (_, Some(data)) => Some((i64::from(data.num_values), data.statistics.take())),
+                    _ => None,
+                };
+                if rewrite.page_statistics {
+                    output.extend_from_slice(&bytes[page.offset..page.offset + page.header_len]);
+                } else {
+                    let mut protocol = TCompactOutputProtocol::new(&mut output);
+                    header.write_to_out_protocol(&mut protocol)
+                        .and_then(|_| protocol.flush())
+                        .map_err(write_error)?;
+                }
+                output.extend_from_slice(&bytes[page.offset + page.header_len..page.end()]);
+                grown += output.len() as i64 - offset - (page.end() - page.offset) as i64;
+                copied = page.end();
+                if let Some((num_values, statistics)) = statistics {
+                    let size = output.len() as i64 - offset;
+                    data_pages.push(DataPage { location: PageLocation::new(offset, size as i32, 0), num_values, statistics });
+                }
+            }
+            meta.total_compressed_size += grown;
+            meta.total_uncompressed_size += grown;
+            row_group.total_byte_size += grown;
+            column.file_offset += output.len() as i64 - copied as i64;
+            if !rewrite.chunk_statistics {
+                meta.statistics = None;
+            }
+            if !rewrite.page_indexes {
+                continue;
+            }
+
+            if descr.column(j).max_rep_level() == 0 {
+                let mut first_row = 0;
+                for page in &mut data_pages {
+                    page.location.first_row_index = first_row;
+                    first_row += page.num_values;
+                }
+            } else {
+                let levels = reader.get_row_group(i)
+                    .and_then(|row_group| row_group.get_column_reader(j))
+                    .and_then(rep_levels)
+                    .map_err(|e| parquet_error("Failed to read Parquet column", e, tag))?;
+                let (mut start, mut first_row) = (0, 0);
+                for page in &mut data_pages {
+                    page.location.first_row_index = first_row;
+                    let end = (start + page.num_values as usize).min(levels.len());
+                    first_row += levels[start..end].iter().filter(|level| **level == 0).count() as i64;
+                    start = end;
+                }
+            }
+            chunks.push(((i, j), column_index(&data_pages, descr.column(j).physical_type()), data_pages));
+        }
+    }
+    output.extend_from_slice(&bytes[copied..footer_start]);
+
+    let mut report = None;
+    if rewrite.page_indexes {
+        // Column indexes come first, then offset indexes, as the format lays them out.
+        let index_start = output.len();
+        let mut column_indexes = Vec::with_capacity(chunks.len());
+        for (_, index, _) in &chunks {
+            column_indexes.push(match index {
+                Some(index) => Some(write_index(&mut output, |p| index.write_to_out_protocol(p)).map_err(write_error)?),
+                None => None,
+            });
+        }
+        let mut written = PageIndexReport { pages: 0, index_bytes: 0 };
+        for (((i, j), _, pages), column_index) in chunks.into_iter().zip(column_indexes) {
+            written.pages += pages.len() as u64;
+            let index = OffsetIndex::new(pages.into_iter().map(|page| page.location).collect());
+            let (offset, length) = write_index(&mut output, |p| index.write_to_out_protocol(p)).map_err(write_error)?;
+            let column = &mut footer.row_groups[i].columns[j];
+            column.column_index_offset = column_index.map(|(offset, _)| offset);
+            column.column_index_length = column_index.map(|(_, length)| length);
+            column.offset_index_offset = Some(offset);
+            column.offset_index_length = Some(length);
+        }
+        written.index_bytes = (output.len() - index_start) as u64;
+        report = Some(written);
+    }
+
+    let mut footer_bytes = Vec::new();
+    {
+        let mut protocol = TCompactOutputProtocol::new(&mut footer_bytes);
+        footer.write_to_out_protocol(&mut protocol)
+            .and_then(|_| protocol.flush())
+            .map_err(write_error)?;
+    }
+    output.extend_from_slice(&footer_bytes);
+    output.extend_from_slice(&(footer_bytes.len() as u32).to_le_bytes());
+    output.extend_from_slice(b"PAR1");
+    Ok((output, report))
+}
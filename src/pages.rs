@@ -0,0 +1,76 @@
+use parquet::basic::{Encoding, PageType};
+use parquet::file::reader::FileReader;
+use parquet_format::PageHeader;
+use thrift::protocol::TCompactInputProtocol;
+use nu_errors::ShellError;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use std::io::Cursor;
+use crate::open_reader;
+
+/// Lists the page headers of the column chunk of the leaf column `column` in row group
+/// `row_group`. The page reader of parquet 3.0 hands out decompressed pages without their
+/// sizes or offsets, so the headers are read from the chunk bytes instead.
+pub fn parquet_pages(bytes: Vec<u8>, tag: impl Into<Tag>, column: &str, row_group: usize) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes.clone(), &tag)?;
+    let metadata = reader.metadata();
+    if row_group >= metadata.num_row_groups() {
+        return Err(ShellError::labeled_error(
+            format!("Row group {} is out of range", row_group),
+            format!("valid row groups are 0 to {}", metadata.num_row_groups() as i64 - 1),
+            &tag,
+        ));
+    }
+    let row_group = metadata.row_group(row_group);
+    let chunk = match row_group.columns().iter().find(|c| c.column_path().string() == column) {
+        Some(chunk) => chunk,
+        None => {
+            let available: Vec<String> = row_group.columns().iter().map(|c| c.column_path().string()).collect();
+            return Err(ShellError::labeled_error(
+                format!("Unknown column '{}'", column),
+                format!("available columns are: {}", available.join(", ")),
+                &tag,
+            ));
+        }
+    };
+
+    // Some writers put a dictionary offset of 0 on chunks without a dictionary.
+    let start = match chunk.dictionary_page_offset() {
+        Some(offset) if offset > 0 && offset < chunk.data_page_offset() => offset,
+        _ => chunk.data_page_offset(),
+    };
+    let end = start.saturating_add(chunk.compressed_size()).min(bytes.len() as i64);
+    let mut offset = start;
+    let mut pages = Vec::new();
+    while offset < end {
+        let mut cursor = Cursor::new(&bytes[offset as usize..end as usize]);
+        let header = PageHeader::read_from_in_protocol(&mut TCompactInputProtocol::new(&mut cursor))
+            .map_err(|e| ShellError::labeled_error(format!("Failed to read Parquet page header at byte {}", offset), e.to_string(), &tag))?;
+        let (num_values, encoding, has_statistics) = if let Some(data) = &header.data_page_header {
+            (data.num_values, Some(data.encoding), data.statistics.is_some())
+        } else if let Some(data) = &header.data_page_header_v2 {
+            (data.num_values, Some(data.encoding), data.statistics.is_some())
+        } else if let Some(dictionary) = &header.dictionary_page_header {
+            (dictionary.num_values, Some(dictionary.encoding), false)
+        } else {
+            (0, None, false)
+        };
+
+        let mut dict = TaggedDictBuilder::new(&tag);
+        dict.insert_untagged("page_type", UntaggedValue::string(PageType::from(header.type_).to_string()));
+        dict.insert_untagged("compressed_size", UntaggedValue::int(header.compressed_page_size));
+        dict.insert_untagged("uncompressed_size", UntaggedValue::int(header.uncompressed_page_size));
+        dict.insert_untagged("num_values", UntaggedValue::int(num_values));
+        match encoding {
+            Some(encoding) => dict.insert_untagged("encoding", UntaggedValue::string(Encoding::from(encoding).to_string())),
+            None => dict.insert_untagged("encoding", UntaggedValue::nothing()),
+        }
+        dict.insert_untagged("has_statistics", UntaggedValue::boolean(has_statistics));
+        dict.insert_untagged("offset", UntaggedValue::int(offset));
+        pages.push(dict.into_value());
+
+        offset += cursor.position() as i64 + i64::from(header.compressed_page_size.max(0));
+    }
+    Ok(UntaggedValue::Table(pages).into_value(&tag))
+}
@@ -0,0 +1,135 @@
+use parquet::basic::{LogicalType, Type as PhysicalType};
+use parquet::column::page::PageReader;
+use parquet::errors::Result;
+use parquet::file::metadata::{ColumnChunkMetaData, RowGroupMetaData};
+use parquet::file::reader::RowGroupReader;
+use parquet::record::reader::RowIter;
+use parquet::schema::types::{SchemaDescPtr, Type};
+use std::sync::Arc;
+
+/// Returns a copy of the schema without the annotations the parquet row reader cannot
+/// decode, or `None` when it can read the schema as is. Those columns are read as their
+/// raw physical values and converted using the original schema instead.
+///
+/// INT96 columns are read as 12-byte fixed length arrays, which have the same encoding,
+/// because the row reader truncates them to milliseconds. With `raw_strings`, UTF8, ENUM
+/// and JSON columns are read as bytes, as the row reader panics on invalid UTF-8.
+pub(crate) fn read_schema(ty: &Type, raw_strings: bool) -> Result<Option<Type>> {
+    let info = ty.get_basic_info();
+    if ty.is_primitive() && ty.get_physical_type() == PhysicalType::INT96 {
+        return Type::primitive_type_builder(info.name(), PhysicalType::FIXED_LEN_BYTE_ARRAY)
+            .with_repetition(info.repetition())
+            .with_length(12)
+            .build()
+            .map(Some);
+    }
+    if ty.is_primitive() {
+        return match info.logical_type() {
+            LogicalType::UTF8 | LogicalType::ENUM | LogicalType::JSON if raw_strings => {
+                Type::primitive_type_builder(info.name(), ty.get_physical_type())
+                    .with_repetition(info.repetition())
+                    .build()
+                    .map(Some)
+            }
+            LogicalType::TIME_MILLIS | LogicalType::TIME_MICROS => {
+                Type::primitive_type_builder(info.name(), ty.get_physical_type())
+                    .with_repetition(info.repetition())
+                    .build()
+                    .map(Some)
+            }
+            LogicalType::INTERVAL => {
+                Type::primitive_type_builder(info.name(), PhysicalType::FIXED_LEN_BYTE_ARRAY)
+                    .with_repetition(info.repetition())
+                    .with_length(12)
+                    .build()
+                    .map(Some)
+            }
+            _ => Ok(None),
+        };
+    }
+
+    let mut changed = false;
+    let mut fields = Vec::with_capacity(ty.get_fields().len());
+    for field in ty.get_fields() {
+        match read_schema(field, raw_strings)? {
+            Some(readable) => {
+                changed = true;
+                fields.push(Arc::new(readable));
+            }
+            None => fields.push(field.clone()),
+        }
+    }
+    if !changed {
+        return Ok(None);
+    }
+    let mut builder = Type::group_type_builder(info.name())
+        .with_logical_type(info.logical_type())
+        .with_fields(&mut fields);
+    if info.has_repetition() {
+        builder = builder.with_repetition(info.repetition());
+    }
+    if info.has_id() {
+        builder = builder.with_id(info.id());
+    }
+    builder.build().map(Some)
+}
+
+/// A row group whose metadata describes its columns with a schema from `read_schema`.
+/// The row reader takes the column annotations from the row group metadata, so this is
+/// what makes it decode those columns as raw values.
+pub(crate) struct ReadableRowGroup<'a> {
+    inner: Box<dyn RowGroupReader + 'a>,
+    metadata: RowGroupMetaData,
+}
+
+impl<'a> ReadableRowGroup<'a> {
+    pub(crate) fn new(inner: Box<dyn RowGroupReader + 'a>, schema: SchemaDescPtr) -> Result<Self> {
+        let original = inner.metadata();
+        let columns = original.columns().iter()
+            .zip(schema.columns())
+            .map(|(column, descr)| {
+                let mut builder = ColumnChunkMetaData::builder(descr.clone())
+                    .set_encodings(column.encodings().clone())
+                    .set_file_offset(column.file_offset())
+                    .set_num_values(column.num_values())
+                    .set_compression(column.compression())
+                    .set_total_compressed_size(column.compressed_size())
+                    .set_total_uncompressed_size(column.uncompressed_size())
+                    .set_data_page_offset(column.data_page_offset())
+                    .set_dictionary_page_offset(column.dictionary_page_offset())
+                    .set_index_page_offset(column.index_page_offset());
+                if let Some(path) = column.file_path() {
+                    builder = builder.set_file_path(path.clone());
+                }
+                if let Some(statistics) = column.statistics() {
+                    builder = builder.set_statistics(statistics.clone());
+                }
+                builder.build()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let metadata = RowGroupMetaData::builder(schema)
+            .set_num_rows(original.num_rows())
+            .set_total_byte_size(original.total_byte_size())
+            .set_column_metadata(columns)
+            .build()?;
+        Ok(ReadableRowGroup { inner, metadata })
+    }
+}
+
+impl<'a> RowGroupReader for ReadableRowGroup<'a> {
+    fn metadata(&self) -> &RowGroupMetaData {
+        &self.metadata
+    }
+
+    fn num_columns(&self) -> usize {
+        self.inner.num_columns()
+    }
+
+    fn get_column_page_reader(&self, i: usize) -> Result<Box<dyn PageReader>> {
+        self.inner.get_column_page_reader(i)
+    }
+
+    fn get_row_iter(&self, projection: Option<Type>) -> Result<RowIter<'_>> {
+        RowIter::from_row_group(projection, self)
+    }
+}
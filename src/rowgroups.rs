@@ -0,0 +1,46 @@
+use parquet::file::metadata::{ColumnChunkMetaData, RowGroupMetaData};
+use parquet::file::reader::FileReader;
+use nu_errors::ShellError;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use crate::open_reader;
+
+fn column_chunk(column: &ColumnChunkMetaData, tag: &Tag) -> Value {
+    let encodings = column.encodings().iter()
+        .map(|e| UntaggedValue::string(e.to_string()).into_value(tag))
+        .collect();
+
+    let mut dict = TaggedDictBuilder::new(tag);
+    dict.insert_untagged("name", UntaggedValue::string(column.column_path().string()));
+    dict.insert_untagged("compression", UntaggedValue::string(column.compression().to_string()));
+    dict.insert_untagged("encodings", UntaggedValue::Table(encodings));
+    dict.insert_untagged("compressed_size", UntaggedValue::int(column.compressed_size()));
+    dict.insert_untagged("uncompressed_size", UntaggedValue::int(column.uncompressed_size()));
+    dict.insert_untagged("num_values", UntaggedValue::int(column.num_values()));
+    dict.into_value()
+}
+
+fn row_group(index: usize, row_group: &RowGroupMetaData, tag: &Tag) -> Value {
+    let columns = row_group.columns().iter()
+        .map(|column| column_chunk(column, tag))
+        .collect();
+
+    let mut dict = TaggedDictBuilder::new(tag);
+    dict.insert_untagged("index", UntaggedValue::int(index as u64));
+    dict.insert_untagged("num_rows", UntaggedValue::int(row_group.num_rows()));
+    dict.insert_untagged("total_byte_size", UntaggedValue::int(row_group.total_byte_size()));
+    dict.insert_untagged("num_columns", UntaggedValue::int(row_group.num_columns() as u64));
+    dict.insert_untagged("columns", UntaggedValue::Table(columns));
+    dict.into_value()
+}
+
+/// Describes every row group and its column chunks from the footer, without decoding any rows.
+pub fn parquet_rowgroups(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+    let row_groups = reader.metadata().row_groups().iter()
+        .enumerate()
+        .map(|(index, rg)| row_group(index, rg, &tag))
+        .collect();
+    Ok(UntaggedValue::Table(row_groups).into_value(&tag))
+}
@@ -0,0 +1,60 @@
+use parquet::arrow::parquet_to_arrow_schema;
+use parquet::basic::LogicalType;
+use parquet::file::reader::FileReader;
+use parquet::schema::types::Type;
+use nu_errors::ShellError;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use crate::{open_reader, parquet_error};
+
+/// Converts a schema node and all of its children into a record with
+/// `name`, `type`, `repetition`, `logical_type` and `children` columns.
+fn convert_schema_type(ty: &Type, tag: &Tag) -> Value {
+    let info = ty.get_basic_info();
+    let mut dict = TaggedDictBuilder::new(tag);
+    dict.insert_untagged("name", UntaggedValue::string(ty.name()));
+    if ty.is_primitive() {
+        dict.insert_untagged("type", UntaggedValue::string(ty.get_physical_type().to_string()));
+    } else {
+        dict.insert_untagged("type", UntaggedValue::string("GROUP"));
+    }
+    if info.has_repetition() {
+        dict.insert_untagged("repetition", UntaggedValue::string(info.repetition().to_string()));
+    } else {
+        dict.insert_untagged("repetition", UntaggedValue::nothing());
+    }
+    match info.logical_type() {
+        LogicalType::NONE => dict.insert_untagged("logical_type", UntaggedValue::nothing()),
+        logical_type => dict.insert_untagged("logical_type", UntaggedValue::string(logical_type.to_string())),
+    }
+    if ty.is_group() {
+        let children = ty.get_fields().iter()
+            .map(|field| convert_schema_type(field, tag))
+            .collect();
+        dict.insert_untagged("children", UntaggedValue::Table(children));
+    } else {
+        dict.insert_untagged("children", UntaggedValue::nothing());
+    }
+    dict.into_value()
+}
+
+/// Reads the schema from the file footer without decoding any rows.
+pub fn parquet_schema(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+    Ok(convert_schema_type(reader.metadata().file_metadata().schema(), &tag))
+}
+
+/// Converts the schema from the file footer to Arrow and renders it in the Arrow JSON
+/// schema format, `{"fields": [...], "metadata": {...}}`, as read by other tools.
+pub fn parquet_schema_json(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+    let file_metadata = reader.metadata().file_metadata();
+    let schema = parquet_to_arrow_schema(file_metadata.schema_descr(), file_metadata.key_value_metadata())
+        .map_err(|e| parquet_error("Failed to convert Parquet schema to Arrow", e, &tag))?;
+    let json = serde_json::to_string_pretty(&schema.to_json()).map_err(|e| {
+        ShellError::labeled_error("Failed to render Arrow schema", e.to_string(), &tag)
+    })?;
+    Ok(UntaggedValue::string(json).into_value(tag))
+}
@@ -0,0 +1,257 @@
+use nu_errors::ShellError;
+use nu_protocol::{Primitive, ShellTypeName, TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use indexmap::IndexMap;
+use std::path::{Path, PathBuf};
+use crate::from_parquet::{from_parquet_bytes, FromParquetOpts};
+use crate::to_parquet::{to_parquet_bytes, ToParquetOpts};
+
+/// Directory name Hive gives the partition of null values.
+const NULL_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// The text of a value in a partition directory name, `None` for nested values which
+/// cannot name a partition.
+fn partition_value(value: &Value) -> Option<String> {
+    let text = match &value.value {
+        UntaggedValue::Primitive(Primitive::Nothing) => NULL_PARTITION.to_string(),
+        UntaggedValue::Primitive(Primitive::String(s)) => s.clone(),
+        UntaggedValue::Primitive(Primitive::Int(i)) => i.to_string(),
+        UntaggedValue::Primitive(Primitive::Decimal(d)) => d.to_string(),
+        UntaggedValue::Primitive(Primitive::Boolean(b)) => b.to_string(),
+        UntaggedValue::Primitive(Primitive::Date(d)) => d.to_rfc3339(),
+        UntaggedValue::Primitive(Primitive::Filesize(size)) => size.to_string(),
+        UntaggedValue::Primitive(Primitive::Duration(nanos)) => nanos.to_string(),
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// Escapes the characters Hive escapes in partition directory names as `%XX`.
+fn escape_path_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_control() || "\"#%'*/:=?\\{[]^".contains(c) {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn write_error(path: &Path, error: std::io::Error, tag: &Tag) -> ShellError {
+    ShellError::labeled_error(format!("Failed to write {}", path.display()), error.to_string(), tag)
+}
+
+/// What `to parquet --partition-by` does with a partition file that already exists.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExistingFiles {
+    /// Fail before writing anything.
+    Error,
+    /// Replace the file with the new rows.
+    Overwrite,
+    /// Rewrite the file with its rows followed by the new ones.
+    Append,
+}
+
+/// Groups records by the value of their top-level column `by`, in the order the
+/// values first appear, leaving the column out of them.
+fn partition(rows: Vec<Value>, by: &str, tag: &Tag) -> Result<IndexMap<String, Vec<Value>>, ShellError> {
+    let mut partitions: IndexMap<String, Vec<Value>> = IndexMap::new();
+    for row in rows {
+        let mut dict = match row.value {
+            UntaggedValue::Row(dict) => dict,
+            other => {
+                return Err(ShellError::labeled_error(
+                    format!("Cannot partition by '{}'", by),
+                    format!("expected records, found {}", other.type_name()),
+                    &row.tag,
+                ))
+            }
+        };
+        let value = match dict.entries.shift_remove(by) {
+            Some(value) => value,
+            None => {
+                let available: Vec<&str> = dict.entries.keys().map(String::as_str).collect();
+                return Err(ShellError::labeled_error(
+                    format!("Unknown split column '{}'", by),
+                    format!("available columns are: {}", available.join(", ")),
+                    tag,
+                ));
+            }
+        };
+        if dict.entries.is_empty() {
+            return Err(ShellError::labeled_error(
+                format!("Cannot split by '{}', the only column", by),
+                "partition files would have no columns left",
+                tag,
+            ));
+        }
+        let partition = partition_value(&value).ok_or_else(|| {
+            ShellError::labeled_error(
+                format!("Cannot split by column '{}'", by),
+                format!("{} values cannot name a partition", value.type_name()),
+                tag,
+            )
+        })?;
+        partitions.entry(partition).or_default().push(UntaggedValue::Row(dict).into_value(row.tag));
+    }
+    Ok(partitions)
+}
+
+/// The `<output_dir>/<by>=<value>/part-0.parquet` file of a partition.
+fn partition_path(output_dir: &Path, by: &str, partition: &str) -> PathBuf {
+    output_dir
+        .join(format!("{}={}", escape_path_name(by), escape_path_name(partition)))
+        .join("part-0.parquet")
+}
+
+/// Creates the directory of a partition file and writes the file.
+fn write_partition(path: &Path, rows: &[Value], to: &ToParquetOpts, tag: &Tag) -> Result<usize, ShellError> {
+    let data = to_parquet_bytes(rows, tag.clone(), to)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| write_error(dir, e, tag))?;
+    }
+    std::fs::write(path, &data).map_err(|e| write_error(path, e, tag))?;
+    Ok(data.len())
+}
+
+/// Splits a Parquet file into one file per value of the top-level column `by`, written
+/// to `<output_dir>/<by>=<value>/part-0.parquet` the way Hive lays out partitions.
+/// As in Hive the column itself is left out of the files, its value is in their path.
+/// Returns the partition value, file path and row count of every file written.
+pub fn parquet_split(
+    bytes: Vec<u8>,
+    tag: impl Into<Tag>,
+    by: &str,
+    output_dir: &Path,
+    to: &ToParquetOpts,
+) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let rows = match from_parquet_bytes(bytes, tag.clone(), &FromParquetOpts::default())?.value {
+        UntaggedValue::Table(rows) => rows,
+        _ => unreachable!("Internal error: from parquet did not return a table"),
+    };
+
+    let mut files = Vec::new();
+    for (partition, rows) in partition(rows, by, &tag)? {
+        let path = partition_path(output_dir, by, &partition);
+        write_partition(&path, &rows, to, &tag)?;
+
+        let mut dict = TaggedDictBuilder::new(&tag);
+        dict.insert_untagged("partition_value", UntaggedValue::string(partition));
+        dict.insert_untagged("file_path", UntaggedValue::string(path.display().to_string()));
+        dict.insert_untagged("row_count", UntaggedValue::int(rows.len() as u64));
+        files.push(dict.into_value());
+    }
+    Ok(UntaggedValue::Table(files).into_value(&tag))
+}
+
+/// Writes a table as one Parquet file per value of its top-level column `by`, laid out
+/// as `parquet split` lays them out, for `to parquet --partition-by`. Partition files
+/// that already exist are handled as `existing` says, every one of them is checked
+/// before any file is written. Returns the partition value, file path, row count and
+/// size in bytes of every file written, appended files counting their old rows too.
+pub fn to_parquet_partitioned(
+    rows: Vec<Value>,
+    tag: impl Into<Tag>,
+    by: &str,
+    output_dir: &Path,
+    to: &ToParquetOpts,
+    existing: ExistingFiles,
+) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let partitions = partition(rows, by, &tag)?;
+    if existing == ExistingFiles::Error {
+        for partition in partitions.keys() {
+            let path = partition_path(output_dir, by, partition);
+            if path.exists() {
+                return Err(ShellError::labeled_error(
+                    format!("Partition file {} already exists", path.display()),
+                    "use --overwrite to replace it or --append to add to it",
+                    &tag,
+                ));
+            }
+        }
+    }
+
+    let mut files = Vec::with_capacity(partitions.len());
+    for (partition, mut rows) in partitions {
+        let path = partition_path(output_dir, by, &partition);
+        if existing == ExistingFiles::Append && path.exists() {
+            let old = std::fs::read(&path).map_err(|e| {
+                ShellError::labeled_error(format!("Failed to read {}", path.display()), e.to_string(), &tag)
+            })?;
+            let mut old = match from_parquet_bytes(old, tag.clone(), &FromParquetOpts::default())?.value {
+                UntaggedValue::Table(old) => old,
+                _ => unreachable!("Internal error: from parquet did not return a table"),
+            };
+            old.append(&mut rows);
+            rows = old;
+        }
+        let bytes = write_partition(&path, &rows, to, &tag)?;
+
+        let mut dict = TaggedDictBuilder::new(&tag);
+        dict.insert_untagged("partition", UntaggedValue::string(partition));
+        dict.insert_untagged("path", UntaggedValue::string(path.display().to_string()));
+        dict.insert_untagged("rows", UntaggedValue::int(rows.len() as u64));
+        dict.insert_untagged("bytes", UntaggedValue::int(bytes as u64));
+        files.push(dict.into_value());
+    }
+    Ok(UntaggedValue::Table(files).into_value(&tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: Vec<(&str, UntaggedValue)>) -> Value {
+        let mut dict = TaggedDictBuilder::new(Tag::unknown());
+        for (name, value) in fields {
+            dict.insert_untagged(name, value);
+        }
+        dict.into_value()
+    }
+
+    #[test]
+    fn escapes_what_hive_escapes() {
+        assert_eq!(escape_path_name("plain name-1.5"), "plain name-1.5");
+        assert_eq!(escape_path_name("a/b=c"), "a%2Fb%3Dc");
+        assert_eq!(escape_path_name("100%?"), "100%25%3F");
+        assert_eq!(escape_path_name("tab\there"), "tab%09here");
+        assert_eq!(escape_path_name("é"), "é");
+    }
+
+    #[test]
+    fn partitions_rows_in_first_seen_order() {
+        let rows = vec![
+            row(vec![("city", UntaggedValue::string("Oslo")), ("n", UntaggedValue::int(1))]),
+            row(vec![("city", UntaggedValue::nothing()), ("n", UntaggedValue::int(2))]),
+            row(vec![("city", UntaggedValue::string("Lima")), ("n", UntaggedValue::int(3))]),
+            row(vec![("city", UntaggedValue::string("Oslo")), ("n", UntaggedValue::int(4))]),
+        ];
+        let partitions = partition(rows, "city", &Tag::unknown()).unwrap();
+
+        assert_eq!(partitions.keys().collect::<Vec<_>>(), vec!["Oslo", NULL_PARTITION, "Lima"]);
+        assert_eq!(partitions["Oslo"], vec![row(vec![("n", UntaggedValue::int(1))]), row(vec![("n", UntaggedValue::int(4))])]);
+        assert_eq!(partitions[NULL_PARTITION], vec![row(vec![("n", UntaggedValue::int(2))])]);
+        assert_eq!(
+            partition_path(Path::new("out"), "city", NULL_PARTITION),
+            Path::new("out").join("city=__HIVE_DEFAULT_PARTITION__").join("part-0.parquet")
+        );
+    }
+
+    #[test]
+    fn refuses_rows_that_cannot_be_partitioned() {
+        let tag = Tag::unknown();
+        let nested = row(vec![("a", UntaggedValue::int(1))]).value;
+
+        assert!(partition(vec![row(vec![("a", UntaggedValue::int(1)), ("b", UntaggedValue::int(2))])], "c", &tag).is_err());
+        assert!(partition(vec![row(vec![("a", UntaggedValue::int(1))])], "a", &tag).is_err());
+        assert!(partition(vec![row(vec![("a", nested), ("b", UntaggedValue::int(2))])], "a", &tag).is_err());
+        assert!(partition(vec![UntaggedValue::int(1).into_value(&tag)], "a", &tag).is_err());
+    }
+}
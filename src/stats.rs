@@ -0,0 +1,139 @@
+use parquet::basic::LogicalType;
+use parquet::file::reader::FileReader;
+use parquet::file::statistics::Statistics;
+use parquet::schema::types::ColumnDescriptor;
+use nu_errors::ShellError;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use bigdecimal::{BigDecimal, FromPrimitive};
+use crate::convert::{date_or_nothing, days_to_date, timestamp_to_date, TimeUnit};
+use crate::open_reader;
+
+fn decimal_or_nothing(decimal: Option<BigDecimal>) -> UntaggedValue {
+    decimal.map(UntaggedValue::decimal).unwrap_or_else(UntaggedValue::nothing)
+}
+
+/// Converts the min and max statistics of a column chunk into the Nushell type
+/// `from parquet` would produce for values of that column.
+pub(crate) fn min_max(stats: &Statistics, column: &ColumnDescriptor) -> (UntaggedValue, UntaggedValue) {
+    if !stats.has_min_max_set() {
+        return (UntaggedValue::nothing(), UntaggedValue::nothing());
+    }
+
+    match stats {
+        Statistics::Boolean(s) => (
+            UntaggedValue::boolean(*s.min()),
+            UntaggedValue::boolean(*s.max()),
+        ),
+        Statistics::Int32(s) => match column.logical_type() {
+            LogicalType::DATE => (
+                date_or_nothing(days_to_date(i64::from(*s.min()))),
+                date_or_nothing(days_to_date(i64::from(*s.max()))),
+            ),
+            _ => (UntaggedValue::int(*s.min()), UntaggedValue::int(*s.max())),
+        },
+        Statistics::Int64(s) => match column.logical_type() {
+            LogicalType::TIMESTAMP_MILLIS => (
+                date_or_nothing(timestamp_to_date(*s.min(), TimeUnit::Millis)),
+                date_or_nothing(timestamp_to_date(*s.max(), TimeUnit::Millis)),
+            ),
+            LogicalType::TIMESTAMP_MICROS => (
+                date_or_nothing(timestamp_to_date(*s.min(), TimeUnit::Micros)),
+                date_or_nothing(timestamp_to_date(*s.max(), TimeUnit::Micros)),
+            ),
+            _ => (UntaggedValue::int(*s.min()), UntaggedValue::int(*s.max())),
+        },
+        Statistics::Int96(s) => (
+            date_or_nothing(timestamp_to_date(s.min().to_i64(), TimeUnit::Millis)),
+            date_or_nothing(timestamp_to_date(s.max().to_i64(), TimeUnit::Millis)),
+        ),
+        Statistics::Float(s) => (
+            decimal_or_nothing(BigDecimal::from_f32(*s.min())),
+            decimal_or_nothing(BigDecimal::from_f32(*s.max())),
+        ),
+        Statistics::Double(s) => (
+            decimal_or_nothing(BigDecimal::from_f64(*s.min())),
+            decimal_or_nothing(BigDecimal::from_f64(*s.max())),
+        ),
+        Statistics::ByteArray(_) | Statistics::FixedLenByteArray(_) => match column.logical_type() {
+            LogicalType::UTF8 | LogicalType::ENUM | LogicalType::JSON => (
+                UntaggedValue::string(String::from_utf8_lossy(stats.min_bytes())),
+                UntaggedValue::string(String::from_utf8_lossy(stats.max_bytes())),
+            ),
+            _ => (
+                UntaggedValue::binary(stats.min_bytes().to_vec()),
+                UntaggedValue::binary(stats.max_bytes().to_vec()),
+            ),
+        },
+    }
+}
+
+/// Returns one record per row group and column with the statistics stored in the footer.
+pub fn parquet_stats(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+
+    let mut result = Vec::new();
+    for (index, row_group) in reader.metadata().row_groups().iter().enumerate() {
+        for column in row_group.columns() {
+            let mut dict = TaggedDictBuilder::new(&tag);
+            dict.insert_untagged("row_group", UntaggedValue::int(index as u64));
+            dict.insert_untagged("column", UntaggedValue::string(column.column_path().string()));
+            match column.statistics() {
+                Some(stats) => {
+                    let (min, max) = min_max(stats, column.column_descr());
+                    dict.insert_untagged("null_count", UntaggedValue::int(stats.null_count()));
+                    match stats.distinct_count() {
+                        Some(count) => dict.insert_untagged("distinct_count", UntaggedValue::int(count)),
+                        None => dict.insert_untagged("distinct_count", UntaggedValue::nothing()),
+                    }
+                    dict.insert_untagged("min_value", min);
+                    dict.insert_untagged("max_value", max);
+                }
+                None => {
+                    dict.insert_untagged("null_count", UntaggedValue::nothing());
+                    dict.insert_untagged("distinct_count", UntaggedValue::nothing());
+                    dict.insert_untagged("min_value", UntaggedValue::nothing());
+                    dict.insert_untagged("max_value", UntaggedValue::nothing());
+                }
+            }
+            result.push(dict.into_value());
+        }
+    }
+
+    Ok(UntaggedValue::Table(result).into_value(tag))
+}
+
+/// Returns a record with the null count of every column summed over the row groups,
+/// and its percentage of the rows of the file. Columns missing statistics in any row
+/// group have no known count and map to nothing.
+pub fn parquet_null_stats(bytes: Vec<u8>, tag: impl Into<Tag>) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let reader = open_reader(bytes, &tag)?;
+    let metadata = reader.metadata();
+    let num_rows = metadata.file_metadata().num_rows();
+
+    let mut dict = TaggedDictBuilder::new(&tag);
+    for (i, column) in metadata.file_metadata().schema_descr().columns().iter().enumerate() {
+        let null_count = metadata.row_groups().iter()
+            .map(|row_group| row_group.column(i).statistics().map(|stats| stats.null_count()))
+            .sum::<Option<u64>>();
+        let mut counts = TaggedDictBuilder::new(&tag);
+        match null_count {
+            Some(count) => {
+                counts.insert_untagged("null_count", UntaggedValue::int(count));
+                let percent = match num_rows {
+                    0 => None,
+                    rows => BigDecimal::from_f64(count as f64 * 100.0 / rows as f64),
+                };
+                counts.insert_untagged("null_percent", decimal_or_nothing(percent));
+            }
+            None => {
+                counts.insert_untagged("null_count", UntaggedValue::nothing());
+                counts.insert_untagged("null_percent", UntaggedValue::nothing());
+            }
+        }
+        dict.insert_value(column.path().string(), counts.into_value());
+    }
+    Ok(dict.into_value())
+}
@@ -0,0 +1,677 @@
+use parquet::basic::{Compression, LogicalType, Repetition, Type as PhysicalType};
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{FileWriter, InMemoryWriteableCursor, SerializedFileWriter};
+use parquet::schema::types::{SchemaDescriptor, Type};
+use nu_errors::ShellError;
+use nu_protocol::{Primitive, ShellTypeName, TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use bigdecimal::ToPrimitive;
+use std::sync::Arc;
+use crate::bloom::{write_bloom_filters, DEFAULT_BLOOM_FILTER_FPP};
+use crate::page_index::{rewrite_pages, PageIndexReport, PageRewrite};
+use crate::parquet_error;
+
+/// Compression codec applied to every column chunk written by `to parquet`. GZIP and
+/// ZSTD carry the level asked for with `--compression-level`, which parquet 3.0 can
+/// only write at their default level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompressionChoice {
+    /// Pages are stored as they are.
+    Uncompressed,
+    /// Snappy, fast with a modest ratio.
+    Snappy,
+    /// GZIP at a level of 0 to 9.
+    Gzip(u32),
+    /// ZSTD at a level of 1 to 22, the default codec.
+    Zstd(i32),
+    /// LZ4.
+    Lz4,
+}
+
+/// Level parquet 3.0 compresses GZIP pages with.
+pub const GZIP_DEFAULT_LEVEL: u32 = 6;
+/// Level parquet 3.0 compresses ZSTD pages with.
+pub const ZSTD_DEFAULT_LEVEL: i32 = 1;
+
+impl CompressionChoice {
+    /// Looks up a codec by the name `--compression` takes, ignoring case. GZIP and
+    /// ZSTD get their default level.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "uncompressed" | "none" => Some(CompressionChoice::Uncompressed),
+            "snappy" => Some(CompressionChoice::Snappy),
+            "gzip" => Some(CompressionChoice::Gzip(GZIP_DEFAULT_LEVEL)),
+            "zstd" => Some(CompressionChoice::Zstd(ZSTD_DEFAULT_LEVEL)),
+            "lz4" => Some(CompressionChoice::Lz4),
+            _ => None,
+        }
+    }
+
+    /// Whether `--compression-level` applies to the codec.
+    pub fn takes_level(self) -> bool {
+        matches!(self, CompressionChoice::Gzip(_) | CompressionChoice::Zstd(_))
+    }
+
+    /// The codec with the given level, or `None` when the level is out of its range.
+    pub fn with_level(self, level: i64) -> Option<Self> {
+        match self {
+            CompressionChoice::Zstd(_) if (1..=22).contains(&level) => Some(CompressionChoice::Zstd(level as i32)),
+            CompressionChoice::Gzip(_) if (0..=9).contains(&level) => Some(CompressionChoice::Gzip(level as u32)),
+            _ => None,
+        }
+    }
+
+    /// Fails unless pages can be compressed at the level of the choice. The codecs of
+    /// parquet 3.0 have fixed levels, any other level cannot be set.
+    pub fn check_level(self, tag: &Tag) -> Result<(), ShellError> {
+        let applies = match self {
+            CompressionChoice::Gzip(level) => level == GZIP_DEFAULT_LEVEL,
+            CompressionChoice::Zstd(level) => level == ZSTD_DEFAULT_LEVEL,
+            _ => true,
+        };
+        if applies {
+            return Ok(());
+        }
+        Err(ShellError::labeled_error(
+            "Compression level cannot be set",
+            format!(
+                "parquet 3.0 cannot set codec levels, zstd compresses at level {} and gzip at level {}",
+                ZSTD_DEFAULT_LEVEL, GZIP_DEFAULT_LEVEL
+            ),
+            tag,
+        ))
+    }
+
+    fn codec(self) -> Compression {
+        match self {
+            CompressionChoice::Uncompressed => Compression::UNCOMPRESSED,
+            CompressionChoice::Snappy => Compression::SNAPPY,
+            CompressionChoice::Gzip(_) => Compression::GZIP,
+            CompressionChoice::Zstd(_) => Compression::ZSTD,
+            CompressionChoice::Lz4 => Compression::LZ4,
+        }
+    }
+}
+
+/// Statistics written by `to parquet`. Page statistics let readers skip pages rather
+/// than whole row groups, at the cost of a larger header on every page.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatisticsLevel {
+    /// No statistics at all.
+    None,
+    /// Statistics of every column chunk, in the footer.
+    Chunk,
+    /// Statistics of every column chunk and of every page, in the page headers.
+    Page,
+}
+
+impl StatisticsLevel {
+    /// Looks up a level by the name `--statistics` takes, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(StatisticsLevel::None),
+            "chunk" => Some(StatisticsLevel::Chunk),
+            "page" => Some(StatisticsLevel::Page),
+            _ => None,
+        }
+    }
+}
+
+/// Rows written to a row group when `--row-group-size` is not given.
+pub const DEFAULT_ROW_GROUP_SIZE: usize = 1_000_000;
+/// Row groups are closed once their values take about this many bytes, whatever
+/// their row count.
+const MAX_ROW_GROUP_BYTES: usize = 128 * 1024 * 1024;
+
+/// Options controlling how `to parquet` writes a table.
+#[derive(Clone, Debug)]
+pub struct ToParquetOpts {
+    /// Codec of every column chunk.
+    pub compression: CompressionChoice,
+    /// Most rows written to a single row group.
+    pub row_group_size: usize,
+    /// Leaf columns that get a Bloom filter in every row group.
+    pub bloom_filter_columns: Vec<String>,
+    /// False-positive probability the Bloom filters are sized for.
+    pub bloom_filter_fpp: f64,
+    /// Write a column index and an offset index for every column chunk.
+    pub column_index: bool,
+    /// Dictionary encode columns not named in `dictionary_columns`.
+    pub dictionary: bool,
+    /// Columns, top-level or dotted leaf paths, with whether they are dictionary encoded.
+    pub dictionary_columns: Vec<(String, bool)>,
+    /// Statistics kept in the file.
+    pub statistics: StatisticsLevel,
+}
+
+impl Default for ToParquetOpts {
+    fn default() -> Self {
+        ToParquetOpts {
+            compression: CompressionChoice::Zstd(ZSTD_DEFAULT_LEVEL),
+            row_group_size: DEFAULT_ROW_GROUP_SIZE,
+            bloom_filter_columns: Vec::new(),
+            bloom_filter_fpp: DEFAULT_BLOOM_FILTER_FPP,
+            column_index: false,
+            dictionary: true,
+            dictionary_columns: Vec::new(),
+            statistics: StatisticsLevel::Chunk,
+        }
+    }
+}
+
+/// Nushell types that can be stored in a Parquet leaf column.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColumnKind {
+    Boolean,
+    Int,
+    Float,
+    String,
+    Binary,
+    Date,
+}
+
+impl ColumnKind {
+    fn of(value: &Value) -> Option<ColumnKind> {
+        match &value.value {
+            UntaggedValue::Primitive(Primitive::Boolean(_)) => Some(ColumnKind::Boolean),
+            UntaggedValue::Primitive(Primitive::Int(_))
+            | UntaggedValue::Primitive(Primitive::Filesize(_))
+            | UntaggedValue::Primitive(Primitive::Duration(_)) => Some(ColumnKind::Int),
+            UntaggedValue::Primitive(Primitive::Decimal(_)) => Some(ColumnKind::Float),
+            UntaggedValue::Primitive(Primitive::String(_)) => Some(ColumnKind::String),
+            UntaggedValue::Primitive(Primitive::Binary(_)) => Some(ColumnKind::Binary),
+            UntaggedValue::Primitive(Primitive::Date(_)) => Some(ColumnKind::Date),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ColumnKind::Boolean => "boolean",
+            ColumnKind::Int => "integer",
+            ColumnKind::Float => "decimal",
+            ColumnKind::String => "string",
+            ColumnKind::Binary => "binary",
+            ColumnKind::Date => "date",
+        }
+    }
+
+    fn parquet_type(self, name: &str) -> parquet::errors::Result<Type> {
+        let (physical, logical) = match self {
+            ColumnKind::Boolean => (PhysicalType::BOOLEAN, LogicalType::NONE),
+            ColumnKind::Int => (PhysicalType::INT64, LogicalType::NONE),
+            ColumnKind::Float => (PhysicalType::DOUBLE, LogicalType::NONE),
+            ColumnKind::String => (PhysicalType::BYTE_ARRAY, LogicalType::UTF8),
+            ColumnKind::Binary => (PhysicalType::BYTE_ARRAY, LogicalType::NONE),
+            ColumnKind::Date => (PhysicalType::INT64, LogicalType::TIMESTAMP_MICROS),
+        };
+        Type::primitive_type_builder(name, physical)
+            .with_repetition(Repetition::OPTIONAL)
+            .with_logical_type(logical)
+            .build()
+    }
+}
+
+/// Shape of a column, merged from its values in every row.
+#[derive(Clone, Debug, PartialEq)]
+enum Shape {
+    /// Only nulls were seen. Nulls carry no type information, such columns are written
+    /// as optional strings.
+    Unknown,
+    Leaf(ColumnKind),
+    List(Box<Shape>),
+    Record(Vec<(String, Shape)>),
+}
+
+impl Shape {
+    fn of(value: &Value, path: &str) -> Result<Shape, ShellError> {
+        match &value.value {
+            UntaggedValue::Primitive(Primitive::Nothing) => Ok(Shape::Unknown),
+            UntaggedValue::Table(values) => {
+                let mut element = Shape::Unknown;
+                for value in values {
+                    element = element.merge(Shape::of(value, path)?, path, value)?;
+                }
+                Ok(Shape::List(Box::new(element)))
+            }
+            UntaggedValue::Row(dict) => dict.entries.iter()
+                .map(|(name, value)| Ok((name.clone(), Shape::of(value, &format!("{}.{}", path, name))?)))
+                .collect::<Result<Vec<_>, ShellError>>()
+                .map(Shape::Record),
+            _ => match ColumnKind::of(value) {
+                Some(kind) => Ok(Shape::Leaf(kind)),
+                None => Err(ShellError::labeled_error(
+                    format!("Cannot write column '{}' to Parquet", path),
+                    format!("{} values are not supported", value.type_name()),
+                    &value.tag,
+                )),
+            },
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Shape::Unknown => "nothing",
+            Shape::Leaf(kind) => kind.name(),
+            Shape::List(_) => "list",
+            Shape::Record(_) => "record",
+        }
+    }
+
+    /// Merges the shape of a later value of the column, `value` is only used to point
+    /// at it when the two conflict.
+    fn merge(self, other: Shape, path: &str, value: &Value) -> Result<Shape, ShellError> {
+        match (self, other) {
+            (Shape::Unknown, shape) | (shape, Shape::Unknown) => Ok(shape),
+            (Shape::Leaf(a), Shape::Leaf(b)) if a == b => Ok(Shape::Leaf(a)),
+            (Shape::List(a), Shape::List(b)) => Ok(Shape::List(Box::new(a.merge(*b, path, value)?))),
+            (Shape::Record(mut fields), Shape::Record(others)) => {
+                for (name, shape) in others {
+                    match fields.iter_mut().find(|(field, _)| *field == name) {
+                        Some((_, field)) => {
+                            let merged = std::mem::replace(field, Shape::Unknown);
+                            let path = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+                            *field = merged.merge(shape, &path, value)?;
+                        }
+                        None => fields.push((name, shape)),
+                    }
+                }
+                Ok(Shape::Record(fields))
+            }
+            (a, b) => Err(ShellError::labeled_error(
+                format!("Conflicting types in column '{}'", path),
+                format!("found {} after {}", b.name(), a.name()),
+                &value.tag,
+            )),
+        }
+    }
+
+    fn parquet_type(&self, name: &str) -> parquet::errors::Result<Type> {
+        match self {
+            Shape::Unknown => ColumnKind::String.parquet_type(name),
+            Shape::Leaf(kind) => kind.parquet_type(name),
+            Shape::Record(fields) => {
+                let mut children = fields.iter()
+                    .map(|(field, shape)| shape.parquet_type(field).map(Arc::new))
+                    .collect::<parquet::errors::Result<Vec<_>>>()?;
+                Type::group_type_builder(name)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .with_fields(&mut children)
+                    .build()
+            }
+            // The three-level layout of the LIST specification.
+            Shape::List(element) => {
+                let element = element.parquet_type("element")?;
+                let list = Type::group_type_builder("list")
+                    .with_repetition(Repetition::REPEATED)
+                    .with_fields(&mut vec![Arc::new(element)])
+                    .build()?;
+                Type::group_type_builder(name)
+                    .with_repetition(Repetition::OPTIONAL)
+                    .with_logical_type(LogicalType::LIST)
+                    .with_fields(&mut vec![Arc::new(list)])
+                    .build()
+            }
+        }
+    }
+}
+
+/// Infers the Parquet schema of a table from all of its rows. Each column takes the
+/// shape of its first non-null values, later values of another type are an error.
+fn infer_parquet_schema(rows: &[Value]) -> Result<(Type, Vec<(String, Shape)>), ShellError> {
+    let mut columns = Shape::Record(Vec::new());
+    for row in rows {
+        if !matches!(row.value, UntaggedValue::Row(_)) {
+            return Err(expected_row(row));
+        }
+        let shape = match &row.value {
+            UntaggedValue::Row(dict) => dict.entries.iter()
+                .map(|(name, value)| Ok((name.clone(), Shape::of(value, name)?)))
+                .collect::<Result<Vec<_>, ShellError>>()?,
+            _ => unreachable!("Internal error: row is not a record"),
+        };
+        columns = columns.merge(Shape::Record(shape), "", row)?;
+    }
+    let columns = match columns {
+        Shape::Record(columns) => columns,
+        _ => unreachable!("Internal error: table shape is not a record"),
+    };
+    let mut fields = columns.iter()
+        .map(|(name, shape)| shape.parquet_type(name).map(Arc::new))
+        .collect::<parquet::errors::Result<Vec<_>>>()
+        .map_err(|e| ShellError::untagged_runtime_error(format!("Failed to build Parquet schema: {}", e)))?;
+    let schema = Type::group_type_builder("schema")
+        .with_fields(&mut fields)
+        .build()
+        .map_err(|e| ShellError::untagged_runtime_error(format!("Failed to build Parquet schema: {}", e)))?;
+    Ok((schema, columns))
+}
+
+/// Rough size of a value once written, before encoding and compression.
+fn row_bytes(value: &Value) -> usize {
+    match &value.value {
+        UntaggedValue::Row(dict) => dict.entries.values().map(row_bytes).sum(),
+        UntaggedValue::Table(values) => values.iter().map(row_bytes).sum(),
+        UntaggedValue::Primitive(Primitive::Boolean(_)) => 1,
+        UntaggedValue::Primitive(Primitive::String(s)) => s.len() + 4,
+        UntaggedValue::Primitive(Primitive::Binary(b)) => b.len() + 4,
+        _ => 8,
+    }
+}
+
+/// Splits the rows into row groups of at most `size` rows and `MAX_ROW_GROUP_BYTES`.
+fn row_groups(rows: &[Value], size: usize) -> Vec<&[Value]> {
+    let mut groups = Vec::new();
+    let (mut start, mut bytes) = (0, 0);
+    for (i, row) in rows.iter().enumerate() {
+        bytes += row_bytes(row);
+        if i + 1 - start >= size || bytes >= MAX_ROW_GROUP_BYTES {
+            groups.push(&rows[start..=i]);
+            start = i + 1;
+            bytes = 0;
+        }
+    }
+    if start < rows.len() {
+        groups.push(&rows[start..]);
+    }
+    groups
+}
+
+/// How to reach a leaf column from a row.
+#[derive(Clone, Debug)]
+enum Step {
+    Field(String),
+    Element,
+}
+
+/// A leaf column of the schema, in the order the writer expects them.
+struct Leaf {
+    name: String,
+    path: Vec<Step>,
+    kind: ColumnKind,
+    repeated: bool,
+}
+
+fn collect_leaves(shape: &Shape, name: String, path: Vec<Step>, repeated: bool, leaves: &mut Vec<Leaf>) {
+    match shape {
+        Shape::Unknown => leaves.push(Leaf { name, path, kind: ColumnKind::String, repeated }),
+        Shape::Leaf(kind) => leaves.push(Leaf { name, path, kind: *kind, repeated }),
+        Shape::List(element) => {
+            let mut path = path;
+            path.push(Step::Element);
+            collect_leaves(element, name, path, true, leaves);
+        }
+        Shape::Record(fields) => {
+            for (field, shape) in fields {
+                let mut path = path.clone();
+                path.push(Step::Field(field.clone()));
+                collect_leaves(shape, format!("{}.{}", name, field), path, repeated, leaves);
+            }
+        }
+    }
+}
+
+/// The non-null values of a leaf column with their definition and repetition levels.
+#[derive(Default)]
+struct Levels<'a> {
+    values: Vec<&'a Value>,
+    def_levels: Vec<i16>,
+    rep_levels: Vec<i16>,
+}
+
+impl<'a> Levels<'a> {
+    fn push(&mut self, value: Option<&'a Value>, def: i16, rep: i16) {
+        self.values.extend(value);
+        self.def_levels.push(def);
+        self.rep_levels.push(rep);
+    }
+}
+
+fn is_nothing(value: &Value) -> bool {
+    matches!(value.value, UntaggedValue::Primitive(Primitive::Nothing))
+}
+
+/// Shreds the value of an optional node into the leaf column reached through `path`.
+/// `def` is the definition level of the node's parent and `depth` the number of
+/// repeated nodes above it.
+fn shred<'a>(value: Option<&'a Value>, path: &[Step], def: i16, rep: i16, depth: i16, levels: &mut Levels<'a>) {
+    let value = match value {
+        Some(value) if !is_nothing(value) => value,
+        _ => return levels.push(None, def, rep),
+    };
+    match (path.split_first(), &value.value) {
+        (None, _) => levels.push(Some(value), def + 1, rep),
+        (Some((Step::Field(name), rest)), UntaggedValue::Row(dict)) => {
+            shred(dict.entries.get(name), rest, def + 1, rep, depth, levels)
+        }
+        // Empty lists are defined up to the list group, each element adds the repeated
+        // group and repeats at the depth of this list.
+        (Some((Step::Element, rest)), UntaggedValue::Table(values)) if !values.is_empty() => {
+            for (i, element) in values.iter().enumerate() {
+                let rep = if i == 0 { rep } else { depth + 1 };
+                shred(Some(element), rest, def + 2, rep, depth + 1, levels);
+            }
+        }
+        (Some((Step::Element, _)), _) => levels.push(None, def + 1, rep),
+        (Some((Step::Field(_), _)), _) => levels.push(None, def, rep),
+    }
+}
+
+fn leaf_levels<'a>(leaf: &Leaf, rows: &'a [Value]) -> Levels<'a> {
+    let mut levels = Levels::default();
+    let (column, rest) = match leaf.path.split_first() {
+        Some((Step::Field(column), rest)) => (column, rest),
+        _ => unreachable!("Internal error: leaf path does not start with a column"),
+    };
+    for row in rows {
+        let value = match &row.value {
+            UntaggedValue::Row(dict) => dict.entries.get(column),
+            _ => None,
+        };
+        shred(value, rest, 0, 0, 0, &mut levels);
+    }
+    levels
+}
+
+fn expected_row(value: &Value) -> ShellError {
+    ShellError::labeled_error(
+        "Expected a table of rows",
+        format!("found {}", value.type_name()),
+        &value.tag,
+    )
+}
+
+fn type_mismatch(leaf: &Leaf, value: &Value) -> ShellError {
+    ShellError::labeled_error(
+        format!("Expected {} value in column '{}'", leaf.kind.name(), leaf.name),
+        format!("found {}", value.type_name()),
+        &value.tag,
+    )
+}
+
+/// Extracts the values of a leaf column in the writer's type.
+fn leaf_values<T>(
+    leaf: &Leaf,
+    values: &[&Value],
+    extract: impl Fn(&Value) -> Option<Result<T, ShellError>>,
+) -> Result<Vec<T>, ShellError> {
+    values.iter()
+        .map(|value| match extract(value) {
+            Some(v) => v,
+            None => Err(type_mismatch(leaf, value)),
+        })
+        .collect()
+}
+
+fn write_column(writer: &mut ColumnWriter, leaf: &Leaf, rows: &[Value], tag: &Tag) -> Result<(), ShellError> {
+    let levels = leaf_levels(leaf, rows);
+    let def_levels = Some(levels.def_levels.as_slice());
+    let rep_levels = if leaf.repeated { Some(levels.rep_levels.as_slice()) } else { None };
+    let written = match writer {
+        ColumnWriter::BoolColumnWriter(w) => {
+            let values = leaf_values(leaf, &levels.values, |v| match &v.value {
+                UntaggedValue::Primitive(Primitive::Boolean(b)) => Some(Ok(*b)),
+                _ => None,
+            })?;
+            w.write_batch(&values, def_levels, rep_levels)
+        }
+        ColumnWriter::Int64ColumnWriter(w) => {
+            let values = leaf_values(leaf, &levels.values, |v| match &v.value {
+                UntaggedValue::Primitive(Primitive::Date(d)) if leaf.kind == ColumnKind::Date => {
+                    Some(Ok(d.timestamp() * 1_000_000 + i64::from(d.timestamp_subsec_micros())))
+                }
+                UntaggedValue::Primitive(Primitive::Int(i))
+                | UntaggedValue::Primitive(Primitive::Duration(i)) if leaf.kind == ColumnKind::Int => {
+                    Some(i.to_i64().ok_or_else(|| {
+                        ShellError::labeled_error(
+                            "Cannot convert integer to a signed 64-bit integer",
+                            i.to_string(),
+                            &v.tag,
+                        )
+                    }))
+                }
+                UntaggedValue::Primitive(Primitive::Filesize(size)) if leaf.kind == ColumnKind::Int => {
+                    Some(Ok(*size as i64))
+                }
+                _ => None,
+            })?;
+            w.write_batch(&values, def_levels, rep_levels)
+        }
+        ColumnWriter::DoubleColumnWriter(w) => {
+            let values = leaf_values(leaf, &levels.values, |v| match &v.value {
+                UntaggedValue::Primitive(Primitive::Decimal(d)) => Some(d.to_f64().ok_or_else(|| {
+                    ShellError::labeled_error(
+                        "Cannot convert decimal to a 64-bit float",
+                        d.to_string(),
+                        &v.tag,
+                    )
+                })),
+                _ => None,
+            })?;
+            w.write_batch(&values, def_levels, rep_levels)
+        }
+        ColumnWriter::ByteArrayColumnWriter(w) => {
+            let values = leaf_values(leaf, &levels.values, |v| match &v.value {
+                UntaggedValue::Primitive(Primitive::String(s)) if leaf.kind == ColumnKind::String => {
+                    Some(Ok(ByteArray::from(s.as_bytes().to_vec())))
+                }
+                UntaggedValue::Primitive(Primitive::Binary(b)) if leaf.kind == ColumnKind::Binary => {
+                    Some(Ok(ByteArray::from(b.clone())))
+                }
+                _ => None,
+            })?;
+            w.write_batch(&values, def_levels, rep_levels)
+        }
+        _ => unreachable!("Internal error: schema produced an unexpected column writer"),
+    };
+
+    written
+        .map(|_| ())
+        .map_err(|e| parquet_error("Failed to write Parquet column", e, tag))
+}
+
+/// Writes a table as a Parquet file, inferring its schema from the rows.
+pub fn to_parquet_bytes(rows: &[Value], tag: impl Into<Tag>, opts: &ToParquetOpts) -> Result<Vec<u8>, ShellError> {
+    write_parquet(rows, tag.into(), opts).map(|(bytes, _)| bytes)
+}
+
+/// The output of `to parquet`: the binary of the file, or with `column_index` set, a
+/// record of the binary and what its page indexes cost.
+pub fn to_parquet(rows: &[Value], tag: impl Into<Tag>, opts: &ToParquetOpts) -> Result<Value, ShellError> {
+    let tag = tag.into();
+    let (bytes, report) = write_parquet(rows, tag.clone(), opts)?;
+    let report = match report {
+        Some(report) => report,
+        None => return Ok(UntaggedValue::binary(bytes).into_value(tag)),
+    };
+    let overhead = report.index_bytes as f64 * 100.0 / bytes.len() as f64;
+    let mut dict = TaggedDictBuilder::new(&tag);
+    dict.insert_untagged("parquet", UntaggedValue::binary(bytes));
+    dict.insert_untagged("pages_indexed", UntaggedValue::int(report.pages));
+    dict.insert_untagged("index_size", UntaggedValue::filesize(report.index_bytes));
+    dict.insert_untagged("index_overhead_percent", UntaggedValue::decimal_from_float((overhead * 1000.0).round() / 1000.0, tag.span));
+    Ok(dict.into_value())
+}
+
+fn write_parquet(rows: &[Value], tag: Tag, opts: &ToParquetOpts) -> Result<(Vec<u8>, Option<PageIndexReport>), ShellError> {
+    if rows.is_empty() {
+        return Err(ShellError::labeled_error(
+            "Cannot write an empty table to Parquet",
+            "no rows were given",
+            &tag,
+        ));
+    }
+    opts.compression.check_level(&tag)?;
+    let (schema, columns) = infer_parquet_schema(rows)?;
+    let mut leaves = Vec::new();
+    for (name, shape) in &columns {
+        collect_leaves(shape, name.clone(), vec![Step::Field(name.clone())], false, &mut leaves);
+    }
+    let schema = Arc::new(schema);
+    let mut props = WriterProperties::builder()
+        .set_compression(opts.compression.codec())
+        .set_dictionary_enabled(opts.dictionary);
+    let descr = SchemaDescriptor::new(schema.clone());
+    for (name, enabled) in &opts.dictionary_columns {
+        let prefix = format!("{}.", name);
+        let mut found = false;
+        for column in descr.columns() {
+            let path = column.path().string();
+            if path == *name || path.starts_with(&prefix) {
+                props = props.set_column_dictionary_enabled(column.path().clone(), *enabled);
+                found = true;
+            }
+        }
+        if !found {
+            let available: Vec<String> = descr.columns().iter().map(|c| c.path().string()).collect();
+            return Err(ShellError::labeled_error(
+                format!("Unknown column '{}' in --dict", name),
+                format!("available columns are: {}", available.join(", ")),
+                &tag,
+            ));
+        }
+    }
+
+    let cursor = InMemoryWriteableCursor::default();
+    let mut writer = SerializedFileWriter::new(cursor.clone(), schema, Arc::new(props.build()))
+        .map_err(|e| parquet_error("Failed to create Parquet writer", e, &tag))?;
+    for group in row_groups(rows, opts.row_group_size.max(1)) {
+        let mut row_group = writer.next_row_group()
+            .map_err(|e| parquet_error("Failed to create Parquet row group", e, &tag))?;
+        for leaf in &leaves {
+            let mut column_writer = match row_group.next_column()
+                .map_err(|e| parquet_error("Failed to write Parquet column", e, &tag))? {
+                Some(column_writer) => column_writer,
+                None => unreachable!("Internal error: schema has fewer columns than inferred"),
+            };
+            write_column(&mut column_writer, leaf, group, &tag)?;
+            row_group.close_column(column_writer)
+                .map_err(|e| parquet_error("Failed to write Parquet column", e, &tag))?;
+        }
+        writer.close_row_group(row_group)
+            .map_err(|e| parquet_error("Failed to write Parquet row group", e, &tag))?;
+    }
+    writer.close()
+        .map_err(|e| parquet_error("Failed to finish Parquet file", e, &tag))?;
+
+    let mut bytes = cursor.data();
+    let mut report = None;
+    // Pages are rewritten first: their footer is written from the parquet 3.0 thrift
+    // definitions, which would drop the Bloom filter offsets.
+    if opts.column_index || opts.statistics != StatisticsLevel::Page {
+        let rewrite = PageRewrite {
+            page_statistics: opts.statistics == StatisticsLevel::Page,
+            chunk_statistics: opts.statistics != StatisticsLevel::None,
+            page_indexes: opts.column_index,
+        };
+        let (rewritten, written) = rewrite_pages(bytes, rewrite, &tag)?;
+        bytes = rewritten;
+        report = written;
+    }
+    if !opts.bloom_filter_columns.is_empty() {
+        bytes = write_bloom_filters(bytes, &opts.bloom_filter_columns, opts.bloom_filter_fpp, &tag)?;
+    }
+    Ok((bytes, report))
+}
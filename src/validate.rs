@@ -0,0 +1,188 @@
+use parquet::file::footer::parse_metadata;
+use parquet::file::metadata::{ColumnChunkMetaData, ParquetMetaData};
+use parquet::file::reader::ChunkReader;
+use parquet::file::serialized_reader::SliceableCursor;
+use parquet_format::PageHeader;
+use thrift::protocol::TCompactInputProtocol;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use std::io::{Cursor, Read};
+
+const MAGIC: &[u8] = b"PAR1";
+/// The footer ends with its 4-byte length followed by the magic.
+const FOOTER_TAIL: usize = 8;
+
+#[derive(Default)]
+struct Report {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+    crc_pages: usize,
+}
+
+impl Report {
+    fn into_value(self, row_count: i64, tag: &Tag) -> Value {
+        let strings = |messages: Vec<String>| {
+            UntaggedValue::Table(messages.into_iter()
+                .map(|m| UntaggedValue::string(m).into_value(tag))
+                .collect())
+        };
+        let mut dict = TaggedDictBuilder::new(tag);
+        dict.insert_untagged("valid", UntaggedValue::boolean(self.errors.is_empty()));
+        dict.insert_untagged("errors", strings(self.errors));
+        dict.insert_untagged("warnings", strings(self.warnings));
+        dict.insert_untagged("row_count", UntaggedValue::int(row_count));
+        dict.into_value()
+    }
+}
+
+fn column_name(column: &ColumnChunkMetaData) -> String {
+    column.column_path().string()
+}
+
+/// Walks the pages of a column chunk and compares the CRC of those that have one with
+/// the checksum of their compressed data.
+fn check_crcs(cursor: &SliceableCursor, start: u64, length: usize, location: &str, report: &mut Report) {
+    let mut chunk = Vec::with_capacity(length);
+    if let Err(e) = cursor.get_read(start, length).and_then(|mut r| r.read_to_end(&mut chunk).map_err(Into::into)) {
+        report.errors.push(format!("{} cannot be read: {}", location, e));
+        return;
+    }
+    let mut input = Cursor::new(chunk.as_slice());
+    let mut page = 0;
+    while (input.position() as usize) < chunk.len() {
+        let header = {
+            let mut protocol = TCompactInputProtocol::new(&mut input);
+            PageHeader::read_from_in_protocol(&mut protocol)
+        };
+        let header = match header {
+            Ok(header) => header,
+            Err(e) => {
+                report.errors.push(format!("{} page {} has an unreadable header: {}", location, page, e));
+                return;
+            }
+        };
+        let begin = input.position() as usize;
+        let end = begin + header.compressed_page_size.max(0) as usize;
+        if end > chunk.len() {
+            report.errors.push(format!("{} page {} runs past the end of its column chunk", location, page));
+            return;
+        }
+        if let Some(crc) = header.crc {
+            report.crc_pages += 1;
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(&chunk[begin..end]);
+            if hasher.finalize() != crc as u32 {
+                report.errors.push(format!("{} page {} fails its CRC check", location, page));
+            }
+        }
+        input.set_position(end as u64);
+        page += 1;
+    }
+}
+
+fn check_metadata(metadata: &ParquetMetaData, cursor: &SliceableCursor, data_end: u64, crcs: bool, report: &mut Report) {
+    let file_metadata = metadata.file_metadata();
+    let group_rows: i64 = metadata.row_groups().iter().map(|rg| rg.num_rows()).sum();
+    if group_rows != file_metadata.num_rows() {
+        report.errors.push(format!(
+            "row groups hold {} rows but the footer declares {}",
+            group_rows,
+            file_metadata.num_rows()
+        ));
+    }
+
+    let num_leaves = file_metadata.schema_descr().num_columns();
+    for (i, row_group) in metadata.row_groups().iter().enumerate() {
+        if row_group.num_columns() != num_leaves {
+            report.errors.push(format!(
+                "row group {} has {} column chunks for {} schema columns",
+                i,
+                row_group.num_columns(),
+                num_leaves
+            ));
+        }
+        for column in row_group.columns() {
+            let location = format!("row group {} column '{}'", i, column_name(column));
+            let start = column.dictionary_page_offset().unwrap_or_else(|| column.data_page_offset());
+            let end = start.checked_add(column.compressed_size());
+            match end {
+                Some(end) if start >= MAGIC.len() as i64 && end as u64 <= data_end && column.compressed_size() >= 0 => {
+                    if crcs {
+                        check_crcs(cursor, start as u64, column.compressed_size() as usize, &location, report);
+                    }
+                }
+                _ => report.errors.push(format!(
+                    "{} spans bytes {} to {}, outside the data section 4 to {}",
+                    location,
+                    start,
+                    start.saturating_add(column.compressed_size()),
+                    data_end
+                )),
+            }
+            if let Some(dictionary) = column.dictionary_page_offset() {
+                if dictionary > column.data_page_offset() {
+                    report.errors.push(format!("{} has its dictionary page after its data pages", location));
+                }
+            }
+            // Values of repeated columns can outnumber the rows, others hold one per row.
+            let repeated = column.column_descr().max_rep_level() > 0;
+            if !repeated && column.num_values() != row_group.num_rows()
+                || repeated && column.num_values() < row_group.num_rows()
+            {
+                report.errors.push(format!(
+                    "{} has {} values for {} rows",
+                    location,
+                    column.num_values(),
+                    row_group.num_rows()
+                ));
+            }
+            if column.statistics().is_none() {
+                report.warnings.push(format!("{} has no statistics", location));
+            }
+        }
+    }
+}
+
+/// Checks the structure of a Parquet file from its footer: the magic bytes, the row
+/// counts and the byte ranges of every column chunk, without decoding any rows.
+/// With `crcs` the page headers are read too, and every page carrying a CRC has it
+/// checked against its data.
+pub fn parquet_validate(bytes: Vec<u8>, tag: impl Into<Tag>, crcs: bool) -> Value {
+    let tag = tag.into();
+    let mut report = Report::default();
+    if bytes.len() < MAGIC.len() + FOOTER_TAIL {
+        report.errors.push(format!("file is {} bytes, too short for a Parquet file", bytes.len()));
+        return report.into_value(0, &tag);
+    }
+    if !bytes.starts_with(MAGIC) {
+        report.errors.push("file does not start with the PAR1 magic".to_string());
+    }
+    if !bytes.ends_with(MAGIC) {
+        report.errors.push("file does not end with the PAR1 magic".to_string());
+        return report.into_value(0, &tag);
+    }
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&bytes[bytes.len() - FOOTER_TAIL..bytes.len() - MAGIC.len()]);
+    let footer_len = u32::from_le_bytes(footer_len) as usize;
+    let data_end = match (bytes.len() - FOOTER_TAIL).checked_sub(footer_len) {
+        Some(data_end) if data_end >= MAGIC.len() => data_end as u64,
+        _ => {
+            report.errors.push(format!("footer length {} is larger than the file", footer_len));
+            return report.into_value(0, &tag);
+        }
+    };
+
+    let cursor = SliceableCursor::new(bytes);
+    let metadata = match parse_metadata(&cursor) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            report.errors.push(format!("footer cannot be read: {}", e));
+            return report.into_value(0, &tag);
+        }
+    };
+    check_metadata(&metadata, &cursor, data_end, crcs, &mut report);
+    if crcs && report.crc_pages == 0 {
+        report.warnings.push("no page has a CRC, there is nothing to check".to_string());
+    }
+    report.into_value(metadata.file_metadata().num_rows(), &tag)
+}
@@ -0,0 +1,48 @@
+// Every test file compiles its own copy of this module and none uses all of it.
+#![allow(dead_code)]
+
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use parquet_format::FileMetaData;
+use thrift::protocol::{TCompactInputProtocol, TCompactOutputProtocol};
+
+/// A record of the `fields` in order.
+pub fn record(fields: Vec<(&str, UntaggedValue)>) -> Value {
+    let mut dict = TaggedDictBuilder::new(Tag::unknown());
+    for (name, value) in fields {
+        dict.insert_untagged(name, value);
+    }
+    dict.into_value()
+}
+
+/// The value at the dotted `path` of nested records.
+pub fn get(value: &Value, path: &str) -> Value {
+    path.split('.').fold(value.clone(), |value, name| match value.value {
+        UntaggedValue::Row(dict) => dict.entries[name].clone(),
+        other => panic!("expected a record at '{}', got {:?}", name, other),
+    })
+}
+
+/// The values at the dotted `path` in every row of `table`.
+pub fn column(table: &Value, path: &str) -> Vec<UntaggedValue> {
+    match &table.value {
+        UntaggedValue::Table(rows) => rows.iter().map(|row| get(row, path).value).collect(),
+        other => panic!("expected a table, got {:?}", other),
+    }
+}
+
+/// Rewrites the footer of a file, for what the parquet 3.0 writer cannot write.
+pub fn rewrite_footer(bytes: Vec<u8>, rewrite: impl FnOnce(&mut FileMetaData)) -> Vec<u8> {
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+    let footer_start = bytes.len() - 8 - u32::from_le_bytes(footer_len) as usize;
+    let mut footer = FileMetaData::read_from_in_protocol(&mut TCompactInputProtocol::new(&bytes[footer_start..bytes.len() - 8])).unwrap();
+    rewrite(&mut footer);
+    let mut output = bytes[..footer_start].to_vec();
+    let mut written = Vec::new();
+    footer.write_to_out_protocol(&mut TCompactOutputProtocol::new(&mut written)).unwrap();
+    output.extend_from_slice(&written);
+    output.extend_from_slice(&(written.len() as u32).to_le_bytes());
+    output.extend_from_slice(b"PAR1");
+    output
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::{column, get, record};
+use nu_plugin_from_parquet::diff::parquet_diff;
+use nu_plugin_from_parquet::to_parquet::{to_parquet_bytes, ToParquetOpts};
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+
+fn person(id: i64, name: &str) -> Value {
+    record(vec![("id", UntaggedValue::int(id)), ("name", UntaggedValue::string(name))])
+}
+
+fn write(rows: &[Value]) -> Vec<u8> {
+    to_parquet_bytes(rows, Tag::unknown(), &ToParquetOpts::default()).unwrap()
+}
+
+fn strings(values: &[&str]) -> Vec<UntaggedValue> {
+    values.iter().map(|v| UntaggedValue::string(*v)).collect()
+}
+
+#[test]
+fn lists_schema_changes() {
+    let a = write(&[record(vec![
+        ("id", UntaggedValue::int(1)),
+        ("name", UntaggedValue::string("ada")),
+        ("age", UntaggedValue::int(36)),
+    ])]);
+    let b = write(&[record(vec![
+        ("id", UntaggedValue::int(1)),
+        ("name", UntaggedValue::int(7)),
+        ("email", UntaggedValue::string("ada@example.com")),
+    ])]);
+
+    let diff = parquet_diff(a, b, Tag::unknown(), None, 10).unwrap();
+
+    let changes = get(&diff, "schema_changes");
+    assert_eq!(column(&changes, "column"), strings(&["name", "age", "email"]));
+    assert_eq!(column(&changes, "change"), strings(&["modified", "removed", "added"]));
+    assert_eq!(column(&changes, "b")[1], UntaggedValue::nothing());
+    assert_eq!(column(&changes, "a")[2], UntaggedValue::nothing());
+    // Rows are compared on `id` and `name`, whose values differ in type.
+    assert_eq!(get(&diff, "data_diff.only_in_a_count").value, UntaggedValue::int(1));
+    assert_eq!(get(&diff, "data_diff.only_in_b_count").value, UntaggedValue::int(1));
+}
+
+#[test]
+fn matches_rows_by_key() {
+    let a = write(&[person(1, "ada"), person(2, "grace"), person(3, "edsger"), person(2, "duplicate")]);
+    let b = write(&[person(4, "bob"), person(3, "edsger"), person(2, "hopper")]);
+
+    let diff = parquet_diff(a, b, Tag::unknown(), Some("id"), 10).unwrap();
+
+    assert_eq!(get(&diff, "row_count_a").value, UntaggedValue::int(4));
+    assert_eq!(get(&diff, "row_count_b").value, UntaggedValue::int(3));
+    assert_eq!(get(&diff, "schema_changes").table_entries().count(), 0);
+    assert_eq!(get(&diff, "data_diff.only_in_a").table_entries().cloned().collect::<Vec<_>>(), vec![person(1, "ada")]);
+    assert_eq!(get(&diff, "data_diff.only_in_b").table_entries().cloned().collect::<Vec<_>>(), vec![person(4, "bob")]);
+    let changed = get(&diff, "data_diff.changed");
+    assert_eq!(column(&changed, "key"), vec![UntaggedValue::int(2)]);
+    assert_eq!(column(&changed, "columns"), vec![UntaggedValue::Table(vec![UntaggedValue::string("name").into_value(Tag::unknown())])]);
+    assert_eq!(column(&changed, "a.name"), strings(&["grace"]));
+    assert_eq!(column(&changed, "b.name"), strings(&["hopper"]));
+}
+
+#[test]
+fn matches_whole_rows_counting_duplicates() {
+    let a = write(&[person(1, "ada"), person(1, "ada"), person(2, "grace")]);
+    let b = write(&[person(1, "ada"), person(3, "bob"), person(3, "bob")]);
+
+    let diff = parquet_diff(a, b, Tag::unknown(), None, 1).unwrap();
+
+    assert_eq!(get(&diff, "data_diff.only_in_a_count").value, UntaggedValue::int(2));
+    assert_eq!(get(&diff, "data_diff.only_in_b_count").value, UntaggedValue::int(2));
+    assert_eq!(get(&diff, "data_diff.only_in_a").table_entries().cloned().collect::<Vec<_>>(), vec![person(1, "ada")]);
+    assert_eq!(get(&diff, "data_diff.only_in_b").table_entries().cloned().collect::<Vec<_>>(), vec![person(3, "bob")]);
+    assert_eq!(get(&diff, "data_diff.changed_count").value, UntaggedValue::int(0));
+}
+
+#[test]
+fn refuses_keys_missing_from_either_file() {
+    let a = write(&[person(1, "ada")]);
+    let b = write(&[record(vec![("id", UntaggedValue::int(1))])]);
+
+    let error = parquet_diff(a, b, Tag::unknown(), Some("name"), 10).unwrap_err();
+    assert!(format!("{:?}", error).contains("Unknown key column 'name'"), "{:?}", error);
+}
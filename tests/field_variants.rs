@@ -0,0 +1,369 @@
+mod common;
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use bigdecimal::{BigDecimal, FromPrimitive};
+use chrono::DateTime;
+use common::{get, rewrite_footer};
+use indexmap::IndexMap;
+use nu_plugin_from_parquet::from_parquet::{from_parquet_bytes, FromParquetOpts};
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::{ByteArray, FixedLenByteArray};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{FileWriter, InMemoryWriteableCursor, SerializedFileWriter};
+use parquet::schema::parser::parse_message_type;
+use parquet_format::{ConvertedType, LogicalType, MilliSeconds, TimeUnit, TimestampType, UUIDType};
+
+/// The values of one leaf column, in the physical type of its schema node.
+enum Values {
+    Bool(Vec<bool>),
+    Int32(Vec<i32>),
+    Int64(Vec<i64>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+    Bytes(Vec<&'static [u8]>),
+}
+
+/// A leaf column to write, with its definition and repetition levels when it is
+/// optional or repeated.
+struct Leaf {
+    values: Values,
+    def_levels: Option<Vec<i16>>,
+    rep_levels: Option<Vec<i16>>,
+}
+
+fn required(values: Values) -> Leaf {
+    Leaf { values, def_levels: None, rep_levels: None }
+}
+
+/// Writes a single row group file with the leaf columns in schema order.
+fn write(schema: &str, leaves: Vec<Leaf>) -> Vec<u8> {
+    let schema = Arc::new(parse_message_type(schema).unwrap());
+    let props = Arc::new(WriterProperties::builder().build());
+    let cursor = InMemoryWriteableCursor::default();
+    let mut writer = SerializedFileWriter::new(cursor.clone(), schema, props).unwrap();
+    let mut row_group = writer.next_row_group().unwrap();
+    for leaf in leaves {
+        let mut column = row_group.next_column().unwrap().unwrap();
+        let (def, rep) = (leaf.def_levels.as_deref(), leaf.rep_levels.as_deref());
+        match (&mut column, leaf.values) {
+            (ColumnWriter::BoolColumnWriter(w), Values::Bool(v)) => {
+                w.write_batch(&v, def, rep).unwrap();
+            }
+            (ColumnWriter::Int32ColumnWriter(w), Values::Int32(v)) => {
+                w.write_batch(&v, def, rep).unwrap();
+            }
+            (ColumnWriter::Int64ColumnWriter(w), Values::Int64(v)) => {
+                w.write_batch(&v, def, rep).unwrap();
+            }
+            (ColumnWriter::FloatColumnWriter(w), Values::Float(v)) => {
+                w.write_batch(&v, def, rep).unwrap();
+            }
+            (ColumnWriter::DoubleColumnWriter(w), Values::Double(v)) => {
+                w.write_batch(&v, def, rep).unwrap();
+            }
+            (ColumnWriter::ByteArrayColumnWriter(w), Values::Bytes(v)) => {
+                let v: Vec<ByteArray> = v.into_iter().map(|b| ByteArray::from(b.to_vec())).collect();
+                w.write_batch(&v, def, rep).unwrap();
+            }
+            (ColumnWriter::FixedLenByteArrayColumnWriter(w), Values::Bytes(v)) => {
+                let v: Vec<FixedLenByteArray> = v.into_iter().map(|b| ByteArray::from(b.to_vec()).into()).collect();
+                w.write_batch(&v, def, rep).unwrap();
+            }
+            _ => panic!("values do not match the column type"),
+        }
+        row_group.close_column(column).unwrap();
+    }
+    writer.close_row_group(row_group).unwrap();
+    writer.close().unwrap();
+    cursor.data()
+}
+
+/// Reads a file holding a single column and returns the values of every row.
+fn read_column(bytes: Vec<u8>, opts: &FromParquetOpts) -> Vec<UntaggedValue> {
+    let table = from_parquet_bytes(bytes, Tag::unknown(), opts).unwrap();
+    match table.value {
+        UntaggedValue::Table(rows) => rows.into_iter()
+            .map(|row| match row.value {
+                UntaggedValue::Row(dict) => dict.entries.into_iter().next().unwrap().1.value,
+                other => panic!("expected a row, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected a table, got {:?}", other),
+    }
+}
+
+fn read_single(schema: &str, values: Values) -> Vec<UntaggedValue> {
+    read_column(write(schema, vec![required(values)]), &FromParquetOpts::default())
+}
+
+fn value(untagged: UntaggedValue) -> Value {
+    untagged.into_value(Tag::unknown())
+}
+
+fn date(s: &str) -> UntaggedValue {
+    UntaggedValue::date(DateTime::parse_from_rfc3339(s).unwrap())
+}
+
+#[test]
+fn reads_null() {
+    let bytes = write(
+        "message schema { optional int32 value; }",
+        vec![Leaf { values: Values::Int32(vec![]), def_levels: Some(vec![0]), rep_levels: None }],
+    );
+    assert_eq!(read_column(bytes, &FromParquetOpts::default()), vec![UntaggedValue::nothing()]);
+}
+
+#[test]
+fn reads_bool() {
+    assert_eq!(
+        read_single("message schema { required boolean value; }", Values::Bool(vec![true, false])),
+        vec![UntaggedValue::boolean(true), UntaggedValue::boolean(false)]
+    );
+}
+
+#[test]
+fn reads_byte_and_ubyte() {
+    assert_eq!(
+        read_single("message schema { required int32 value (INT_8); }", Values::Int32(vec![-128])),
+        vec![UntaggedValue::int(-128)]
+    );
+    assert_eq!(
+        read_single("message schema { required int32 value (UINT_8); }", Values::Int32(vec![255])),
+        vec![UntaggedValue::int(255)]
+    );
+}
+
+#[test]
+fn reads_short_and_ushort() {
+    assert_eq!(
+        read_single("message schema { required int32 value (INT_16); }", Values::Int32(vec![-32768])),
+        vec![UntaggedValue::int(-32768)]
+    );
+    assert_eq!(
+        read_single("message schema { required int32 value (UINT_16); }", Values::Int32(vec![65535])),
+        vec![UntaggedValue::int(65535)]
+    );
+}
+
+#[test]
+fn reads_int_and_uint() {
+    assert_eq!(
+        read_single("message schema { required int32 value; }", Values::Int32(vec![i32::MIN])),
+        vec![UntaggedValue::int(i32::MIN)]
+    );
+    // Unsigned values are stored in the bits of their signed counterpart.
+    assert_eq!(
+        read_single("message schema { required int32 value (UINT_32); }", Values::Int32(vec![-1])),
+        vec![UntaggedValue::int(u32::MAX)]
+    );
+}
+
+#[test]
+fn reads_long_and_ulong() {
+    assert_eq!(
+        read_single("message schema { required int64 value; }", Values::Int64(vec![i64::MIN])),
+        vec![UntaggedValue::int(i64::MIN)]
+    );
+    assert_eq!(
+        read_single("message schema { required int64 value (UINT_64); }", Values::Int64(vec![-1])),
+        vec![UntaggedValue::int(u64::MAX)]
+    );
+}
+
+#[test]
+fn reads_float_and_double() {
+    assert_eq!(
+        read_single("message schema { required float value; }", Values::Float(vec![1.5])),
+        vec![UntaggedValue::decimal(BigDecimal::from_f32(1.5).unwrap())]
+    );
+    assert_eq!(
+        read_single("message schema { required double value; }", Values::Double(vec![-0.25])),
+        vec![UntaggedValue::decimal(BigDecimal::from_f64(-0.25).unwrap())]
+    );
+}
+
+#[test]
+fn reads_str_and_bytes() {
+    assert_eq!(
+        read_single("message schema { required binary value (UTF8); }", Values::Bytes(vec![b"hello"])),
+        vec![UntaggedValue::string("hello")]
+    );
+    assert_eq!(
+        read_single("message schema { required binary value; }", Values::Bytes(vec![b"\x00\xff"])),
+        vec![UntaggedValue::binary(vec![0, 255])]
+    );
+}
+
+#[test]
+fn reads_date() {
+    assert_eq!(
+        read_single("message schema { required int32 value (DATE); }", Values::Int32(vec![18_000, -1])),
+        vec![date("2019-04-14T00:00:00+00:00"), date("1969-12-31T00:00:00+00:00")]
+    );
+}
+
+#[test]
+fn reads_timestamp_millis_and_micros() {
+    assert_eq!(
+        read_single(
+            "message schema { required int64 value (TIMESTAMP_MILLIS); }",
+            Values::Int64(vec![1_612_325_106_123])
+        ),
+        vec![date("2021-02-03T04:05:06.123+00:00")]
+    );
+    assert_eq!(
+        read_single(
+            "message schema { required int64 value (TIMESTAMP_MICROS); }",
+            Values::Int64(vec![1_612_325_106_123_456])
+        ),
+        vec![date("2021-02-03T04:05:06.123456+00:00")]
+    );
+}
+
+#[test]
+fn reads_decimal() {
+    assert_eq!(
+        read_single("message schema { required int32 value (DECIMAL(9,2)); }", Values::Int32(vec![12_345, -5])),
+        vec![
+            UntaggedValue::decimal(BigDecimal::from_str("123.45").unwrap()),
+            UntaggedValue::decimal(BigDecimal::from_str("-0.05").unwrap()),
+        ]
+    );
+}
+
+#[test]
+fn reads_group() {
+    let bytes = write(
+        "message schema { required group value { required int32 a; required binary b (UTF8); } }",
+        vec![required(Values::Int32(vec![1])), required(Values::Bytes(vec![b"x"]))],
+    );
+    let mut record = IndexMap::new();
+    record.insert("a".to_string(), value(UntaggedValue::int(1)));
+    record.insert("b".to_string(), value(UntaggedValue::string("x")));
+    assert_eq!(read_column(bytes, &FromParquetOpts::default()), vec![UntaggedValue::row(record)]);
+}
+
+#[test]
+fn reads_list() {
+    let schema = "message schema { optional group value (LIST) { repeated group list { required int32 element; } } }";
+    // The rows are [1, 2], [] and null.
+    let bytes = write(schema, vec![Leaf {
+        values: Values::Int32(vec![1, 2]),
+        def_levels: Some(vec![2, 2, 1, 0]),
+        rep_levels: Some(vec![0, 1, 0, 0]),
+    }]);
+    assert_eq!(
+        read_column(bytes, &FromParquetOpts::default()),
+        vec![
+            UntaggedValue::Table(vec![value(UntaggedValue::int(1)), value(UntaggedValue::int(2))]),
+            UntaggedValue::Table(vec![]),
+            UntaggedValue::nothing(),
+        ]
+    );
+}
+
+const MAP_SCHEMA: &str = "message schema {
+    optional group value (MAP) {
+        repeated group key_value { required binary key (UTF8); optional int32 value; }
+    }
+}";
+
+fn map_file() -> Vec<u8> {
+    // The row is {a: 1, b: null}.
+    write(MAP_SCHEMA, vec![
+        Leaf { values: Values::Bytes(vec![b"a", b"b"]), def_levels: Some(vec![2, 2]), rep_levels: Some(vec![0, 1]) },
+        Leaf { values: Values::Int32(vec![1]), def_levels: Some(vec![3, 2]), rep_levels: Some(vec![0, 1]) },
+    ])
+}
+
+#[test]
+fn reads_map_as_pairs() {
+    let pair = |key: &str, v: UntaggedValue| value(UntaggedValue::Table(vec![value(UntaggedValue::string(key)), value(v)]));
+    assert_eq!(
+        read_column(map_file(), &FromParquetOpts::default()),
+        vec![UntaggedValue::Table(vec![pair("a", UntaggedValue::int(1)), pair("b", UntaggedValue::nothing())])]
+    );
+}
+
+#[test]
+fn reads_map_as_record() {
+    let opts = FromParquetOpts { map_as_record: true, ..FromParquetOpts::default() };
+    let mut record = IndexMap::new();
+    record.insert("a".to_string(), value(UntaggedValue::int(1)));
+    record.insert("b".to_string(), value(UntaggedValue::nothing()));
+    assert_eq!(read_column(map_file(), &opts), vec![UntaggedValue::row(record)]);
+}
+
+#[test]
+fn fails_on_timestamps_out_of_range() {
+    let bytes = write("message schema { required int64 value (TIMESTAMP_MILLIS); }", vec![required(Values::Int64(vec![0, i64::MAX]))]);
+
+    let error = from_parquet_bytes(bytes.clone(), Tag::unknown(), &FromParquetOpts::default()).unwrap_err();
+    assert!(format!("{:?}", error).contains("Timestamp is out of range"), "{:?}", error);
+
+    let opts = FromParquetOpts { errors_as_rows: true, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+    let rows: Vec<Value> = table.table_entries().cloned().collect();
+    assert_eq!(get(&rows[0], "value").value, date("1970-01-01T00:00:00+00:00"));
+    assert_eq!(get(&rows[1], "_error").value, UntaggedValue::boolean(true));
+    let message = get(&rows[1], "_error_message").as_string().unwrap();
+    assert!(message.starts_with("Timestamp is out of range"), "{}", message);
+}
+
+/// Gives every TIMESTAMP_MILLIS column the TIMESTAMP logical type, which tells whether
+/// it is adjusted to UTC and which the parquet 3.0 writer cannot write.
+fn annotate_timestamps(bytes: Vec<u8>, adjusted_to_utc: bool) -> Vec<u8> {
+    rewrite_footer(bytes, |footer| {
+        for element in footer.schema.iter_mut().filter(|element| element.converted_type == Some(ConvertedType::TimestampMillis)) {
+            let unit = TimeUnit::MILLIS(MilliSeconds {});
+            element.logical_type = Some(LogicalType::TIMESTAMP(TimestampType { is_adjusted_to_u_t_c: adjusted_to_utc, unit }));
+        }
+    })
+}
+
+#[test]
+fn reads_timestamps_not_adjusted_to_utc_in_the_timezone() {
+    let schema = "message schema { required int64 value (TIMESTAMP_MILLIS); }";
+    let file = || write(schema, vec![required(Values::Int64(vec![1_612_325_106_123, 1_625_285_106_000]))]);
+    let opts = FromParquetOpts { timezone: Some(chrono_tz::America::New_York), ..FromParquetOpts::default() };
+
+    assert_eq!(
+        read_column(annotate_timestamps(file(), false), &opts),
+        vec![date("2021-02-03T04:05:06.123-05:00"), date("2021-07-03T04:05:06-04:00")]
+    );
+    let utc = vec![date("2021-02-03T04:05:06.123+00:00"), date("2021-07-03T04:05:06+00:00")];
+    assert_eq!(read_column(annotate_timestamps(file(), false), &FromParquetOpts::default()), utc);
+    assert_eq!(read_column(annotate_timestamps(file(), true), &opts), utc);
+    // Without the logical type, the converted type says the column is adjusted to UTC.
+    assert_eq!(read_column(file(), &opts), utc);
+}
+
+/// Gives the top-level column `name` the UUID logical type, which the parquet 3.0 writer
+/// cannot write, by rewriting the footer of a file.
+fn annotate_uuid(bytes: Vec<u8>, name: &str) -> Vec<u8> {
+    rewrite_footer(bytes, |footer| {
+        for element in footer.schema.iter_mut().filter(|element| element.name == name) {
+            element.logical_type = Some(LogicalType::UUID(UUIDType {}));
+        }
+    })
+}
+
+#[test]
+fn reads_uuid_columns_as_strings() {
+    let uuid: &'static [u8] = &[0x55, 0x0e, 0x84, 0x00, 0xe2, 0x9b, 0x41, 0xd4, 0xa7, 0x16, 0x44, 0x66, 0x55, 0x44, 0x00, 0x00];
+    let schema = "message schema { required fixed_len_byte_array(16) id; }";
+    let bytes = annotate_uuid(write(schema, vec![required(Values::Bytes(vec![uuid]))]), "id");
+
+    let text = UntaggedValue::string("550e8400-e29b-41d4-a716-446655440000");
+    assert_eq!(read_column(bytes.clone(), &FromParquetOpts::default()), vec![text.clone()]);
+
+    let opts = FromParquetOpts { no_decode_uuid: true, ..FromParquetOpts::default() };
+    assert_eq!(read_column(bytes.clone(), &opts), vec![UntaggedValue::binary(uuid.to_vec())]);
+
+    let opts = FromParquetOpts { columns: Some(vec!["id".to_string()]), ..FromParquetOpts::default() };
+    assert_eq!(read_column(bytes, &opts), vec![text]);
+}
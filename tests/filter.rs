@@ -0,0 +1,218 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::rewrite_footer;
+use nu_plugin_from_parquet::filter::{FilterOp, Literal, Predicate};
+use nu_plugin_from_parquet::from_parquet::{from_parquet_bytes, FromParquetOpts};
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+use parquet::column::writer::ColumnWriter;
+use parquet::data_type::ByteArray;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{FileWriter, InMemoryWriteableCursor, SerializedFileWriter};
+use parquet::schema::parser::parse_message_type;
+
+/// The values of one leaf column of a row group, with its definition levels when it
+/// is optional.
+enum Values {
+    Int32(Vec<i32>, Option<Vec<i16>>),
+    Str(Vec<&'static str>),
+}
+
+/// Writes a file with a row group per entry of `row_groups`, each holding the leaf
+/// columns in schema order.
+fn write(schema: &str, row_groups: Vec<Vec<Values>>) -> Vec<u8> {
+    let schema = Arc::new(parse_message_type(schema).unwrap());
+    let props = Arc::new(WriterProperties::builder().build());
+    let cursor = InMemoryWriteableCursor::default();
+    let mut writer = SerializedFileWriter::new(cursor.clone(), schema, props).unwrap();
+    for columns in row_groups {
+        let mut row_group = writer.next_row_group().unwrap();
+        for values in columns {
+            let mut column = row_group.next_column().unwrap().unwrap();
+            match (&mut column, values) {
+                (ColumnWriter::Int32ColumnWriter(w), Values::Int32(v, def)) => {
+                    w.write_batch(&v, def.as_deref(), None).unwrap();
+                }
+                (ColumnWriter::ByteArrayColumnWriter(w), Values::Str(v)) => {
+                    let v: Vec<ByteArray> = v.into_iter().map(ByteArray::from).collect();
+                    w.write_batch(&v, None, None).unwrap();
+                }
+                _ => panic!("values do not match the column type"),
+            }
+            row_group.close_column(column).unwrap();
+        }
+        writer.close_row_group(row_group).unwrap();
+    }
+    writer.close().unwrap();
+    cursor.data()
+}
+
+fn filtered(bytes: &[u8], filter: &str) -> Vec<UntaggedValue> {
+    let opts = FromParquetOpts { filter: Some(Predicate::parse(filter).unwrap()), ..FromParquetOpts::default() };
+    match from_parquet_bytes(bytes.to_vec(), Tag::unknown(), &opts).unwrap().value {
+        UntaggedValue::Table(rows) => rows.into_iter()
+            .map(|row| match row.value {
+                UntaggedValue::Row(dict) => dict.entries["a"].value.clone(),
+                other => panic!("expected a row, got {:?}", other),
+            })
+            .collect(),
+        other => panic!("expected a table, got {:?}", other),
+    }
+}
+
+/// The `--explain` line counting the row groups a filter leaves to read.
+fn row_groups_read(bytes: &[u8], filter: &str) -> String {
+    let opts = FromParquetOpts {
+        filter: Some(Predicate::parse(filter).unwrap()),
+        explain: true,
+        ..FromParquetOpts::default()
+    };
+    let plan = from_parquet_bytes(bytes.to_vec(), Tag::unknown(), &opts).unwrap();
+    let plan = match plan.value {
+        UntaggedValue::Primitive(_) => plan.as_string().unwrap(),
+        other => panic!("expected a string, got {:?}", other),
+    };
+    plan.lines().find(|line| line.starts_with("row groups:")).unwrap().to_string()
+}
+
+fn ints(values: &[i32]) -> Vec<UntaggedValue> {
+    values.iter().map(|v| UntaggedValue::int(*v)).collect()
+}
+
+/// Two row groups of `a`, 1 to 3 then 10 to 12.
+fn two_row_groups() -> Vec<u8> {
+    write(
+        "message schema { required int32 a; }",
+        vec![vec![Values::Int32(vec![1, 2, 3], None)], vec![Values::Int32(vec![10, 11, 12], None)]],
+    )
+}
+
+/// Two row groups of the optional `a`, (1, null, 3) then (10, 11, 12).
+fn nullable_row_groups() -> Vec<u8> {
+    write(
+        "message schema { optional int32 a; }",
+        vec![
+            vec![Values::Int32(vec![1, 3], Some(vec![1, 0, 1]))],
+            vec![Values::Int32(vec![10, 11, 12], Some(vec![1, 1, 1]))],
+        ],
+    )
+}
+
+#[test]
+fn parses_predicates() {
+    let predicate = Predicate::parse("  age ge 30 ").unwrap();
+    assert_eq!(predicate, Predicate { column: "age".to_string(), op: FilterOp::Ge, literal: Some(Literal::Int(30)) });
+    assert_eq!(Predicate::parse("name IS_NULL").unwrap().op, FilterOp::IsNull);
+    assert_eq!(Predicate::parse("name is_null").unwrap().literal, None);
+    assert_eq!(Predicate::parse("name eq New York").unwrap().literal, Some(Literal::Str("New York".to_string())));
+}
+
+#[test]
+fn parses_literals_by_their_text() {
+    let literal = |text: &str| Predicate::parse(&format!("a eq {}", text)).unwrap().literal.unwrap();
+    assert_eq!(literal("42"), Literal::Int(42));
+    assert_eq!(literal("-7"), Literal::Int(-7));
+    assert_eq!(literal("1.5"), Literal::Float(1.5));
+    assert_eq!(literal("1e3"), Literal::Float(1000.0));
+    assert_eq!(literal("true"), Literal::Bool(true));
+    assert_eq!(literal("\"42\""), Literal::Str("42".to_string()));
+    assert_eq!(literal("'a b'"), Literal::Str("a b".to_string()));
+    assert_eq!(literal("inf"), Literal::Str("inf".to_string()));
+    assert_eq!(literal("abc"), Literal::Str("abc".to_string()));
+}
+
+#[test]
+fn rejects_malformed_predicates() {
+    assert_eq!(Predicate::parse("   "), Err("expected <column> <op> <value>".to_string()));
+    assert_eq!(Predicate::parse("age"), Err("expected an operator after the column".to_string()));
+    assert!(Predicate::parse("age between 1").unwrap_err().starts_with("unknown operator 'between'"));
+    assert_eq!(Predicate::parse("age gt"), Err("'gt' needs a value to compare with".to_string()));
+    assert_eq!(Predicate::parse("age is_null 1"), Err("'is_null' does not take a value".to_string()));
+}
+
+#[test]
+fn checks_the_column_and_literal_against_the_schema() {
+    let bytes = two_row_groups();
+    for filter in &["b eq 1", "a eq 'x'", "a eq true"] {
+        let opts = FromParquetOpts { filter: Some(Predicate::parse(filter).unwrap()), ..FromParquetOpts::default() };
+        assert!(from_parquet_bytes(bytes.clone(), Tag::unknown(), &opts).is_err(), "{}", filter);
+    }
+}
+
+#[test]
+fn applies_every_operator_to_rows() {
+    let bytes = nullable_row_groups();
+    assert_eq!(filtered(&bytes, "a eq 3"), ints(&[3]));
+    assert_eq!(filtered(&bytes, "a ne 3"), ints(&[1, 10, 11, 12]));
+    assert_eq!(filtered(&bytes, "a lt 10"), ints(&[1, 3]));
+    assert_eq!(filtered(&bytes, "a le 10"), ints(&[1, 3, 10]));
+    assert_eq!(filtered(&bytes, "a gt 10"), ints(&[11, 12]));
+    assert_eq!(filtered(&bytes, "a ge 10.5"), ints(&[11, 12]));
+    assert_eq!(filtered(&bytes, "a is_null"), vec![UntaggedValue::nothing()]);
+    assert_eq!(filtered(&bytes, "a not_null"), ints(&[1, 3, 10, 11, 12]));
+}
+
+#[test]
+fn compares_strings_with_quoted_literals() {
+    let bytes = write(
+        "message schema { required binary a (UTF8); }",
+        vec![vec![Values::Str(vec!["42", "apple", "pear"])]],
+    );
+    let strings = |values: &[&str]| -> Vec<UntaggedValue> { values.iter().map(|v| UntaggedValue::string(*v)).collect() };
+    assert_eq!(filtered(&bytes, "a eq \"42\""), strings(&["42"]));
+    assert_eq!(filtered(&bytes, "a gt apple"), strings(&["pear"]));
+}
+
+#[test]
+fn prunes_row_groups_by_their_statistics() {
+    let bytes = two_row_groups();
+    assert_eq!(row_groups_read(&bytes, "a gt 5"), "row groups: 1 of 2 read, 1 skipped by statistics");
+    assert_eq!(row_groups_read(&bytes, "a lt 5"), "row groups: 1 of 2 read, 1 skipped by statistics");
+    assert_eq!(row_groups_read(&bytes, "a eq 11"), "row groups: 1 of 2 read, 1 skipped by statistics");
+    assert_eq!(row_groups_read(&bytes, "a ge 3"), "row groups: 2 of 2 read, 0 skipped by statistics");
+    assert_eq!(row_groups_read(&bytes, "a gt 12"), "row groups: 0 of 2 read, 2 skipped by statistics");
+    assert_eq!(row_groups_read(&bytes, "a not_null"), "row groups: 2 of 2 read, 0 skipped by statistics");
+    assert_eq!(filtered(&bytes, "a eq 11"), ints(&[11]));
+}
+
+/// Sets the null count of every column chunk, `None` leaves it unwritten.
+fn with_null_counts(bytes: Vec<u8>, null_counts: &[Option<i64>]) -> Vec<u8> {
+    rewrite_footer(bytes, |footer| {
+        for (row_group, null_count) in footer.row_groups.iter_mut().zip(null_counts) {
+            for column in &mut row_group.columns {
+                column.meta_data.as_mut().unwrap().statistics.as_mut().unwrap().null_count = *null_count;
+            }
+        }
+    })
+}
+
+#[test]
+fn prunes_row_groups_without_nulls_for_is_null() {
+    let bytes = with_null_counts(nullable_row_groups(), &[Some(1), Some(0)]);
+    assert_eq!(row_groups_read(&bytes, "a is_null"), "row groups: 1 of 2 read, 1 skipped by statistics");
+    assert_eq!(filtered(&bytes, "a is_null"), vec![UntaggedValue::nothing()]);
+}
+
+#[test]
+fn keeps_row_groups_whose_null_count_was_not_written() {
+    // parquet 3.0 leaves a null count of 0 out of the statistics it writes.
+    let bytes = with_null_counts(nullable_row_groups(), &[None, None]);
+    assert_eq!(row_groups_read(&bytes, "a is_null"), "row groups: 2 of 2 read, 0 skipped by statistics");
+    assert_eq!(filtered(&bytes, "a is_null"), vec![UntaggedValue::nothing()]);
+}
+
+#[test]
+fn stream_prunes_like_the_table() {
+    let bytes = two_row_groups();
+    let opts = FromParquetOpts { filter: Some(Predicate::parse("a gt 10").unwrap()), ..FromParquetOpts::default() };
+    let rows: Vec<Value> = nu_plugin_from_parquet::from_parquet_stream(bytes.clone(), Tag::unknown(), &opts)
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+    assert_eq!(table.table_entries().cloned().collect::<Vec<_>>().len(), 2);
+    assert_eq!(rows.len(), 2);
+}
+
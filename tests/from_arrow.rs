@@ -0,0 +1,84 @@
+mod common;
+
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayData, ArrayRef, DictionaryArray, Int64Array, StringArray};
+use arrow::buffer::Buffer;
+use arrow::datatypes::{DataType, Field, Int8Type, Schema};
+use arrow::ipc::writer::{FileWriter, StreamWriter};
+use arrow::record_batch::RecordBatch;
+use common::record;
+use nu_plugin_from_parquet::from_arrow::from_arrow_bytes;
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+
+fn ipc_file(batch: &RecordBatch) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    {
+        let mut writer = FileWriter::try_new(&mut bytes, &batch.schema()).unwrap();
+        writer.write(batch).unwrap();
+        writer.finish().unwrap();
+    }
+    bytes
+}
+
+fn ipc_stream(batch: &RecordBatch) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    {
+        let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema()).unwrap();
+        writer.write(batch).unwrap();
+        writer.finish().unwrap();
+    }
+    bytes
+}
+
+/// A dictionary of `values` indexed by the raw `keys`, which arrow does not check.
+fn dictionary(keys: &[i8], values: &[&str]) -> ArrayRef {
+    let values: StringArray = values.iter().map(|v| Some(*v)).collect();
+    let data = ArrayData::builder(DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8)))
+        .len(keys.len())
+        .add_buffer(Buffer::from(keys.iter().map(|k| *k as u8).collect::<Vec<u8>>()))
+        .add_child_data(values.data())
+        .build();
+    Arc::new(DictionaryArray::<Int8Type>::from(data))
+}
+
+fn batch(columns: Vec<(&str, ArrayRef)>) -> RecordBatch {
+    let fields = columns.iter().map(|(name, array)| Field::new(name, array.data_type().clone(), true)).collect();
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns.into_iter().map(|(_, array)| array).collect()).unwrap()
+}
+
+fn row(id: i64, name: Option<&str>, color: &str) -> Value {
+    record(vec![
+        ("id", UntaggedValue::int(id)),
+        ("name", name.map_or_else(UntaggedValue::nothing, UntaggedValue::string)),
+        ("color", UntaggedValue::string(color)),
+    ])
+}
+
+#[test]
+fn reads_ipc_files_and_streams() {
+    let batch = batch(vec![
+        ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+        ("name", Arc::new(StringArray::from(vec![Some("ada"), None, Some("grace")]))),
+        ("color", dictionary(&[1, 0, 1], &["red", "blue"])),
+    ]);
+    let expected = vec![row(1, Some("ada"), "blue"), row(2, None, "red"), row(3, Some("grace"), "blue")];
+
+    for bytes in [ipc_file(&batch), ipc_stream(&batch)] {
+        let table = from_arrow_bytes(bytes, Tag::unknown()).unwrap();
+        assert_eq!(table.table_entries().cloned().collect::<Vec<_>>(), expected);
+    }
+}
+
+#[test]
+fn refuses_dictionary_keys_outside_the_dictionary() {
+    for keys in &[[0, -1], [0, 2]] {
+        let batch = batch(vec![("color", dictionary(keys, &["red", "blue"]))]);
+
+        let error = from_arrow_bytes(ipc_file(&batch), Tag::unknown()).unwrap_err();
+        let error = format!("{:?}", error);
+        assert!(error.contains("Invalid dictionary key in Arrow column 'color'"), "{}", error);
+        assert!(error.contains(&format!("key {} in row 1 is outside the dictionary of 2 values", keys[1])), "{}", error);
+    }
+}
@@ -0,0 +1,340 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::column;
+use nu_plugin_from_parquet::group::{AggOp, Aggregation};
+use nu_plugin_from_parquet::from_parquet::{
+    from_parquet_bytes, BinaryRepresentation, FromParquetOpts, NullRepresentation, TimeRepresentation,
+};
+use nu_protocol::{Primitive, UntaggedValue, Value};
+use nu_source::Tag;
+use parquet::column::writer::ColumnWriter;
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{FileWriter, InMemoryWriteableCursor, SerializedFileWriter};
+use parquet::schema::parser::parse_message_type;
+
+/// Writes a single row group file with one required INT32-backed column per schema field.
+fn write_int32_columns(schema: &str, columns: &[&[i32]]) -> Vec<u8> {
+    let schema = Arc::new(parse_message_type(schema).unwrap());
+    let props = Arc::new(WriterProperties::builder().build());
+    let cursor = InMemoryWriteableCursor::default();
+    let mut writer = SerializedFileWriter::new(cursor.clone(), schema, props).unwrap();
+    let mut row_group = writer.next_row_group().unwrap();
+    for values in columns {
+        let mut column = row_group.next_column().unwrap().unwrap();
+        match &mut column {
+            ColumnWriter::Int32ColumnWriter(w) => {
+                w.write_batch(values, None, None).unwrap();
+            }
+            _ => panic!("expected an INT32 column"),
+        }
+        row_group.close_column(column).unwrap();
+    }
+    writer.close_row_group(row_group).unwrap();
+    writer.close().unwrap();
+    cursor.data()
+}
+
+const BYTES_SCHEMA: &str = "message schema { required int32 signed (INT_8); required int32 unsigned (UINT_8); }";
+
+#[test]
+fn reads_int8_columns_as_integers() {
+    let bytes = write_int32_columns(BYTES_SCHEMA, &[&[-1, 7], &[255, 0]]);
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &FromParquetOpts::default()).unwrap();
+
+    assert_eq!(column(&table, "signed"), vec![UntaggedValue::int(-1), UntaggedValue::int(7)]);
+    assert_eq!(column(&table, "unsigned"), vec![UntaggedValue::int(255), UntaggedValue::int(0)]);
+}
+
+#[test]
+fn reads_int8_columns_as_binary_when_asked() {
+    let bytes = write_int32_columns(BYTES_SCHEMA, &[&[-1], &[255]]);
+    let opts = FromParquetOpts { bytes_as_binary: true, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    assert_eq!(column(&table, "signed"), vec![UntaggedValue::Primitive(Primitive::Binary(vec![255]))]);
+    assert_eq!(column(&table, "unsigned"), vec![UntaggedValue::Primitive(Primitive::Binary(vec![255]))]);
+}
+
+#[test]
+fn encodes_int8_columns_with_the_binary_encodings() {
+    let bytes = write_int32_columns(BYTES_SCHEMA, &[&[-1, 7], &[255, 0]]);
+    let read = |binary_representation| {
+        let opts = FromParquetOpts { binary_representation, ..FromParquetOpts::default() };
+        let table = from_parquet_bytes(bytes.clone(), Tag::unknown(), &opts).unwrap();
+        (column(&table, "signed"), column(&table, "unsigned"))
+    };
+    let strings = |values: &[&str]| values.iter().map(|v| UntaggedValue::string(*v)).collect::<Vec<_>>();
+
+    assert_eq!(read(BinaryRepresentation::Hex), (strings(&["ff", "07"]), strings(&["ff", "00"])));
+    assert_eq!(read(BinaryRepresentation::Base64), (strings(&["/w==", "Bw=="]), strings(&["/w==", "AA=="])));
+}
+
+const TIME_SCHEMA: &str = "message schema { required int32 time (TIME_MILLIS); }";
+
+#[test]
+fn reads_time_millis_as_string() {
+    let bytes = write_int32_columns(TIME_SCHEMA, &[&[45_296_789, 0]]);
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &FromParquetOpts::default()).unwrap();
+
+    assert_eq!(
+        column(&table, "time"),
+        vec![UntaggedValue::string("12:34:56.789"), UntaggedValue::string("00:00:00.000")]
+    );
+}
+
+#[test]
+fn reads_time_millis_as_duration() {
+    let bytes = write_int32_columns(TIME_SCHEMA, &[&[45_296_789]]);
+    let opts = FromParquetOpts { time_representation: TimeRepresentation::Duration, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    assert_eq!(column(&table, "time"), vec![UntaggedValue::duration(45_296_789_000_000i64)]);
+}
+
+#[test]
+fn applies_annotations_of_nested_columns() {
+    let schema = "message schema { required group inner { required int32 time (TIME_MILLIS); required int32 byte (INT_8); } }";
+    let bytes = write_int32_columns(schema, &[&[45_296_789], &[-3]]);
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &FromParquetOpts::default()).unwrap();
+
+    let inner = match &column(&table, "inner")[0] {
+        UntaggedValue::Row(dict) => dict.clone(),
+        other => panic!("expected a record, got {:?}", other),
+    };
+    assert_eq!(inner.entries["time"].value, UntaggedValue::string("12:34:56.789"));
+    assert_eq!(inner.entries["byte"].value, UntaggedValue::int(-3));
+}
+
+#[test]
+fn builds_options_from_the_defaults() {
+    let bytes = write_int32_columns(BYTES_SCHEMA, &[&[-1, 7], &[255, 0]]);
+    let opts = nu_plugin_from_parquet::FromParquetOpts::builder()
+        .bytes_as_binary(true)
+        .limit(1)
+        .build();
+    let table = nu_plugin_from_parquet::from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    assert_eq!(column(&table, "signed"), vec![UntaggedValue::Primitive(Primitive::Binary(vec![255]))]);
+    assert_eq!(column(&table, "unsigned"), vec![UntaggedValue::Primitive(Primitive::Binary(vec![255]))]);
+}
+
+#[test]
+fn orders_listed_columns_first_and_sorts_the_rest() {
+    let schema = "message schema { required int32 c; required int32 a; required int32 d; required int32 b; }";
+    let bytes = write_int32_columns(schema, &[&[1], &[2], &[3], &[4]]);
+    let opts = FromParquetOpts { column_order: vec!["d".to_string()], ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    let names: Vec<String> = match &table.table_entries().next().unwrap().value {
+        UntaggedValue::Row(dict) => dict.entries.keys().cloned().collect(),
+        other => panic!("expected a record, got {:?}", other),
+    };
+    assert_eq!(names, vec!["d", "a", "b", "c"]);
+}
+
+/// Writes the optional columns `a` and `b` with the rows (1, 4), (null, 6) and (3, null).
+fn write_rows_with_nulls() -> Vec<u8> {
+    let schema = "message schema { optional int32 a; optional int32 b; }";
+    let props = Arc::new(WriterProperties::builder().build());
+    let cursor = InMemoryWriteableCursor::default();
+    let mut writer = SerializedFileWriter::new(cursor.clone(), Arc::new(parse_message_type(schema).unwrap()), props).unwrap();
+    let mut row_group = writer.next_row_group().unwrap();
+    for (values, def_levels) in [(vec![1, 3], [1, 0, 1]), (vec![4, 6], [1, 1, 0])].iter() {
+        let mut column = row_group.next_column().unwrap().unwrap();
+        match &mut column {
+            ColumnWriter::Int32ColumnWriter(w) => {
+                w.write_batch(values, Some(def_levels), None).unwrap();
+            }
+            _ => panic!("expected an INT32 column"),
+        }
+        row_group.close_column(column).unwrap();
+    }
+    writer.close_row_group(row_group).unwrap();
+    writer.close().unwrap();
+    cursor.data()
+}
+
+#[test]
+fn only_returns_rows_with_nulls() {
+    let bytes = write_rows_with_nulls();
+
+    let opts = FromParquetOpts { only_nulls: true, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes.clone(), Tag::unknown(), &opts).unwrap();
+    assert_eq!(column(&table, "a"), vec![UntaggedValue::nothing(), UntaggedValue::int(3)]);
+
+    let opts = FromParquetOpts { only_nulls_in: vec!["b".to_string()], ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+    assert_eq!(column(&table, "a"), vec![UntaggedValue::int(3)]);
+}
+
+#[test]
+fn keeps_one_row_per_dedup_key() {
+    let schema = "message schema { required int32 id; required int32 version; }";
+    let bytes = write_int32_columns(schema, &[&[1, 2, 1, 2, 3], &[10, 20, 11, 21, 30]]);
+
+    let opts = FromParquetOpts { dedup: vec!["id".to_string()], ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes.clone(), Tag::unknown(), &opts).unwrap();
+    let versions: Vec<UntaggedValue> = [10, 20, 30].iter().map(|v| UntaggedValue::int(*v)).collect();
+    assert_eq!(column(&table, "version"), versions);
+
+    let opts = FromParquetOpts { dedup: vec!["id".to_string()], dedup_keep_last: true, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+    let versions: Vec<UntaggedValue> = [11, 21, 30].iter().map(|v| UntaggedValue::int(*v)).collect();
+    assert_eq!(column(&table, "version"), versions);
+}
+
+#[test]
+fn infers_dates_from_column_names() {
+    let schema = "message schema { required int32 birth_date; required int32 count; }";
+    let bytes = write_int32_columns(schema, &[&[1], &[1]]);
+    let opts = FromParquetOpts { infer_types: true, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    let date = chrono::DateTime::parse_from_rfc3339("1970-01-02T00:00:00+00:00").unwrap();
+    assert_eq!(column(&table, "birth_date"), vec![UntaggedValue::date(date)]);
+    assert_eq!(column(&table, "count"), vec![UntaggedValue::int(1)]);
+}
+
+#[test]
+fn describes_numeric_columns() {
+    let schema = "message schema { required int32 a; }";
+    let bytes = write_int32_columns(schema, &[&[2, 4, 4, 4, 5, 5, 7, 9]]);
+    let opts = FromParquetOpts { describe: true, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    assert_eq!(column(&table, "column"), vec![UntaggedValue::string("a")]);
+    assert_eq!(column(&table, "null_count"), vec![UntaggedValue::int(0)]);
+    assert_eq!(column(&table, "min"), vec![UntaggedValue::int(2)]);
+    assert_eq!(column(&table, "max"), vec![UntaggedValue::int(9)]);
+    assert_eq!(column(&table, "sum"), vec![UntaggedValue::int(40)]);
+    assert_eq!(column(&table, "mean"), vec![UntaggedValue::decimal_from_float(5.0, Tag::unknown().span)]);
+}
+
+#[test]
+fn aggregates_rows_per_group() {
+    let schema = "message schema { required int32 key; required int32 amount; }";
+    let bytes = write_int32_columns(schema, &[&[2, 1, 2, 2], &[10, 5, 20, 30]]);
+    let aggregations = vec![
+        Aggregation { column: "amount".to_string(), op: AggOp::Sum },
+        Aggregation { column: "amount".to_string(), op: AggOp::Max },
+    ];
+    let opts = FromParquetOpts { group_by: Some("key".to_string()), aggregations, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    assert_eq!(column(&table, "key"), vec![UntaggedValue::int(2), UntaggedValue::int(1)]);
+    assert_eq!(column(&table, "amount_sum"), vec![UntaggedValue::int(60), UntaggedValue::int(5)]);
+    assert_eq!(column(&table, "amount_max"), vec![UntaggedValue::int(30), UntaggedValue::int(5)]);
+}
+
+#[test]
+fn converts_rows_and_fields_with_try_from() {
+    use nu_plugin_from_parquet::{FieldAdapter, ParquetRowAdapter};
+    use parquet::file::reader::FileReader;
+    use parquet::file::serialized_reader::{SerializedFileReader, SliceableCursor};
+    use std::convert::TryFrom;
+
+    let bytes = write_int32_columns("message schema { required int32 id; }", &[&[7]]);
+    let reader = SerializedFileReader::new(SliceableCursor::new(bytes)).unwrap();
+    let row = reader.get_row_iter(None).unwrap().next().unwrap();
+    let opts = FromParquetOpts::default();
+
+    let record = Value::try_from(ParquetRowAdapter(&row, Tag::unknown(), &opts)).unwrap();
+    let table = UntaggedValue::Table(vec![record]).into_value(Tag::unknown());
+    assert_eq!(column(&table, "id"), vec![UntaggedValue::int(7)]);
+
+    let field = parquet::record::Field::Int(7);
+    let value = Value::try_from(FieldAdapter(&field, Tag::unknown(), &opts)).unwrap();
+    assert_eq!(value.value, UntaggedValue::int(7));
+}
+
+#[test]
+fn explains_the_read_without_running_it() {
+    use nu_plugin_from_parquet::filter::Predicate;
+
+    let bytes = write_int32_columns("message schema { required int32 a; }", &[&[1, 2, 3]]);
+    let filter = Some(Predicate::parse("a gt 10").unwrap());
+    let opts = FromParquetOpts { explain: true, filter, ..FromParquetOpts::default() };
+    let plan = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap().as_string().unwrap();
+
+    assert!(plan.contains("row groups: 0 of 1 read, 1 skipped by statistics"), "{}", plan);
+    assert!(plan.contains("row group 0: skipped by statistics, 3 rows"), "{}", plan);
+    assert!(plan.contains("estimated rows to decode: 0"), "{}", plan);
+}
+
+#[test]
+fn emits_failed_conversions_as_error_rows() {
+    let opts = FromParquetOpts { null_representation: NullRepresentation::Error, ..FromParquetOpts::default() };
+    assert!(from_parquet_bytes(write_rows_with_nulls(), Tag::unknown(), &opts).is_err());
+
+    let opts = FromParquetOpts { errors_as_rows: true, ..opts };
+    let table = from_parquet_bytes(write_rows_with_nulls(), Tag::unknown(), &opts).unwrap();
+    assert_eq!(column(&table, "a"), vec![UntaggedValue::int(1), UntaggedValue::nothing(), UntaggedValue::nothing()]);
+    let errors = match table.value {
+        UntaggedValue::Table(rows) => UntaggedValue::Table(rows[1..].to_vec()).into_value(Tag::unknown()),
+        other => panic!("expected a table, got {:?}", other),
+    };
+    assert_eq!(column(&errors, "_error"), vec![UntaggedValue::boolean(true), UntaggedValue::boolean(true)]);
+    assert_eq!(column(&errors, "_row_index"), vec![UntaggedValue::int(1), UntaggedValue::int(2)]);
+    assert_eq!(column(&errors, "_column"), vec![UntaggedValue::string("a"), UntaggedValue::string("b")]);
+}
+
+#[test]
+fn appends_selected_columns_to_every_row() {
+    use nu_plugin_from_parquet::expr::Select;
+
+    let schema = "message schema { required int32 price; required int32 quantity; }";
+    let bytes = write_int32_columns(schema, &[&[3, 5], &[2, 4]]);
+    let selects = vec![Select::parse("total = price * quantity").unwrap(), Select::parse("half = total / 2").unwrap()];
+    let opts = FromParquetOpts { selects, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    assert_eq!(column(&table, "total"), vec![UntaggedValue::int(6), UntaggedValue::int(20)]);
+    assert_eq!(column(&table, "half"), vec![UntaggedValue::int(3), UntaggedValue::int(10)]);
+
+    let selects = vec![Select::parse("total = price * missing").unwrap()];
+    let opts = FromParquetOpts { selects, ..FromParquetOpts::default() };
+    assert!(from_parquet_bytes(write_int32_columns(schema, &[&[3], &[2]]), Tag::unknown(), &opts).is_err());
+}
+
+#[test]
+fn streams_the_rows_of_the_table() {
+    use nu_plugin_from_parquet::filter::Predicate;
+    use nu_plugin_from_parquet::from_parquet_stream;
+    use nu_plugin_from_parquet::to_parquet::{to_parquet_bytes, ToParquetOpts};
+    use nu_protocol::TaggedDictBuilder;
+
+    // The first rows have no label, their schema differs from that of the others.
+    let rows: Vec<Value> = (0..10)
+        .map(|id| {
+            let mut dict = TaggedDictBuilder::new(Tag::unknown());
+            dict.insert_untagged("id", UntaggedValue::int(id));
+            dict.insert_untagged("key", UntaggedValue::int(id % 3));
+            let label = if id < 2 { UntaggedValue::nothing() } else { UntaggedValue::string(format!("row {}", id)) };
+            dict.insert_untagged("label", label);
+            dict.into_value()
+        })
+        .collect();
+    let to = ToParquetOpts { row_group_size: 3, ..ToParquetOpts::default() };
+    let bytes = to_parquet_bytes(&rows, Tag::unknown(), &to).unwrap();
+
+    let cases = vec![
+        FromParquetOpts { skip: 2, limit: Some(5), ..FromParquetOpts::default() },
+        FromParquetOpts { skip: 4, dedup: vec!["key".to_string()], ..FromParquetOpts::default() },
+        FromParquetOpts { skip: 1, limit: Some(8), dedup: vec!["key".to_string()], dedup_keep_last: true, ..FromParquetOpts::default() },
+        FromParquetOpts { skip: 1, limit: Some(3), filter: Some(Predicate::parse("id ge 3").unwrap()), ..FromParquetOpts::default() },
+        FromParquetOpts { skip: 20, ..FromParquetOpts::default() },
+    ];
+    for opts in cases {
+        for output_schema in &[false, true] {
+            let opts = FromParquetOpts { output_schema: *output_schema, ..opts.clone() };
+            let table = from_parquet_bytes(bytes.clone(), Tag::unknown(), &opts).unwrap();
+            let streamed = from_parquet_stream(bytes.clone(), Tag::unknown(), &opts).unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+
+            assert_eq!(streamed, table.table_entries().cloned().collect::<Vec<_>>(), "{:?}", opts);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use nu_plugin_from_parquet::locale::NumberFormat;
+
+fn format(locale: &str, number: &str) -> String {
+    NumberFormat::for_locale(locale).unwrap().format(number)
+}
+
+#[test]
+fn formats_with_the_separators_of_the_locale() {
+    assert_eq!(format("en", "-1234567.25"), "-1,234,567.25");
+    assert_eq!(format("de", "1234567.25"), "1.234.567,25");
+    assert_eq!(format("en-IN", "1234567"), "12,34,567");
+    assert_eq!(format("de_ch", "1234.5"), format("de-CH", "1234.5"));
+    assert_eq!(format("de-DE", "1234.5"), "1.234,5");
+    assert_eq!(format("en", "123"), "123");
+}
+
+#[test]
+fn refuses_unknown_locales() {
+    let error = NumberFormat::for_locale("xx-YY").unwrap_err();
+    assert!(error.starts_with("unknown locale 'xx-YY'"), "{}", error);
+}
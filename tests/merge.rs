@@ -0,0 +1,59 @@
+mod common;
+
+use common::record;
+use nu_plugin_from_parquet::from_parquet::{from_parquet_bytes, FromParquetOpts};
+use nu_plugin_from_parquet::merge::{parquet_merge, MergeMode};
+use nu_plugin_from_parquet::to_parquet::{to_parquet_bytes, ToParquetOpts};
+use nu_protocol::{Primitive, UntaggedValue, Value};
+use nu_source::Tag;
+use parquet::file::reader::FileReader;
+use parquet::file::serialized_reader::{SerializedFileReader, SliceableCursor};
+
+fn row(id: i64, name: Option<&str>) -> Value {
+    record(vec![("id", UntaggedValue::int(id)), ("name", name.map_or_else(UntaggedValue::nothing, UntaggedValue::string))])
+}
+
+fn write(rows: &[Value], row_group_size: usize) -> Vec<u8> {
+    let opts = ToParquetOpts { row_group_size, ..ToParquetOpts::default() };
+    to_parquet_bytes(rows, Tag::unknown(), &opts).unwrap()
+}
+
+fn merge(files: Vec<Vec<u8>>, mode: MergeMode) -> Vec<u8> {
+    match parquet_merge(files, Tag::unknown(), mode).unwrap().value {
+        UntaggedValue::Primitive(Primitive::Binary(bytes)) => bytes,
+        other => panic!("expected binary, got {:?}", other),
+    }
+}
+
+#[test]
+fn merges_the_row_groups_of_every_file() {
+    let first = vec![row(1, Some("ada")), row(2, None), row(3, Some("grace"))];
+    let second = vec![row(4, Some("edsger"))];
+    let files = vec![write(&first, 2), write(&second, 2)];
+    let expected: Vec<Value> = first.iter().chain(&second).cloned().collect();
+
+    for mode in &[MergeMode::RawCopy, MergeMode::ReEncode] {
+        let merged = merge(files.clone(), *mode);
+
+        let reader = SerializedFileReader::new(SliceableCursor::new(merged.clone())).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 4, "{:?}", mode);
+        assert_eq!(reader.metadata().num_row_groups(), 3, "{:?}", mode);
+        let rows: Vec<i64> = reader.metadata().row_groups().iter().map(|rg| rg.num_rows()).collect();
+        assert_eq!(rows, vec![2, 1, 1], "{:?}", mode);
+
+        let table = from_parquet_bytes(merged, Tag::unknown(), &FromParquetOpts::default()).unwrap();
+        assert_eq!(table.table_entries().cloned().collect::<Vec<_>>(), expected, "{:?}", mode);
+    }
+}
+
+#[test]
+fn refuses_files_of_other_schemas() {
+    let other = record(vec![("id", UntaggedValue::string("1"))]);
+    let files = vec![write(&[row(1, Some("ada"))], 2), write(&[other], 2)];
+
+    for mode in &[MergeMode::RawCopy, MergeMode::ReEncode] {
+        let error = parquet_merge(files.clone(), Tag::unknown(), *mode).unwrap_err();
+        assert!(format!("{:?}", error).contains("Incompatible Parquet schemas in inputs 0 and 1"), "{:?}", error);
+    }
+    assert!(parquet_merge(Vec::new(), Tag::unknown(), MergeMode::RawCopy).is_err());
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::{column, record};
+use nu_plugin_from_parquet::from_parquet::{from_parquet_bytes, FromParquetOpts};
+use nu_plugin_from_parquet::split::{parquet_split, to_parquet_partitioned, ExistingFiles};
+use nu_plugin_from_parquet::to_parquet::{to_parquet_bytes, ToParquetOpts};
+use nu_protocol::{UntaggedValue, Value};
+use nu_source::Tag;
+use std::path::{Path, PathBuf};
+
+/// An empty directory of its own for a test, removed when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("nu_plugin_from_parquet-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn row(city: Option<&str>, n: i64) -> Value {
+    record(vec![
+        ("city", city.map_or_else(UntaggedValue::nothing, UntaggedValue::string)),
+        ("n", UntaggedValue::int(n)),
+    ])
+}
+
+fn n(values: &[i64]) -> Vec<Value> {
+    values.iter().map(|n| record(vec![("n", UntaggedValue::int(*n))])).collect()
+}
+
+/// The rows of the partition file of `city` under `dir`.
+fn read_partition(dir: &Path, city: &str) -> Vec<Value> {
+    let bytes = std::fs::read(dir.join(format!("city={}", city)).join("part-0.parquet")).unwrap();
+    from_parquet_bytes(bytes, Tag::unknown(), &FromParquetOpts::default()).unwrap().table_entries().cloned().collect()
+}
+
+#[test]
+fn splits_a_file_into_hive_partitions() {
+    let dir = TempDir::new("split");
+    let rows = vec![row(Some("Oslo"), 1), row(None, 2), row(Some("a/b"), 3), row(Some("Oslo"), 4)];
+    let bytes = to_parquet_bytes(&rows, Tag::unknown(), &ToParquetOpts::default()).unwrap();
+
+    let written = parquet_split(bytes, Tag::unknown(), "city", &dir.0, &ToParquetOpts::default()).unwrap();
+
+    let values = ["Oslo", "__HIVE_DEFAULT_PARTITION__", "a/b"];
+    assert_eq!(column(&written, "partition_value"), values.iter().map(|v| UntaggedValue::string(*v)).collect::<Vec<_>>());
+    assert_eq!(column(&written, "row_count"), vec![UntaggedValue::int(2), UntaggedValue::int(1), UntaggedValue::int(1)]);
+    let path = dir.0.join("city=a%2Fb").join("part-0.parquet");
+    assert_eq!(column(&written, "file_path")[2], UntaggedValue::string(path.display().to_string()));
+    assert_eq!(read_partition(&dir.0, "Oslo"), n(&[1, 4]));
+    assert_eq!(read_partition(&dir.0, "__HIVE_DEFAULT_PARTITION__"), n(&[2]));
+    assert_eq!(read_partition(&dir.0, "a%2Fb"), n(&[3]));
+}
+
+#[test]
+fn writes_partitions_as_existing_files_say() {
+    let dir = TempDir::new("partitioned");
+    let write = |rows: Vec<Value>, existing| {
+        to_parquet_partitioned(rows, Tag::unknown(), "city", &dir.0, &ToParquetOpts::default(), existing)
+    };
+
+    let written = write(vec![row(Some("Oslo"), 1), row(None, 2)], ExistingFiles::Error).unwrap();
+    assert_eq!(column(&written, "rows"), vec![UntaggedValue::int(1), UntaggedValue::int(1)]);
+    assert_eq!(read_partition(&dir.0, "__HIVE_DEFAULT_PARTITION__"), n(&[2]));
+
+    // Nothing is written when any partition file exists, not even the new Lima one.
+    let error = write(vec![row(Some("Lima"), 3), row(Some("Oslo"), 4)], ExistingFiles::Error).unwrap_err();
+    assert!(format!("{:?}", error).contains("already exists"), "{:?}", error);
+    assert!(!dir.0.join("city=Lima").exists());
+    assert_eq!(read_partition(&dir.0, "Oslo"), n(&[1]));
+
+    let written = write(vec![row(Some("Oslo"), 5), row(Some("Oslo"), 6)], ExistingFiles::Append).unwrap();
+    assert_eq!(column(&written, "rows"), vec![UntaggedValue::int(3)]);
+    assert_eq!(read_partition(&dir.0, "Oslo"), n(&[1, 5, 6]));
+
+    let written = write(vec![row(Some("Oslo"), 7), row(Some("Lima"), 8)], ExistingFiles::Overwrite).unwrap();
+    assert_eq!(column(&written, "partition"), vec![UntaggedValue::string("Oslo"), UntaggedValue::string("Lima")]);
+    assert_eq!(read_partition(&dir.0, "Oslo"), n(&[7]));
+    assert_eq!(read_partition(&dir.0, "Lima"), n(&[8]));
+    assert_eq!(read_partition(&dir.0, "__HIVE_DEFAULT_PARTITION__"), n(&[2]));
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, FixedOffset};
+use nu_plugin_from_parquet::from_parquet::{days_to_date, timestamp_to_date, TimeUnit};
+
+fn date(s: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(s).unwrap()
+}
+
+#[test]
+fn converts_timestamps_in_every_unit() {
+    let expected = date("2021-02-03T04:05:06.123456789+00:00");
+    assert_eq!(
+        timestamp_to_date(1_612_325_106_123, TimeUnit::Millis),
+        Some(date("2021-02-03T04:05:06.123+00:00"))
+    );
+    assert_eq!(
+        timestamp_to_date(1_612_325_106_123_456, TimeUnit::Micros),
+        Some(date("2021-02-03T04:05:06.123456+00:00"))
+    );
+    assert_eq!(timestamp_to_date(1_612_325_106_123_456_789, TimeUnit::Nanos), Some(expected));
+    assert_eq!(expected.timestamp_nanos(), 1_612_325_106_123_456_789);
+}
+
+#[test]
+fn converts_timestamps_before_the_epoch() {
+    assert_eq!(
+        timestamp_to_date(-1, TimeUnit::Millis),
+        Some(date("1969-12-31T23:59:59.999+00:00"))
+    );
+    assert_eq!(days_to_date(-1), Some(date("1969-12-31T00:00:00+00:00")));
+}
+
+#[test]
+fn out_of_range_timestamps_are_rejected() {
+    assert_eq!(timestamp_to_date(i64::MAX, TimeUnit::Millis), None);
+    assert_eq!(days_to_date(i64::MAX), None);
+}
@@ -0,0 +1,238 @@
+mod common;
+
+use common::{column, record};
+use nu_plugin_from_parquet::bloom::parquet_bloom_check;
+use nu_plugin_from_parquet::from_parquet::{from_parquet_bytes, FromParquetOpts};
+use nu_plugin_from_parquet::pages::parquet_pages;
+use nu_plugin_from_parquet::stats::parquet_stats;
+use nu_plugin_from_parquet::to_parquet::{
+    to_parquet, to_parquet_bytes, CompressionChoice, StatisticsLevel, ToParquetOpts, GZIP_DEFAULT_LEVEL, ZSTD_DEFAULT_LEVEL,
+};
+use nu_protocol::{Primitive, UntaggedValue, Value};
+use nu_source::Tag;
+use parquet::file::reader::FileReader;
+use parquet::file::serialized_reader::{SerializedFileReader, SliceableCursor};
+
+/// Writes `rows` with `opts` and reads them back with the default options.
+fn round_trip(rows: &[Value], opts: &ToParquetOpts) -> Vec<Value> {
+    let bytes = to_parquet_bytes(rows, Tag::unknown(), opts).unwrap();
+    match from_parquet_bytes(bytes, Tag::unknown(), &FromParquetOpts::default()).unwrap().value {
+        UntaggedValue::Table(rows) => rows,
+        other => panic!("expected a table, got {:?}", other),
+    }
+}
+
+#[test]
+fn round_trips_flat_rows() {
+    let date = UntaggedValue::date(chrono::DateTime::parse_from_rfc3339("2021-02-03T04:05:06.789Z").unwrap());
+    let rows = vec![
+        record(vec![
+            ("id", UntaggedValue::int(1)),
+            ("name", UntaggedValue::string("ada")),
+            ("active", UntaggedValue::boolean(true)),
+            ("score", UntaggedValue::decimal_from_float(1.5, Tag::unknown().span)),
+            ("blob", UntaggedValue::binary(vec![0, 1, 2])),
+            ("at", date),
+        ]),
+        record(vec![
+            ("id", UntaggedValue::int(-2)),
+            ("name", UntaggedValue::nothing()),
+            ("active", UntaggedValue::boolean(false)),
+            ("score", UntaggedValue::decimal_from_float(-0.25, Tag::unknown().span)),
+            ("blob", UntaggedValue::binary(vec![255])),
+            ("at", UntaggedValue::nothing()),
+        ]),
+    ];
+
+    assert_eq!(round_trip(&rows, &ToParquetOpts::default()), rows);
+}
+
+fn people() -> Vec<Value> {
+    ["ada", "grace", "edsger", "bob"].iter().enumerate()
+        .map(|(id, name)| record(vec![("id", UntaggedValue::int(id as i64)), ("name", UntaggedValue::string(*name))]))
+        .collect()
+}
+
+#[test]
+fn writes_every_codec_at_its_default_level() {
+    let codecs = ["uncompressed", "snappy", "gzip", "zstd", "lz4"];
+    for name in codecs.iter() {
+        let compression = CompressionChoice::from_name(name).unwrap();
+        let opts = ToParquetOpts { compression, ..ToParquetOpts::default() };
+        assert_eq!(round_trip(&people(), &opts), people(), "{}", name);
+    }
+}
+
+#[test]
+fn refuses_levels_parquet_cannot_set() {
+    for compression in [CompressionChoice::Zstd(ZSTD_DEFAULT_LEVEL + 1), CompressionChoice::Gzip(GZIP_DEFAULT_LEVEL - 1)].iter() {
+        let opts = ToParquetOpts { compression: *compression, ..ToParquetOpts::default() };
+        let error = to_parquet_bytes(&people(), Tag::unknown(), &opts).unwrap_err();
+        assert!(format!("{:?}", error).contains("Compression level cannot be set"), "{:?}", error);
+    }
+    assert!(CompressionChoice::Zstd(ZSTD_DEFAULT_LEVEL).check_level(&Tag::unknown()).is_ok());
+}
+
+/// The `may_contain` column of `parquet bloom-check`.
+fn may_contain(bytes: &[u8], name: &str, literal: &str) -> Vec<UntaggedValue> {
+    column(&parquet_bloom_check(bytes.to_vec(), Tag::unknown(), name, literal).unwrap(), "may_contain")
+}
+
+#[test]
+fn round_trips_rows_with_bloom_filters() {
+    let rows = people();
+    let opts = ToParquetOpts { bloom_filter_columns: vec!["name".to_string(), "id".to_string()], row_group_size: 2, ..ToParquetOpts::default() };
+
+    assert_eq!(round_trip(&rows, &opts), rows);
+}
+
+#[test]
+fn checks_values_against_the_bloom_filters() {
+    let opts = ToParquetOpts { bloom_filter_columns: vec!["name".to_string()], row_group_size: 2, ..ToParquetOpts::default() };
+    let bytes = to_parquet_bytes(&people(), Tag::unknown(), &opts).unwrap();
+    let yes = UntaggedValue::boolean(true);
+    let no = UntaggedValue::boolean(false);
+
+    assert_eq!(may_contain(&bytes, "name", "grace"), vec![yes.clone(), no.clone()]);
+    assert_eq!(may_contain(&bytes, "name", "bob"), vec![no.clone(), yes]);
+    assert_eq!(may_contain(&bytes, "name", "linus"), vec![no.clone(), no]);
+}
+
+#[test]
+fn refuses_columns_without_a_bloom_filter() {
+    let opts = ToParquetOpts { bloom_filter_columns: vec!["name".to_string()], ..ToParquetOpts::default() };
+    let bytes = to_parquet_bytes(&people(), Tag::unknown(), &opts).unwrap();
+    let plain = to_parquet_bytes(&people(), Tag::unknown(), &ToParquetOpts::default()).unwrap();
+
+    let error = parquet_bloom_check(bytes.clone(), Tag::unknown(), "id", "1").unwrap_err();
+    assert!(format!("{:?}", error).contains("Column 'id' has no Bloom filter"), "{:?}", error);
+    let error = parquet_bloom_check(plain, Tag::unknown(), "name", "ada").unwrap_err();
+    assert!(format!("{:?}", error).contains("Column 'name' has no Bloom filter"), "{:?}", error);
+    assert!(parquet_bloom_check(bytes, Tag::unknown(), "missing", "ada").is_err());
+}
+
+/// Whether each data page of the `name` chunks, by row group, has statistics in its header.
+fn page_statistics(bytes: &[u8], row_groups: usize) -> Vec<UntaggedValue> {
+    (0..row_groups)
+        .flat_map(|row_group| {
+            let pages = parquet_pages(bytes.to_vec(), Tag::unknown(), "name", row_group).unwrap();
+            column(&pages, "page_type").into_iter().zip(column(&pages, "has_statistics"))
+                .filter(|(page_type, _)| *page_type != UntaggedValue::string("DICTIONARY_PAGE"))
+                .map(|(_, has_statistics)| has_statistics)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[test]
+fn round_trips_every_statistics_level() {
+    let rows = people();
+    let written = |statistics| {
+        let opts = ToParquetOpts { statistics, row_group_size: 2, ..ToParquetOpts::default() };
+        to_parquet_bytes(&rows, Tag::unknown(), &opts).unwrap()
+    };
+    let read = |bytes: &[u8]| from_parquet_bytes(bytes.to_vec(), Tag::unknown(), &FromParquetOpts::default()).unwrap();
+    let (none, chunk, page) = (written(StatisticsLevel::None), written(StatisticsLevel::Chunk), written(StatisticsLevel::Page));
+
+    for bytes in &[&none, &chunk, &page] {
+        assert_eq!(read(bytes).table_entries().cloned().collect::<Vec<_>>(), rows);
+    }
+
+    let stats = parquet_stats(none.clone(), Tag::unknown()).unwrap();
+    assert_eq!(column(&stats, "min_value"), vec![UntaggedValue::nothing(); 4]);
+    assert_eq!(column(&stats, "null_count"), vec![UntaggedValue::nothing(); 4]);
+    assert_eq!(page_statistics(&none, 2), vec![UntaggedValue::boolean(false); 2]);
+
+    for bytes in &[&chunk, &page] {
+        let stats = parquet_stats(bytes.to_vec(), Tag::unknown()).unwrap();
+        assert_eq!(column(&stats, "column"), ["id", "name", "id", "name"].iter().map(|c| UntaggedValue::string(*c)).collect::<Vec<_>>());
+        assert_eq!(column(&stats, "min_value"), vec![
+            UntaggedValue::int(0), UntaggedValue::string("ada"), UntaggedValue::int(2), UntaggedValue::string("bob"),
+        ]);
+        assert_eq!(column(&stats, "max_value"), vec![
+            UntaggedValue::int(1), UntaggedValue::string("grace"), UntaggedValue::int(3), UntaggedValue::string("edsger"),
+        ]);
+    }
+    assert_eq!(page_statistics(&chunk, 2), vec![UntaggedValue::boolean(false); 2]);
+    assert_eq!(page_statistics(&page, 2), vec![UntaggedValue::boolean(true); 2]);
+}
+
+#[test]
+fn reads_the_written_column_index_as_page_stats() {
+    let rows = people();
+    let opts = ToParquetOpts { column_index: true, row_group_size: 2, ..ToParquetOpts::default() };
+    let written = match to_parquet(&rows, Tag::unknown(), &opts).unwrap().value {
+        UntaggedValue::Row(dict) => dict,
+        other => panic!("expected a record, got {:?}", other),
+    };
+    assert_eq!(written.entries["pages_indexed"].value, UntaggedValue::int(4));
+    let bytes = match &written.entries["parquet"].value {
+        UntaggedValue::Primitive(Primitive::Binary(bytes)) => bytes.clone(),
+        other => panic!("expected binary, got {:?}", other),
+    };
+
+    let opts = FromParquetOpts { page_stats: true, ..FromParquetOpts::default() };
+    let table = from_parquet_bytes(bytes, Tag::unknown(), &opts).unwrap();
+
+    let ints = |values: &[i64]| values.iter().map(|v| UntaggedValue::int(*v)).collect::<Vec<_>>();
+    let strings = |values: &[&str]| values.iter().map(|v| UntaggedValue::string(*v)).collect::<Vec<_>>();
+    assert_eq!(column(&table, "id"), ints(&[0, 1, 2, 3]));
+    assert_eq!(column(&table, "_page_min_id"), ints(&[0, 0, 2, 2]));
+    assert_eq!(column(&table, "_page_max_id"), ints(&[1, 1, 3, 3]));
+    assert_eq!(column(&table, "_page_min_name"), strings(&["ada", "ada", "bob", "bob"]));
+    assert_eq!(column(&table, "_page_max_name"), strings(&["grace", "grace", "edsger", "edsger"]));
+}
+
+fn list(values: Vec<UntaggedValue>) -> UntaggedValue {
+    UntaggedValue::Table(values.into_iter().map(|v| v.into_value(Tag::unknown())).collect())
+}
+
+#[test]
+fn round_trips_nested_records_and_lists() {
+    let user = |name: &str, age: i64| record(vec![("name", UntaggedValue::string(name)), ("age", UntaggedValue::int(age))]).value;
+    let rows = vec![
+        record(vec![
+            ("id", UntaggedValue::int(1)),
+            ("user", user("ada", 36)),
+            ("tags", list(vec![UntaggedValue::string("math"), UntaggedValue::string("code")])),
+            ("scores", list(vec![list(vec![UntaggedValue::int(1), UntaggedValue::int(2)]), list(vec![])])),
+        ]),
+        record(vec![
+            ("id", UntaggedValue::int(2)),
+            ("user", UntaggedValue::nothing()),
+            ("tags", list(vec![])),
+            ("scores", UntaggedValue::nothing()),
+        ]),
+        record(vec![
+            ("id", UntaggedValue::int(3)),
+            ("user", user("grace", 85)),
+            ("tags", UntaggedValue::nothing()),
+            ("scores", list(vec![list(vec![UntaggedValue::int(3)])])),
+        ]),
+    ];
+
+    assert_eq!(round_trip(&rows, &ToParquetOpts::default()), rows);
+
+    let bytes = to_parquet_bytes(&rows, Tag::unknown(), &ToParquetOpts::default()).unwrap();
+    let reader = SerializedFileReader::new(SliceableCursor::new(bytes)).unwrap();
+    let leaves: Vec<String> = reader.metadata().file_metadata().schema_descr().columns().iter().map(|c| c.path().string()).collect();
+    assert_eq!(leaves, vec!["id", "user.name", "user.age", "tags.list.element", "scores.list.element.list.element"]);
+}
+
+#[test]
+fn refuses_columns_of_conflicting_shapes() {
+    let conflict = |first: UntaggedValue, second: UntaggedValue| {
+        let rows = vec![record(vec![("a", first)]), record(vec![("a", second)])];
+        format!("{:?}", to_parquet_bytes(&rows, Tag::unknown(), &ToParquetOpts::default()).unwrap_err())
+    };
+    let nested = record(vec![("b", UntaggedValue::int(1))]).value;
+
+    let error = conflict(UntaggedValue::int(1), UntaggedValue::string("x"));
+    assert!(error.contains("Conflicting types in column 'a'") && error.contains("found string after integer"), "{}", error);
+    let error = conflict(list(vec![UntaggedValue::int(1)]), UntaggedValue::int(2));
+    assert!(error.contains("found integer after list"), "{}", error);
+    let error = conflict(nested.clone(), list(vec![]));
+    assert!(error.contains("found list after record"), "{}", error);
+    let error = conflict(nested, record(vec![("b", UntaggedValue::string("x"))]).value);
+    assert!(error.contains("Conflicting types in column 'a.b'"), "{}", error);
+}
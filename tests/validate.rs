@@ -0,0 +1,117 @@
+use nu_plugin_from_parquet::to_parquet::{to_parquet_bytes, ToParquetOpts};
+use nu_plugin_from_parquet::validate::parquet_validate;
+use nu_protocol::{TaggedDictBuilder, UntaggedValue, Value};
+use nu_source::Tag;
+use parquet_format::{FileMetaData, PageHeader};
+use std::io::Cursor;
+use thrift::protocol::{TCompactInputProtocol, TCompactOutputProtocol};
+
+/// A file of one row group with a single page in its only column chunk, `a`.
+fn single_page() -> Vec<u8> {
+    let rows: Vec<Value> = (0..3)
+        .map(|i| TaggedDictBuilder::build(Tag::unknown(), |dict| dict.insert_untagged("a", UntaggedValue::int(i))))
+        .collect();
+    let opts = ToParquetOpts { dictionary: false, ..ToParquetOpts::default() };
+    to_parquet_bytes(&rows, Tag::unknown(), &opts).unwrap()
+}
+
+fn split_footer(bytes: &[u8]) -> (usize, FileMetaData) {
+    let mut footer_len = [0u8; 4];
+    footer_len.copy_from_slice(&bytes[bytes.len() - 8..bytes.len() - 4]);
+    let footer_start = bytes.len() - 8 - u32::from_le_bytes(footer_len) as usize;
+    let footer = FileMetaData::read_from_in_protocol(&mut TCompactInputProtocol::new(&bytes[footer_start..bytes.len() - 8])).unwrap();
+    (footer_start, footer)
+}
+
+fn with_footer(mut data: Vec<u8>, footer: &FileMetaData) -> Vec<u8> {
+    let mut written = Vec::new();
+    footer.write_to_out_protocol(&mut TCompactOutputProtocol::new(&mut written)).unwrap();
+    data.extend_from_slice(&written);
+    data.extend_from_slice(&(written.len() as u32).to_le_bytes());
+    data.extend_from_slice(b"PAR1");
+    data
+}
+
+/// Writes the page header of `single_page` again with the CRC `crc` gives for the
+/// checksum of the page data. The header grows, so the chunk size and the offset of
+/// the column metadata after it grow too.
+fn with_crc(crc: impl Fn(u32) -> u32) -> Vec<u8> {
+    let bytes = single_page();
+    let (footer_start, mut footer) = split_footer(&bytes);
+    let column = &mut footer.row_groups[0].columns[0];
+    let chunk = column.meta_data.as_mut().unwrap();
+    let (start, end) = (chunk.data_page_offset as usize, (chunk.data_page_offset + chunk.total_compressed_size) as usize);
+    let mut input = Cursor::new(&bytes[start..end]);
+    let mut header = PageHeader::read_from_in_protocol(&mut TCompactInputProtocol::new(&mut input)).unwrap();
+    let page = &bytes[start + input.position() as usize..end];
+    assert_eq!(page.len(), header.compressed_page_size as usize, "expected a single page");
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(page);
+    header.crc = Some(crc(hasher.finalize()) as i32);
+    let mut data = bytes[..start].to_vec();
+    header.write_to_out_protocol(&mut TCompactOutputProtocol::new(&mut data)).unwrap();
+    data.extend_from_slice(page);
+    let grown = (data.len() - end) as i64;
+    data.extend_from_slice(&bytes[end..footer_start]);
+    chunk.total_compressed_size += grown;
+    column.file_offset += grown;
+    with_footer(data, &footer)
+}
+
+/// The `valid` flag, errors and warnings of a report.
+fn report(bytes: Vec<u8>, crcs: bool) -> (bool, Vec<String>, Vec<String>) {
+    let report = match parquet_validate(bytes, Tag::unknown(), crcs).value {
+        UntaggedValue::Row(dict) => dict,
+        other => panic!("expected a record, got {:?}", other),
+    };
+    let strings = |name: &str| report.entries[name].table_entries().map(|v| v.as_string().unwrap()).collect();
+    let valid = report.entries["valid"].value == UntaggedValue::boolean(true);
+    (valid, strings("errors"), strings("warnings"))
+}
+
+#[test]
+fn passes_a_valid_file() {
+    let bytes = single_page();
+
+    assert_eq!(report(bytes.clone(), false), (true, vec![], vec![]));
+    assert_eq!(report(bytes, true), (true, vec![], vec!["no page has a CRC, there is nothing to check".to_string()]));
+    assert_eq!(report(with_crc(|crc| crc), true), (true, vec![], vec![]));
+}
+
+#[test]
+fn reports_a_page_failing_its_crc() {
+    let bytes = with_crc(|crc| crc ^ 1);
+
+    assert_eq!(report(bytes.clone(), false), (true, vec![], vec![]));
+    assert_eq!(report(bytes, true), (false, vec!["row group 0 column 'a' page 0 fails its CRC check".to_string()], vec![]));
+}
+
+#[test]
+fn reports_a_chunk_past_the_end_of_the_data() {
+    let bytes = single_page();
+    let (footer_start, mut footer) = split_footer(&bytes);
+    footer.row_groups[0].columns[0].meta_data.as_mut().unwrap().total_compressed_size += 100;
+    let bytes = with_footer(bytes[..footer_start].to_vec(), &footer);
+
+    let (valid, errors, _) = report(bytes, true);
+    assert!(!valid);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].starts_with("row group 0 column 'a' spans bytes 4 to "), "{}", errors[0]);
+    assert!(errors[0].ends_with(&format!("outside the data section 4 to {}", footer_start)), "{}", errors[0]);
+}
+
+#[test]
+fn reports_broken_framing() {
+    let bytes = single_page();
+    let mut no_magic = bytes.clone();
+    no_magic[0] = b'X';
+    let mut long_footer = bytes[..bytes.len() - 8].to_vec();
+    long_footer.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    long_footer.extend_from_slice(b"PAR1");
+
+    assert_eq!(report(b"PAR1".to_vec(), false).1, vec!["file is 4 bytes, too short for a Parquet file".to_string()]);
+    assert_eq!(report(no_magic, false).1, vec!["file does not start with the PAR1 magic".to_string()]);
+    assert_eq!(report(bytes[..bytes.len() - 1].to_vec(), false).1, vec!["file does not end with the PAR1 magic".to_string()]);
+    assert_eq!(report(long_footer, false).1, vec![format!("footer length {} is larger than the file", bytes.len())]);
+}